@@ -6,10 +6,10 @@ use visqol_rs::{
     *,
 };
 fn main() -> Result<(), Box<dyn Error>> {
-    let path_to_reference_file = "./test_data/clean_speech/reference_signal.wav";
-    let path_to_degraded_file = "./test_data/clean_speech/degraded_signal.wav";
+    let path_to_reference_file = "./test_data/clean_speech/reference_signal_16k.wav";
+    let path_to_degraded_file = "./test_data/clean_speech/degraded_signal_16k.wav";
 
-    let mut visqol = visqol_manager::VisqolManager::<NUM_BANDS_SPEECH>::new(
+    let visqol = visqol_manager::VisqolManager::<NUM_BANDS_SPEECH>::new(
         Variant::Wideband {
             use_unscaled_mos_mapping: false,
         },
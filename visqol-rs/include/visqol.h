@@ -0,0 +1,114 @@
+#ifndef VISQOL_H
+#define VISQOL_H
+
+/* This file is generated by cbindgen from src/capi.rs. Do not edit it manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Status codes returned by the C API. Every `VisqolError` is mapped to one of them.
+ */
+typedef enum VisqolStatus {
+  VISQOL_STATUS_OK = 0,
+  /**
+   * A required pointer argument was null.
+   */
+  VISQOL_STATUS_NULL_POINTER = 1,
+  /**
+   * A path was not valid UTF-8.
+   */
+  VISQOL_STATUS_INVALID_STRING = 2,
+  /**
+   * The manager is configured with invalid spectrogram, frequency or downmix settings, or an invalid model.
+   */
+  VISQOL_STATUS_INVALID_CONFIGURATION = 3,
+  VISQOL_STATUS_FILE_NOT_FOUND = 4,
+  /**
+   * A file could not be decoded, e.g. because of an unsupported format or bit depth.
+   */
+  VISQOL_STATUS_DECODE_FAILED = 5,
+  VISQOL_STATUS_IO = 6,
+  /**
+   * The signals have different or unsupported sample rates or channel counts, or hold non-finite samples.
+   */
+  VISQOL_STATUS_UNSUPPORTED_SIGNAL = 7,
+  /**
+   * A signal is too short to be scored, or the durations of both signals differ too much.
+   */
+  VISQOL_STATUS_SIGNAL_TOO_SHORT = 8,
+  /**
+   * The signals could not be aligned or compared.
+   */
+  VISQOL_STATUS_SCORING_FAILED = 9,
+  /**
+   * An unexpected internal error occurred.
+   */
+  VISQOL_STATUS_INTERNAL_ERROR = 10,
+} VisqolStatus;
+
+/**
+ * Selects the variant of a manager created by `visqol_create`, which takes the values as `int`.
+ */
+typedef enum VisqolVariant {
+  /**
+   * `Variant::Wideband` with the scaled MOS mapping, for speech sampled at 16 kHz.
+   */
+  VISQOL_VARIANT_SPEECH = 0,
+  /**
+   * `Variant::Fullband`, for audio sampled at 48 kHz.
+   */
+  VISQOL_VARIANT_AUDIO = 1,
+} VisqolVariant;
+
+/**
+ * Opaque handle to a manager, created by `visqol_create` and released by `visqol_destroy`.
+ */
+typedef struct VisqolHandle VisqolHandle;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a manager for `variant`, one of the `VisqolVariant` values, which searches `window_size` frames for the best matching patch.
+ * `model_path` is the path to the SVR model used by `VisqolVariant::Audio`. If it is null, the model embedded in the library is used. It is ignored for `VisqolVariant::Speech`.
+ * Returns null if the arguments are invalid or the model cannot be loaded, e.g. with `VisqolStatus::InvalidConfiguration` for an unknown `variant`.
+ * The status is written to `out_status` unless it is null. The handle must be released with `visqol_destroy`.
+ *
+ * # Safety
+ * `model_path` must be null or point to a nul-terminated string, `out_status` must be null or point to writable memory.
+ */
+struct VisqolHandle *visqol_create(int variant,
+                                   const char *model_path,
+                                   uintptr_t window_size,
+                                   enum VisqolStatus *out_status);
+
+/**
+ * Scores the degraded file in `deg_path` against the reference file in `ref_path` and writes the MOS to `out_mos`.
+ * `out_mos` is only written if `VisqolStatus::Ok` is returned.
+ *
+ * # Safety
+ * `handle` must be null or have been returned by `visqol_create` and not yet been destroyed.
+ * `ref_path` and `deg_path` must be null or point to nul-terminated strings, `out_mos` must be null or point to writable memory.
+ */
+enum VisqolStatus visqol_run(const struct VisqolHandle *handle,
+                             const char *ref_path,
+                             const char *deg_path,
+                             double *out_mos);
+
+/**
+ * Releases a manager created by `visqol_create`. Passing null does nothing.
+ *
+ * # Safety
+ * `handle` must be null or have been returned by `visqol_create` and not yet been destroyed.
+ */
+void visqol_destroy(struct VisqolHandle *handle);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* VISQOL_H */
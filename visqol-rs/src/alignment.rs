@@ -1,19 +1,40 @@
 use crate::audio_signal::AudioSignal;
+use crate::audio_utils;
 use crate::envelope;
+use crate::fast_fourier_transform;
+use crate::fft_manager::FftManager;
 use crate::xcorr;
 use ndarray::Array1;
 use ndarray::{concatenate, s, Axis};
+use num::complex::Complex64;
+use std::f64::consts::PI;
+
+/// Number of zeros appended before delaying a signal by a fraction of a sample, to keep the delay from wrapping around.
+const FRACTIONAL_DELAY_PADDING: usize = 64;
+
+/// The result of globally aligning a degraded signal to a reference signal.
+pub struct AlignmentResult {
+    /// Copy of the degraded signal, shifted to be time-aligned with the reference signal.
+    pub aligned_degraded: AudioSignal,
+    /// Delay of the degraded signal relative to the reference signal in seconds.
+    /// A negative lag means the degraded signal started late, so its beginning was truncated.
+    /// A positive lag means the degraded signal started early, so its beginning was zero-padded.
+    pub lag_seconds: f64,
+}
 
 /// Creates copy of `deg_signal` which is time-aligned to `ref_signal` by either zero-padding the beginning and truncating at the end or truncating the signal at the beginning.
 /// Returns a copy of the reference signal, a copy of the aligned degraded signal and the delay between the signals.
-pub fn align_and_truncate(
+pub(crate) fn align_and_truncate(
     ref_signal: &AudioSignal,
     deg_signal: &AudioSignal,
 ) -> Option<(AudioSignal, AudioSignal, f64)> {
-    let (aligned_deg_signal, lag) = globally_align(ref_signal, deg_signal)?;
+    let AlignmentResult {
+        aligned_degraded,
+        lag_seconds: lag,
+    } = globally_align(ref_signal, deg_signal)?;
 
     let mut new_ref_matrix = ref_signal.data_matrix.clone();
-    let mut new_deg_matrix = aligned_deg_signal.data_matrix;
+    let mut new_deg_matrix = aligned_degraded.data_matrix;
 
     match new_ref_matrix.len().cmp(&new_deg_matrix.len()) {
         std::cmp::Ordering::Less => {
@@ -42,47 +63,334 @@ pub fn align_and_truncate(
     ))
 }
 
-/// Aligns a degraded signal to the reference signal, truncating them to
-/// be the same length.
+/// Aligns a degraded signal to the reference signal by cross-correlating their upper envelopes.
+/// Lags of more than half the reference's length are ignored. Returns `None` if the envelopes cannot be computed.
 pub fn globally_align(
     ref_signal: &AudioSignal,
     deg_signal: &AudioSignal,
-) -> Option<(AudioSignal, f64)> {
+) -> Option<AlignmentResult> {
+    globally_align_within(ref_signal, deg_signal, None)
+}
+
+/// Like `globally_align`, but if `max_lag_seconds` is set, the best lag is searched among lags of at most `max_lag_seconds` in either direction instead.
+pub fn globally_align_within(
+    ref_signal: &AudioSignal,
+    deg_signal: &AudioSignal,
+    max_lag_seconds: Option<f64>,
+) -> Option<AlignmentResult> {
+    align(ref_signal, deg_signal, max_lag_seconds, false)
+}
+
+/// Like `globally_align_within`, but the lag is estimated to a fraction of a sample by interpolating the cross-correlation peak.
+/// The degraded signal is shifted by the integer part of the lag and then delayed by the remaining fraction of a sample using a band-limited delay.
+pub fn globally_align_subsample(
+    ref_signal: &AudioSignal,
+    deg_signal: &AudioSignal,
+    max_lag_seconds: Option<f64>,
+) -> Option<AlignmentResult> {
+    align(ref_signal, deg_signal, max_lag_seconds, true)
+}
+
+/// Like `globally_align_within`, or `globally_align_subsample` if `subsample` is set, but the leading samples of both signals below `threshold_db` dB
+/// relative to full scale are skipped while searching the lag. Long silent lead-ins correlate weakly and would otherwise take up the lags within `max_lag_seconds`,
+/// which then bounds the lag between the signals after their lead-ins. The returned lag and the shifted degraded signal refer to the complete signals.
+/// If either signal is completely silent, nothing is skipped.
+pub fn globally_align_skipping_silence(
+    ref_signal: &AudioSignal,
+    deg_signal: &AudioSignal,
+    max_lag_seconds: Option<f64>,
+    subsample: bool,
+    threshold_db: f64,
+) -> Option<AlignmentResult> {
+    let (ref_leading, _) = audio_utils::find_silence(ref_signal, threshold_db);
+    let (deg_leading, _) = audio_utils::find_silence(deg_signal, threshold_db);
+    if ref_leading == ref_signal.len() || deg_leading == deg_signal.len() {
+        return align(ref_signal, deg_signal, max_lag_seconds, subsample);
+    }
+
+    let skip_leading = |signal: &AudioSignal, leading: usize| AudioSignal {
+        data_matrix: signal.data_matrix.slice(s![leading..]).to_owned(),
+        sample_rate: signal.sample_rate,
+    };
+    let lag = find_lag(
+        &skip_leading(ref_signal, ref_leading),
+        &skip_leading(deg_signal, deg_leading),
+        max_lag_seconds,
+        subsample,
+    )?;
+    // The lag between the lead-ins is added back, so that it is relative to the complete signals.
+    shift_degraded(deg_signal, lag + ref_leading as f64 - deg_leading as f64)
+}
+
+/// Locates `deg_signal` within the longer `ref_signal`, e.g. a clip cut from it, by correlating their envelopes like `globally_align_within`.
+/// Returns the sample of `ref_signal` at which the best matching region starts, at most the difference of their lengths.
+pub fn find_subregion_start(ref_signal: &AudioSignal, deg_signal: &AudioSignal) -> Option<usize> {
+    let max_start = ref_signal.len().saturating_sub(deg_signal.len());
+    let max_lag_seconds = max_start as f64 / ref_signal.sample_rate as f64;
+    let lag = find_lag(ref_signal, deg_signal, Some(max_lag_seconds), false)?;
+    Some((lag.max(0.0) as usize).min(max_start))
+}
+
+fn align(
+    ref_signal: &AudioSignal,
+    deg_signal: &AudioSignal,
+    max_lag_seconds: Option<f64>,
+    subsample: bool,
+) -> Option<AlignmentResult> {
+    let lag = find_lag(ref_signal, deg_signal, max_lag_seconds, subsample)?;
+    shift_degraded(deg_signal, lag)
+}
+
+/// Returns the lag in samples which aligns `deg_signal` to `ref_signal`, see `align_degraded`, or 0 if the best lag exceeds the valid lags.
+fn find_lag(
+    ref_signal: &AudioSignal,
+    deg_signal: &AudioSignal,
+    max_lag_seconds: Option<f64>,
+    subsample: bool,
+) -> Option<f64> {
     let ref_upper_env = envelope::calculate_upper_env(&ref_signal.data_matrix)?;
     let deg_upper_env = envelope::calculate_upper_env(&deg_signal.data_matrix)?;
 
-    let best_lag = xcorr::calculate_best_lag(ref_upper_env.as_slice()?, deg_upper_env.as_slice()?)?;
+    let max_lag = max_lag_seconds
+        .map(|max_lag_seconds| (max_lag_seconds * ref_signal.sample_rate as f64) as usize);
+    let (ref_upper_env, deg_upper_env) = (ref_upper_env.as_slice()?, deg_upper_env.as_slice()?);
+    let best_lag = if subsample {
+        xcorr::calculate_best_subsample_lag(ref_upper_env, deg_upper_env, max_lag)?
+    } else {
+        xcorr::calculate_best_lag(ref_upper_env, deg_upper_env, max_lag)? as f64
+    };
+
+    let max_valid_lag = max_lag.unwrap_or(ref_signal.data_matrix.len() / 2) as f64;
+    if best_lag.abs() > max_valid_lag {
+        return Some(0.0);
+    }
+    Some(best_lag)
+}
 
-    if best_lag == 0 || best_lag.abs() > (ref_signal.data_matrix.len() / 2) as i64 {
+/// Shifts `deg_signal` by `best_lag` samples, zero-padding its beginning for a positive lag and truncating it for a negative one.
+fn shift_degraded(deg_signal: &AudioSignal, best_lag: f64) -> Option<AlignmentResult> {
+    if best_lag == 0.0 {
         // If signals are correlated already, return deg signal and 0.
         let new_deg_signal =
             AudioSignal::new(deg_signal.data_matrix.as_slice()?, deg_signal.sample_rate);
 
-        Some((new_deg_signal, 0.0f64))
+        Some(AlignmentResult {
+            aligned_degraded: new_deg_signal,
+            lag_seconds: 0.0,
+        })
     } else {
+        let integer_lag = best_lag.round() as i64;
         let mut new_deg_matrix = deg_signal.data_matrix.clone();
         // align degraded matrix
-        if best_lag < 0 {
+        if integer_lag < 0 {
             new_deg_matrix = new_deg_matrix
                 .slice(s![
-                    best_lag.unsigned_abs() as usize..deg_signal.data_matrix.len()
+                    integer_lag.unsigned_abs() as usize..deg_signal.data_matrix.len()
                 ])
                 .to_owned();
         } else {
-            let zeros = Array1::<f64>::zeros(best_lag as usize);
+            let zeros = Array1::<f64>::zeros(integer_lag as usize);
             new_deg_matrix = concatenate(Axis(0), &[zeros.view(), new_deg_matrix.view()])
                 .expect("Failed to zero pad degraded matrix!");
         }
 
+        let fractional_lag = best_lag - integer_lag as f64;
+        if fractional_lag != 0.0 {
+            new_deg_matrix = Array1::from_vec(fractional_delay(
+                new_deg_matrix
+                    .as_slice()
+                    .expect("Failed to convert degraded matrix to slice!"),
+                fractional_lag,
+            ));
+        }
+
         let new_deg_signal = AudioSignal::new(
             new_deg_matrix
                 .as_slice()
                 .expect("Failed to create AudioSignal from slice!"),
             deg_signal.sample_rate,
         );
-        Some((
-            new_deg_signal,
-            (best_lag as f64 / deg_signal.sample_rate as f64),
-        ))
+        Some(AlignmentResult {
+            aligned_degraded: new_deg_signal,
+            lag_seconds: best_lag / deg_signal.sample_rate as f64,
+        })
+    }
+}
+
+/// Delays `signal` by `delay` samples, which may be a fraction of a sample, by applying a linear phase shift in the frequency domain.
+/// The signal is zero-padded before the transform, so the shifted samples do not wrap around.
+fn fractional_delay(signal: &[f64], delay: f64) -> Vec<f64> {
+    let mut fft_manager = FftManager::new(signal.len() + FRACTIONAL_DELAY_PADDING);
+    let fft_size = fft_manager.fft_size;
+    let mut spectrum = fast_fourier_transform::forward_1d_from_points(
+        &mut fft_manager,
+        signal,
+        fft_size,
+    );
+
+    for (bin, value) in spectrum.iter_mut().enumerate() {
+        let frequency = if bin < fft_size / 2 {
+            bin as f64
+        } else {
+            bin as f64 - fft_size as f64
+        };
+        let phase = -2.0 * PI * frequency * delay / fft_size as f64;
+        *value *= Complex64::from_polar(1.0, phase);
+    }
+
+    let mut delayed = fast_fourier_transform::inverse_1d_conj_sym(&mut fft_manager, &spectrum);
+    delayed.truncate(signal.len());
+    delayed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_utils;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn late_degraded_signal_is_truncated_with_negative_lag() {
+        let ref_signal =
+            audio_utils::load_as_mono("test_data/clean_speech/reference_signal_16k.wav").unwrap();
+        let delayed = concatenate(
+            Axis(0),
+            &[Array1::zeros(1600).view(), ref_signal.data_matrix.view()],
+        )
+        .unwrap();
+        let deg_signal = AudioSignal::new(delayed.as_slice().unwrap(), ref_signal.sample_rate);
+
+        let result = globally_align(&ref_signal, &deg_signal).unwrap();
+
+        assert_abs_diff_eq!(result.lag_seconds, -0.1, epsilon = 1e-3);
+        assert_eq!(result.aligned_degraded.len(), ref_signal.len());
+    }
+
+    #[test]
+    fn early_degraded_signal_is_padded_with_positive_lag() {
+        const NUM_ADVANCED_SAMPLES: usize = 1600;
+        let ref_signal =
+            audio_utils::load_as_mono("test_data/clean_speech/reference_signal_16k.wav").unwrap();
+        let advanced = ref_signal.data_matrix.slice(s![NUM_ADVANCED_SAMPLES..]);
+        let deg_signal = AudioSignal::new(
+            advanced.as_standard_layout().as_slice().unwrap(),
+            ref_signal.sample_rate,
+        );
+
+        let result = globally_align(&ref_signal, &deg_signal).unwrap();
+
+        assert_abs_diff_eq!(result.lag_seconds, 0.1, epsilon = 1e-3);
+        assert_eq!(result.aligned_degraded.len(), ref_signal.len());
+        let aligned = &result.aligned_degraded.data_matrix;
+        assert!(aligned
+            .slice(s![..NUM_ADVANCED_SAMPLES])
+            .iter()
+            .all(|&sample| sample == 0.0));
+        assert_eq!(
+            aligned.slice(s![NUM_ADVANCED_SAMPLES..]),
+            ref_signal.data_matrix.slice(s![NUM_ADVANCED_SAMPLES..])
+        );
+    }
+
+    #[test]
+    fn lag_beyond_max_lag_is_not_found() {
+        let ref_signal =
+            audio_utils::load_as_mono("test_data/clean_speech/reference_signal_16k.wav").unwrap();
+        let delayed = concatenate(
+            Axis(0),
+            &[Array1::zeros(8000).view(), ref_signal.data_matrix.view()],
+        )
+        .unwrap();
+        let deg_signal = AudioSignal::new(delayed.as_slice().unwrap(), ref_signal.sample_rate);
+
+        let unlimited = globally_align_within(&ref_signal, &deg_signal, Some(1.0)).unwrap();
+        let limited = globally_align_within(&ref_signal, &deg_signal, Some(0.1)).unwrap();
+
+        assert_abs_diff_eq!(unlimited.lag_seconds, -0.5, epsilon = 1e-3);
+        assert!(limited.lag_seconds.abs() <= 0.1);
+    }
+
+    #[test]
+    fn fractional_delay_is_estimated_and_compensated() {
+        const SAMPLE_RATE: u32 = 16000;
+        const DELAY: f64 = 10.5;
+        // Tone bursts at irregular times, evaluated analytically to delay them by a fraction of a sample.
+        let bursts = |delay: f64| {
+            (0..2 * SAMPLE_RATE)
+                .map(|n| {
+                    let t = (n as f64 - delay) / SAMPLE_RATE as f64;
+                    let envelope = [0.3, 0.7, 1.25, 1.6]
+                        .iter()
+                        .map(|center| (-((t - center) / 0.02).powi(2)).exp())
+                        .sum::<f64>();
+                    envelope * (2.0 * PI * 1000.0 * t).sin()
+                })
+                .collect::<Vec<f64>>()
+        };
+        let ref_signal = AudioSignal::new(bursts(0.0), SAMPLE_RATE);
+        let deg_signal = AudioSignal::new(bursts(DELAY), SAMPLE_RATE);
+
+        let integer = globally_align_within(&ref_signal, &deg_signal, None).unwrap();
+        let subsample = globally_align_subsample(&ref_signal, &deg_signal, None).unwrap();
+
+        let integer_lag = integer.lag_seconds * SAMPLE_RATE as f64;
+        let subsample_lag = subsample.lag_seconds * SAMPLE_RATE as f64;
+        assert_eq!(integer_lag.fract(), 0.0);
+        assert_abs_diff_eq!(subsample_lag, -DELAY, epsilon = 0.1);
+
+        let residual_error = |aligned: &AudioSignal| {
+            (aligned.data_matrix.slice(s![..30000]).to_owned()
+                - ref_signal.data_matrix.slice(s![..30000]))
+            .mapv(|difference| difference.powi(2))
+            .sum()
+        };
+        assert!(
+            residual_error(&subsample.aligned_degraded) < residual_error(&integer.aligned_degraded)
+        );
+    }
+
+    #[test]
+    fn asymmetric_silent_lead_ins_are_skipped() {
+        let ref_signal =
+            audio_utils::load_as_mono("test_data/clean_speech/reference_signal_16k.wav").unwrap();
+        let lead_in = |num_samples: usize| {
+            let samples = concatenate(
+                Axis(0),
+                &[Array1::zeros(num_samples).view(), ref_signal.data_matrix.view()],
+            )
+            .unwrap();
+            AudioSignal::new(samples.as_slice().unwrap(), ref_signal.sample_rate)
+        };
+        let (short_lead_in, long_lead_in) = (lead_in(3200), lead_in(16000));
+
+        let limited = globally_align_within(&short_lead_in, &long_lead_in, Some(0.1)).unwrap();
+        let skipped = globally_align_skipping_silence(
+            &short_lead_in,
+            &long_lead_in,
+            Some(0.1),
+            false,
+            -60.0,
+        )
+        .unwrap();
+
+        assert!(limited.lag_seconds.abs() <= 0.1);
+        assert_abs_diff_eq!(skipped.lag_seconds, -0.8, epsilon = 1e-3);
+        assert_eq!(
+            skipped.aligned_degraded.data_matrix.slice(s![..short_lead_in.len()]),
+            short_lead_in.data_matrix
+        );
+    }
+
+    #[test]
+    fn clip_is_located_within_reference() {
+        let ref_signal =
+            audio_utils::load_as_mono("test_data/clean_speech/reference_signal_16k.wav").unwrap();
+        let clip = AudioSignal {
+            data_matrix: ref_signal.data_matrix.slice(s![12000..30000]).to_owned(),
+            sample_rate: ref_signal.sample_rate,
+        };
+
+        assert_eq!(find_subregion_start(&ref_signal, &clip), Some(12000));
+        assert_eq!(find_subregion_start(&clip, &ref_signal), Some(0));
     }
 }
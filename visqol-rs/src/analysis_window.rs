@@ -1,9 +1,44 @@
+use std::f64::consts::PI;
+
+/// Window function which is applied to each frame of a signal before it is passed to the gammatone filterbank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowFunction {
+    /// Leaves the frames unchanged. This is the window ViSQOL uses.
+    #[default]
+    Rectangular,
+    /// Hann window, which tapers both ends of each frame to zero.
+    Hann,
+    /// Hamming window, which tapers both ends of each frame to 0.08 and has a lower first side lobe than the Hann window.
+    Hamming,
+    /// Blackman window, which has lower side lobes than the Hann window at the cost of a wider main lobe.
+    Blackman,
+}
+
+impl WindowFunction {
+    /// Returns the `size` coefficients of the symmetric window.
+    pub fn coefficients(&self, size: usize) -> Vec<f64> {
+        let phase = |n: usize| 2.0 * PI * n as f64 / (size.max(2) - 1) as f64;
+        (0..size)
+            .map(|n| match self {
+                WindowFunction::Rectangular => 1.0,
+                WindowFunction::Hann => 0.5 - 0.5 * phase(n).cos(),
+                WindowFunction::Hamming => 0.54 - 0.46 * phase(n).cos(),
+                WindowFunction::Blackman => {
+                    0.42 - 0.5 * phase(n).cos() + 0.08 * (2.0 * phase(n)).cos()
+                }
+            })
+            .collect()
+    }
+}
+
 /// Temporal analysis window used for creating spectrograms
 pub struct AnalysisWindow {
     /// Size of the window in samples
     pub size: usize,
     /// Overlap of the window in milliseconds
     pub overlap: f64,
+    /// Window function applied to each frame
+    pub window_function: WindowFunction,
 }
 
 impl AnalysisWindow {
@@ -12,6 +47,40 @@ impl AnalysisWindow {
         Self {
             size: (sample_rate as f64 * window_duration).round() as usize,
             overlap,
+            window_function: WindowFunction::default(),
+        }
+    }
+
+    /// Returns the number of samples between the starts of two consecutive frames.
+    pub fn hop_size(&self) -> usize { (self.size as f64 * self.overlap).round() as usize }
+
+    /// Replaces the rectangular window function with `window_function`.
+    pub fn with_window_function(mut self, window_function: WindowFunction) -> Self {
+        self.window_function = window_function;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn window_functions_taper_frame_edges() {
+        let rectangular = WindowFunction::Rectangular.coefficients(9);
+        let hann = WindowFunction::Hann.coefficients(9);
+        let hamming = WindowFunction::Hamming.coefficients(9);
+        let blackman = WindowFunction::Blackman.coefficients(9);
+
+        assert_eq!(rectangular, vec![1.0; 9]);
+        for window in [&hann, &hamming, &blackman] {
+            assert_eq!(window.len(), 9);
+            assert_abs_diff_eq!(window[4], 1.0, epsilon = 1e-12);
+            assert_abs_diff_eq!(window[0], window[8], epsilon = 1e-12);
         }
+        assert_abs_diff_eq!(hann[0], 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(hamming[0], 0.08, epsilon = 1e-12);
+        assert_abs_diff_eq!(blackman[0], 0.0, epsilon = 1e-12);
     }
 }
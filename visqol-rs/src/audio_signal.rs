@@ -1,6 +1,10 @@
 use ndarray::Array1;
 
+use crate::audio_utils::{self, ResampleQuality};
+use crate::visqol_error::VisqolError;
+
 /// represents a time domain audio signal.
+#[derive(Clone)]
 pub struct AudioSignal {
     /// The actual time domain samples
     pub data_matrix: Array1<f64>,
@@ -9,10 +13,12 @@ pub struct AudioSignal {
 }
 
 impl AudioSignal {
-    /// Creates a new `AudioSignal`.
-    pub fn new(data_matrix: &[f64], sample_rate: u32) -> AudioSignal {
+    /// Creates a new `AudioSignal` holding the mono `samples` sampled at `sample_rate` Hz, e.g. synthesized data.
+    /// A `Vec<f64>` is taken over without copying, a slice is copied.
+    /// The samples are expected in the range [-1.0, 1.0] with 1.0 being full scale, like the samples of decoded files.
+    pub fn new(samples: impl Into<Vec<f64>>, sample_rate: u32) -> AudioSignal {
         AudioSignal {
-            data_matrix: Array1::from_vec(data_matrix.to_vec()),
+            data_matrix: Array1::from_vec(samples.into()),
             sample_rate,
         }
     }
@@ -24,6 +30,16 @@ impl AudioSignal {
 
     /// Returns `true` if the number o samples is 0
     pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Returns a copy of this signal resampled to `target_sample_rate` with the given `quality`, see `audio_utils::resample_sinc`.
+    /// This is independent of `VisqolManager::set_resample_to_match`, e.g. to condition signals before scoring them.
+    pub fn resample(
+        &self,
+        target_sample_rate: u32,
+        quality: ResampleQuality,
+    ) -> Result<AudioSignal, VisqolError> {
+        audio_utils::resample_sinc(self, target_sample_rate, quality)
+    }
 }
 
 impl std::ops::Index<usize> for AudioSignal {
@@ -34,3 +50,42 @@ impl std::ops::Index<usize> for AudioSignal {
 impl std::ops::IndexMut<usize> for AudioSignal {
     fn index_mut(&mut self, index: usize) -> &mut f64 { &mut (self.data_matrix[index]) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signal_is_created_from_vec_and_slice() {
+        let samples = vec![0.0, 0.5, -0.5, 1.0];
+
+        let from_slice = AudioSignal::new(samples.as_slice(), 2);
+        let from_vec = AudioSignal::new(samples, 2);
+
+        assert_eq!(from_vec.data_matrix, from_slice.data_matrix);
+        assert_eq!(from_vec.len(), 4);
+        assert_eq!(from_vec.get_duration(), 2.0);
+    }
+
+    #[test]
+    fn resampling_down_and_up_preserves_rms() {
+        let rms = |samples: &[f64]| {
+            (samples.iter().map(|sample| sample * sample).sum::<f64>() / samples.len() as f64)
+                .sqrt()
+        };
+        let signal = audio_utils::load_as_mono("test_data/CA01_01.wav").unwrap();
+
+        for quality in [ResampleQuality::Fast, ResampleQuality::Balanced, ResampleQuality::High] {
+            let downsampled = signal.resample(16000, quality).unwrap();
+            let restored = downsampled.resample(48000, quality).unwrap();
+
+            assert_eq!(downsampled.sample_rate, 16000);
+            assert_eq!(restored.sample_rate, 48000);
+            // Rounding up the downsampled length may add one sample.
+            assert!(restored.len().abs_diff(signal.len()) <= 1);
+            let original_rms = rms(signal.data_matrix.as_slice().unwrap());
+            let restored_rms = rms(restored.data_matrix.as_slice().unwrap());
+            assert!((restored_rms - original_rms).abs() < 0.05 * original_rms);
+        }
+    }
+}
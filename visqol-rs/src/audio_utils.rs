@@ -1,19 +1,242 @@
-use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::Path;
 
 use crate::audio_signal::AudioSignal;
+use crate::constants;
+#[cfg(feature = "flac")]
+use crate::flac_reader::FlacFile;
+use crate::loudness;
 use crate::math_utils;
 use crate::spectrogram::Spectrogram;
-use crate::wav_reader::WavFile;
-use ndarray::{Array1, Array2, Axis, ShapeBuilder};
+#[cfg(any(feature = "mp3", feature = "symphonia"))]
+use crate::symphonia_reader::SymphoniaFile;
+use crate::visqol_error::VisqolError;
+use crate::wav_reader::{self, WavChunkReader, WavFile};
+use crate::weighting;
+use ndarray::{s, Array1, Array2, Axis, ShapeBuilder};
 use num::complex::Complex64;
 use num_traits::Zero;
+use rubato::{
+    FftFixedIn, Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType,
+    WindowFunction,
+};
 
 // Constants
 const SPL_REFERENCE_POINT: f64 = 0.00002;
 const NOISE_FLOOR_RELATIVE_TO_PEAK_DB: f64 = 45.0;
 const NOISE_FLOOR_ABSOLUTE_DB: f64 = -45.0;
+const NUM_MAGIC_BYTES: usize = 4;
+const RESAMPLER_CHUNK_SIZE: usize = 1024;
+#[cfg(feature = "mmap")]
+const MMAP_CHUNK_FRAMES: u32 = 1 << 16;
+const RIFF_MAGIC_BYTES: &[u8; 4] = b"RIFF";
+#[cfg(feature = "flac")]
+const FLAC_MAGIC_BYTES: &[u8; 4] = b"fLaC";
+#[cfg(feature = "mp3")]
+const ID3_MAGIC_BYTES: &[u8; 3] = b"ID3";
+
+/// Describes the format of audio data passed to `load_as_mono_from_reader`.
+/// Apart from wav, each format is only available with its corresponding cargo feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormatHint {
+    /// Detects the format from the leading magic bytes of the data.
+    Detect,
+    /// RIFF wave data
+    Wav,
+    /// Native flac data
+    #[cfg(feature = "flac")]
+    Flac,
+    /// MPEG-1/2 Layer III data
+    #[cfg(any(feature = "mp3", feature = "symphonia"))]
+    Mp3,
+}
+
+/// Encodes a single sample of raw PCM data read by `load_raw_pcm`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RawSampleFormat {
+    /// 16 bit signed integers, little endian
+    I16Le,
+    /// 16 bit signed integers, big endian
+    I16Be,
+    /// 24 bit signed integers packed into 3 bytes, little endian
+    I24Le,
+    /// 24 bit signed integers packed into 3 bytes, big endian
+    I24Be,
+    /// 32 bit floats in the range [-1.0, 1.0], little endian
+    F32Le,
+    /// 32 bit floats in the range [-1.0, 1.0], big endian
+    F32Be,
+}
+
+impl RawSampleFormat {
+    /// Returns the number of bytes of a single sample.
+    pub fn bytes_per_sample(self) -> usize {
+        match self {
+            Self::I16Le | Self::I16Be => 2,
+            Self::I24Le | Self::I24Be => 3,
+            Self::F32Le | Self::F32Be => 4,
+        }
+    }
+}
+
+/// Describes headerless, interleaved PCM data passed to `load_raw_pcm`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawPcmFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: RawSampleFormat,
+}
+
+/// Describes how the channels of a multichannel signal are combined into a mono signal.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DownmixConfig {
+    /// Gain applied to each channel before summing to mono, in channel order.
+    /// If empty, all channels are averaged with equal weights.
+    pub weights: Vec<f64>,
+}
+
+/// Trades the accuracy of `resample_sinc` against its speed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResampleQuality {
+    /// Short sinc filters with linear interpolation between them.
+    Fast,
+    /// Medium length sinc filters with cubic interpolation between them.
+    #[default]
+    Balanced,
+    /// Long sinc filters with cubic interpolation between them and a cutoff close to the Nyquist frequency.
+    High,
+}
+
+impl ResampleQuality {
+    /// Returns the parameters of the sinc interpolation for this quality.
+    fn sinc_parameters(self) -> SincInterpolationParameters {
+        let (sinc_len, f_cutoff, interpolation) = match self {
+            Self::Fast => (64, 0.9, SincInterpolationType::Linear),
+            Self::Balanced => (128, 0.925, SincInterpolationType::Cubic),
+            Self::High => (256, 0.95, SincInterpolationType::Cubic),
+        };
+        SincInterpolationParameters {
+            sinc_len,
+            f_cutoff,
+            oversampling_factor: 128,
+            interpolation,
+            window: WindowFunction::BlackmanHarris2,
+        }
+    }
+}
+
+/// Selects the samples written by `save_wav_with`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WavSampleFormat {
+    /// 16 bit integers, samples exceeding full scale are clipped.
+    #[default]
+    Int16,
+    /// 32 bit floats, which preserve samples exceeding full scale.
+    Float32,
+}
+
+/// Describes the level which `normalize_loudness` scales a signal to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NormalizationTarget {
+    /// Scales the signal to an RMS level of `target_dbfs` dB relative to full scale, i.e. an RMS of 1.0.
+    Rms { target_dbfs: f64 },
+    /// Scales the signal to an integrated loudness of `constants::EBU_R128_TARGET_LUFS` (-23 LUFS), measured according to ITU-R BS.1770-4.
+    EbuR128,
+}
+
+/// Frequency weighting curves of IEC 61672-1 which `apply_weighting` filters a signal with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeightingCurve {
+    /// Approximates the sensitivity of hearing at low levels, strongly attenuating low and very high frequencies.
+    A,
+    /// Approximates the sensitivity of hearing at high levels, with a nearly flat response between 31.5 Hz and 8 kHz.
+    C,
+}
+
+/// Format of an audio file as read from its header by `probe`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AudioInfo {
+    /// The sample rate in Hz
+    pub sample_rate: u32,
+    /// The number of channels
+    pub channels: u16,
+    /// The number of bits per sample, `None` for compressed codecs such as mp3 which have no fixed bit depth.
+    pub bit_depth: Option<u16>,
+    /// The duration in seconds, `None` if the header does not store the number of frames, e.g. for mp3 files without a Xing header.
+    pub duration: Option<f64>,
+}
+
+/// Decoders used by `load_as_mono` and `load_as_mono_from_reader`.
+enum AudioFormat {
+    Wav,
+    #[cfg(feature = "flac")]
+    Flac,
+    #[cfg(any(feature = "mp3", feature = "symphonia"))]
+    Symphonia { extension: Option<String> },
+}
+
+impl AudioFormat {
+    /// Determines the format of audio data by its `magic_bytes`, falling back to its file `extension` if available.
+    /// With the `symphonia` feature, all data which is not wav data is probed by `symphonia`.
+    fn detect(magic_bytes: &[u8], extension: Option<&str>) -> Self {
+        let has_extension = |expected: &str| {
+            extension.is_some_and(|extension| extension.eq_ignore_ascii_case(expected))
+        };
+
+        if magic_bytes.starts_with(RIFF_MAGIC_BYTES) || has_extension("wav") {
+            return Self::Wav;
+        }
+
+        #[cfg(feature = "flac")]
+        if magic_bytes.starts_with(FLAC_MAGIC_BYTES) || has_extension("flac") {
+            return Self::Flac;
+        }
+
+        #[cfg(feature = "mp3")]
+        {
+            // An mp3 file starts either with an ID3 tag or directly with the sync word of the first frame.
+            let is_mp3_frame =
+                magic_bytes.len() >= 2 && magic_bytes[0] == 0xFF && magic_bytes[1] & 0xE0 == 0xE0;
+            if magic_bytes.starts_with(ID3_MAGIC_BYTES) || is_mp3_frame || has_extension("mp3") {
+                return Self::Symphonia {
+                    extension: Some(String::from("mp3")),
+                };
+            }
+        }
+
+        Self::fallback(extension)
+    }
+
+    /// All unknown data is probed by `symphonia`.
+    #[cfg(feature = "symphonia")]
+    fn fallback(extension: Option<&str>) -> Self {
+        Self::Symphonia {
+            extension: extension.map(String::from),
+        }
+    }
+
+    /// All unknown data is assumed to be wav data, leaving its validation to the wav decoder.
+    #[cfg(not(feature = "symphonia"))]
+    fn fallback(_extension: Option<&str>) -> Self { Self::Wav }
+
+    /// Reads the leading magic bytes of `reader` and rewinds it to its previous position.
+    fn read_magic_bytes<R: Read + Seek>(
+        reader: &mut R,
+    ) -> Result<Vec<u8>, VisqolError> {
+        let start_position = reader.stream_position()?;
+        let mut magic_bytes = Vec::<u8>::with_capacity(NUM_MAGIC_BYTES);
+        reader
+            .by_ref()
+            .take(NUM_MAGIC_BYTES as u64)
+            .read_to_end(&mut magic_bytes)?;
+        reader.seek(SeekFrom::Start(start_position))?;
+        Ok(magic_bytes)
+    }
+}
 
 /// Returns a copy of `degraded` which has the same SPL as `reference`.
+/// A silent `degraded` signal cannot be scaled and is returned unchanged.
 pub fn scale_to_match_sound_pressure_level(
     reference: &AudioSignal,
     degraded: &AudioSignal,
@@ -22,6 +245,7 @@ pub fn scale_to_match_sound_pressure_level(
     let deg_spl = calculate_sound_pressure_level(degraded);
 
     let scale_factor = 10.0f64.powf((ref_spl - deg_spl) / 20.0);
+    let scale_factor = if scale_factor.is_finite() { scale_factor } else { 1.0 };
     let scaled_mat = degraded.data_matrix.clone() * scale_factor;
     AudioSignal::new(
         scaled_mat
@@ -31,6 +255,158 @@ pub fn scale_to_match_sound_pressure_level(
     )
 }
 
+/// Returns a copy of `signal` which is resampled to `target_sample_rate`.
+/// The resampler's delay is compensated, so the output is time-aligned with the input and has the same duration.
+pub fn resample(
+    signal: &AudioSignal,
+    target_sample_rate: u32,
+) -> Result<AudioSignal, VisqolError> {
+    if signal.sample_rate == target_sample_rate {
+        return Ok(signal.clone());
+    }
+
+    let mut resampler = FftFixedIn::<f64>::new(
+        signal.sample_rate as usize,
+        target_sample_rate as usize,
+        RESAMPLER_CHUNK_SIZE,
+        1,
+        1,
+    )?;
+    run_resampler(&mut resampler, signal, target_sample_rate)
+}
+
+/// Returns a copy of `signal` which is resampled to `target_sample_rate` by a sinc interpolating resampler of the given `quality`.
+/// Like `resample`, the output is time-aligned with the input and has the same duration.
+pub fn resample_sinc(
+    signal: &AudioSignal,
+    target_sample_rate: u32,
+    quality: ResampleQuality,
+) -> Result<AudioSignal, VisqolError> {
+    if signal.sample_rate == target_sample_rate {
+        return Ok(signal.clone());
+    }
+
+    let mut resampler = SincFixedIn::<f64>::new(
+        target_sample_rate as f64 / signal.sample_rate as f64,
+        1.0,
+        quality.sinc_parameters(),
+        RESAMPLER_CHUNK_SIZE,
+        1,
+    )?;
+    run_resampler(&mut resampler, signal, target_sample_rate)
+}
+
+/// Feeds `signal` through `resampler`, which converts it to `target_sample_rate`, and compensates the resampler's delay.
+fn run_resampler(
+    resampler: &mut impl Resampler<f64>,
+    signal: &AudioSignal,
+    target_sample_rate: u32,
+) -> Result<AudioSignal, VisqolError> {
+    let input = signal.data_matrix.to_vec();
+    let delay = resampler.output_delay();
+    let expected_len = (input.len() as u64 * target_sample_rate as u64)
+        .div_ceil(signal.sample_rate as u64) as usize;
+    let mut output = Vec::<f64>::with_capacity(expected_len + delay);
+
+    let mut chunks = input.chunks_exact(resampler.input_frames_next());
+    for chunk in chunks.by_ref() {
+        output.extend_from_slice(&resampler.process(&[chunk], None)?[0]);
+    }
+    let remainder = chunks.remainder();
+    if !remainder.is_empty() {
+        output.extend_from_slice(&resampler.process_partial(Some(&[remainder]), None)?[0]);
+    }
+    // Flush the samples still held back by the resampler.
+    while output.len() < expected_len + delay {
+        output.extend_from_slice(&resampler.process_partial::<&[f64]>(None, None)?[0]);
+    }
+
+    output.drain(..delay);
+    output.truncate(expected_len);
+    Ok(AudioSignal::new(output, target_sample_rate))
+}
+
+/// Scales `signal` in place so that its level matches `target`.
+/// Signals whose level cannot be measured, e.g. digital silence or signals shorter than 400 ms for `NormalizationTarget::EbuR128`, are left unchanged.
+pub fn normalize_loudness(signal: &mut AudioSignal, target: NormalizationTarget) {
+    let gain_db = match target {
+        NormalizationTarget::Rms { target_dbfs } => {
+            let energy = signal.data_matrix.iter().map(|sample| sample.powi(2)).sum::<f64>();
+            if energy == 0.0 {
+                return;
+            }
+            let rms_dbfs = 10.0 * (energy / signal.len() as f64).log10();
+            target_dbfs - rms_dbfs
+        }
+        NormalizationTarget::EbuR128 => match loudness::integrated_loudness(signal) {
+            Some(loudness) => constants::EBU_R128_TARGET_LUFS - loudness,
+            None => return,
+        },
+    };
+    signal.data_matrix *= 10.0f64.powf(gain_db / 20.0);
+}
+
+/// Filters `signal` in place with the weighting `curve`, so that it emphasizes the frequencies which dominate perceived loudness.
+/// The gain is 0 dB at 1 kHz. The digital filter follows the standardized curve closely up to a few kHz and rolls off towards the Nyquist frequency.
+pub fn apply_weighting(signal: &mut AudioSignal, curve: WeightingCurve) {
+    let samples = signal.data_matrix.to_vec();
+    signal.data_matrix =
+        Array1::from(weighting::apply_weighting(&samples, curve, signal.sample_rate));
+}
+
+/// Removes the leading and trailing samples of `signal` whose magnitude is below `threshold_db` dB relative to full scale.
+/// Returns the number of samples removed at the start and at the end of the signal.
+pub fn trim_silence(signal: &mut AudioSignal, threshold_db: f64) -> (usize, usize) {
+    let (leading, trailing) = find_silence(signal, threshold_db);
+    signal.data_matrix = signal
+        .data_matrix
+        .slice(s![leading..signal.len() - trailing])
+        .to_owned();
+    (leading, trailing)
+}
+
+/// Returns `true` if no sample of `signal` reaches `constants::SILENCE_LEVEL_DB`, e.g. for digital silence.
+pub fn is_silent(signal: &AudioSignal) -> bool {
+    find_silence(signal, constants::SILENCE_LEVEL_DB).0 == signal.len()
+}
+
+/// Returns the DC offset of `signal`, i.e. the mean of its samples, or 0 for an empty signal.
+pub fn dc_offset(signal: &AudioSignal) -> f64 { signal.data_matrix.mean().unwrap_or(0.0) }
+
+/// Subtracts the DC offset of `signal` from each of its samples, see `dc_offset`.
+/// A constant bias, e.g. added by a capture device, raises the energy of the lowest band and thereby shifts the spectrogram.
+pub fn remove_dc_offset(signal: &mut AudioSignal) {
+    let offset = dc_offset(signal);
+    signal.data_matrix -= offset;
+}
+
+/// Returns the fraction of the samples of `signal` whose magnitude reaches full scale, see `constants::CLIPPING_LEVEL`, or 0 for an empty signal.
+pub fn clipped_fraction(signal: &AudioSignal) -> f64 {
+    if signal.is_empty() {
+        return 0.0;
+    }
+    let is_clipped = |sample: &&f64| sample.abs() >= constants::CLIPPING_LEVEL;
+    signal.data_matrix.iter().filter(is_clipped).count() as f64 / signal.len() as f64
+}
+
+/// Counts the leading and trailing samples of `signal` whose magnitude is below `threshold_db` dB relative to full scale.
+/// All samples of a completely silent signal are counted as leading silence.
+pub fn find_silence(signal: &AudioSignal, threshold_db: f64) -> (usize, usize) {
+    let threshold = 10.0f64.powf(threshold_db / 20.0);
+    let is_audible = |sample: &f64| sample.abs() >= threshold;
+    match signal.data_matrix.iter().position(is_audible) {
+        Some(first_audible) => {
+            let last_audible = signal
+                .data_matrix
+                .iter()
+                .rposition(is_audible)
+                .unwrap_or(first_audible);
+            (first_audible, signal.len() - 1 - last_audible)
+        }
+        None => (signal.len(), 0),
+    }
+}
+
 /// Computes the sound pressure level of an audio signal in dB
 fn calculate_sound_pressure_level(signal: &AudioSignal) -> f64 {
     let energy: f64 = signal
@@ -45,30 +421,344 @@ fn calculate_sound_pressure_level(signal: &AudioSignal) -> f64 {
 /// Calculates the per-column sum of a 2d array and returns them as a 1d array
 fn to_mono_matrix(sample_matrix: &Array2<f64>) -> Array1<f64> { sample_matrix.sum_axis(Axis(1)) }
 
-/// Given a `file_path` to a wav file on disk, this file is loaded. If there are multiple channels, these are summed and normalized to 1 mono channel.
-pub fn load_as_mono(file_path: &str) -> Result<AudioSignal, Box<dyn Error>> {
-    let wav_reader = WavFile::open(file_path)?;
+/// Combines the channels of `sample_matrix` to a mono signal using the weights in `downmix`.
+fn downmix_to_mono(
+    sample_matrix: &Array2<f64>,
+    downmix: &DownmixConfig,
+) -> Result<Array1<f64>, VisqolError> {
+    let num_channels = sample_matrix.ncols();
+    if downmix.weights.is_empty() {
+        return Ok(to_mono_matrix(sample_matrix) / num_channels as f64);
+    }
+    if downmix.weights.len() != num_channels {
+        return Err(VisqolError::InvalidDownmixWeights {
+            num_weights: downmix.weights.len(),
+            num_channels,
+        });
+    }
+    let weights = Array1::from_vec(downmix.weights.clone());
+    Ok(to_mono_matrix(&(sample_matrix * &weights)))
+}
+
+/// Given a `file_path` to an audio file on disk, this file is loaded. If there are multiple channels, these are summed and normalized to 1 mono channel.
+/// The format is detected from the file's magic bytes or its extension. Wav files are always supported, flac and mp3 files require the `flac` and `mp3` features.
+/// With the `symphonia` feature, any format supported by `symphonia` can be loaded.
+pub fn load_as_mono(file_path: &str) -> Result<AudioSignal, VisqolError> {
+    load_as_mono_with(file_path, &DownmixConfig::default())
+}
+
+/// Like `load_as_mono`, but multiple channels are combined according to `downmix`.
+/// With the `mmap` feature, wav files are memory-mapped and decoded in chunks, see `load_mapped_wav_as_mono`.
+pub fn load_as_mono_with(
+    file_path: &str,
+    downmix: &DownmixConfig,
+) -> Result<AudioSignal, VisqolError> {
+    #[cfg(feature = "mmap")]
+    if let Some(signal) = load_mapped_wav_as_mono(file_path, downmix) {
+        return signal;
+    }
+
+    let (sample_matrix, sample_rate) = decode_file(file_path)?;
+
+    Ok(AudioSignal {
+        data_matrix: downmix_to_mono(&sample_matrix, downmix)?,
+        sample_rate,
+    })
+}
+
+/// Given a `file_path` to an audio file on disk, this file is loaded and each channel is returned as a separate signal, in channel order.
+/// The format is detected in the same way as in `load_as_mono`.
+pub fn load_channels(file_path: &str) -> Result<Vec<AudioSignal>, VisqolError> {
+    let (sample_matrix, sample_rate) = decode_file(file_path)?;
+    Ok(sample_matrix
+        .columns()
+        .into_iter()
+        .map(|channel| AudioSignal {
+            data_matrix: channel.to_owned(),
+            sample_rate,
+        })
+        .collect())
+}
 
-    let data_vector_float = math_utils::normalize_int16_to_double(&wav_reader.samples);
-    let final_signal = extract_multichannel(wav_reader.num_channels as usize, &data_vector_float);
+/// Writes `signal` to a mono 16 bit wav file at `file_path` at the signal's sample rate, e.g. to listen to the signals returned by `VisqolManager::scored_signals`.
+pub fn save_wav(signal: &AudioSignal, file_path: &str) -> Result<(), VisqolError> {
+    save_wav_with(signal, file_path, WavSampleFormat::default())
+}
 
-    let final_signal = to_mono_matrix(&final_signal);
+/// Like `save_wav`, but the samples are written in `sample_format`.
+pub fn save_wav_with(
+    signal: &AudioSignal,
+    file_path: &str,
+    sample_format: WavSampleFormat,
+) -> Result<(), VisqolError> {
+    let samples = signal.data_matrix.to_vec();
+    let float = sample_format == WavSampleFormat::Float32;
+    wav_reader::write_mono(file_path, &samples, signal.sample_rate, float)
+}
+
+/// Opens the wav file at `file_path` for reading it in chunks with `read_mono_chunk`, without decoding the whole file.
+/// Failures are reported as `VisqolError::FileNotFound` or `VisqolError::DecodeFailed`, like in `load_as_mono`.
+pub fn open_wav_chunks(file_path: &str) -> Result<WavChunkReader<BufReader<File>>, VisqolError> {
+    let decode_failed = |source: VisqolError| VisqolError::DecodeFailed {
+        path: file_path.to_string(),
+        source: Box::new(source),
+    };
+    let file = File::open(file_path).map_err(|error| match error.kind() {
+        ErrorKind::NotFound => VisqolError::FileNotFound {
+            path: file_path.to_string(),
+        },
+        _ => decode_failed(error.into()),
+    })?;
+    WavChunkReader::new(BufReader::new(file)).map_err(decode_failed)
+}
+
+/// Reads up to `num_frames` frames starting at frame `start` from `reader` and combines its channels to a mono signal according to `downmix`.
+pub fn read_mono_chunk<R: Read + Seek>(
+    reader: &mut WavChunkReader<R>,
+    start: u32,
+    num_frames: u32,
+    downmix: &DownmixConfig,
+) -> Result<AudioSignal, VisqolError> {
+    let samples = reader.read_frames(start, num_frames)?;
+    let sample_matrix = extract_multichannel(reader.num_channels() as usize, &samples)?;
 
     Ok(AudioSignal {
-        data_matrix: final_signal / wav_reader.num_channels as f64,
-        sample_rate: wav_reader.sample_rate,
+        data_matrix: downmix_to_mono(&sample_matrix, downmix)?,
+        sample_rate: reader.sample_rate(),
     })
 }
 
+/// Memory-maps the wav file at `file_path` and decodes it in chunks of `MMAP_CHUNK_FRAMES` frames, each of which is combined to mono right away,
+/// so that the decoded samples of all channels are never held in memory at once. The samples are identical to those decoded by `decode_file`.
+/// Returns `None` if the file cannot be opened or mapped or is not a wav file, in which case it is left to `decode_file`.
+#[cfg(feature = "mmap")]
+fn load_mapped_wav_as_mono(
+    file_path: &str,
+    downmix: &DownmixConfig,
+) -> Option<Result<AudioSignal, VisqolError>> {
+    let file = File::open(file_path).ok()?;
+    // Safety: the map is only read. Like with a buffered reader, the decoded samples are garbage if the file is modified while it is decoded.
+    let mmap = unsafe { memmap2::Mmap::map(&file) }.ok()?;
+    let extension = Path::new(file_path)
+        .extension()
+        .and_then(|extension| extension.to_str());
+    let magic_bytes = &mmap[..mmap.len().min(NUM_MAGIC_BYTES)];
+    if !matches!(AudioFormat::detect(magic_bytes, extension), AudioFormat::Wav) {
+        return None;
+    }
+
+    let decode = || {
+        let mut reader = WavChunkReader::new(std::io::Cursor::new(&mmap[..]))?;
+        let mut samples = Vec::with_capacity(reader.num_frames() as usize);
+        let mut start = 0;
+        while start < reader.num_frames() {
+            let chunk = read_mono_chunk(&mut reader, start, MMAP_CHUNK_FRAMES, downmix)?;
+            samples.extend(chunk.data_matrix);
+            start = start.saturating_add(MMAP_CHUNK_FRAMES);
+        }
+        Ok(AudioSignal::new(samples, reader.sample_rate()))
+    };
+    Some(decode().map_err(|source| match source {
+        VisqolError::InvalidDownmixWeights { .. } => source,
+        source => VisqolError::DecodeFailed {
+            path: file_path.to_string(),
+            source: Box::new(source),
+        },
+    }))
+}
+
+/// Opens the file at `file_path`, detects its format from its magic bytes or its extension and decodes it.
+/// Failures are reported as `VisqolError::FileNotFound` or `VisqolError::DecodeFailed`, so the error names the offending file.
+fn decode_file(file_path: &str) -> Result<(Array2<f64>, u32), VisqolError> {
+    read_file(file_path, decode_multichannel)
+}
+
+/// Reads the sample rate, channel count, bit depth and duration of the audio file at `file_path` from its header, without decoding any samples,
+/// e.g. to check the formats of a batch before scoring it. The same formats as in `load_as_mono` are supported and detected in the same way.
+/// Failures are reported as `VisqolError::FileNotFound` or `VisqolError::DecodeFailed`, like in `load_as_mono`.
+pub fn probe(file_path: &str) -> Result<AudioInfo, VisqolError> {
+    read_file(file_path, |reader, format| match format {
+        AudioFormat::Wav => WavFile::probe(reader),
+        #[cfg(feature = "flac")]
+        AudioFormat::Flac => FlacFile::probe(reader),
+        #[cfg(any(feature = "mp3", feature = "symphonia"))]
+        AudioFormat::Symphonia { extension } => SymphoniaFile::probe(reader, extension.as_deref()),
+    })
+}
+
+/// Opens the file at `file_path`, detects its format and passes the buffered file and its format to `read`.
+/// Failures are reported as `VisqolError::FileNotFound` or `VisqolError::DecodeFailed`, so the error names the offending file.
+fn read_file<T>(
+    file_path: &str,
+    read: impl FnOnce(BufReader<File>, AudioFormat) -> Result<T, VisqolError>,
+) -> Result<T, VisqolError> {
+    let decode_failed = |source: VisqolError| VisqolError::DecodeFailed {
+        path: file_path.to_string(),
+        source: Box::new(source),
+    };
+    let file = File::open(file_path).map_err(|error| match error.kind() {
+        ErrorKind::NotFound => VisqolError::FileNotFound {
+            path: file_path.to_string(),
+        },
+        _ => decode_failed(error.into()),
+    })?;
+    let extension = Path::new(file_path)
+        .extension()
+        .and_then(|extension| extension.to_str());
+
+    let mut reader = BufReader::new(file);
+    AudioFormat::read_magic_bytes(&mut reader)
+        .and_then(|magic_bytes| read(reader, AudioFormat::detect(&magic_bytes, extension)))
+        .map_err(decode_failed)
+}
+
+/// Decodes the headerless PCM data in `bytes`, e.g. received over a socket, according to `format` and averages its interleaved channels to a mono signal.
+/// Returns `VisqolError::InvalidRawPcm` if `bytes` does not hold a whole number of frames, i.e. one sample per channel.
+pub fn load_raw_pcm(bytes: &[u8], format: RawPcmFormat) -> Result<AudioSignal, VisqolError> {
+    let bytes_per_sample = format.sample_format.bytes_per_sample();
+    let bytes_per_frame = bytes_per_sample * format.channels as usize;
+    if bytes_per_frame == 0 || !bytes.len().is_multiple_of(bytes_per_frame) {
+        return Err(VisqolError::InvalidRawPcm {
+            num_bytes: bytes.len(),
+            bytes_per_frame,
+        });
+    }
+
+    let samples = decode_raw_samples(bytes, format.sample_format);
+    let sample_matrix = extract_multichannel(format.channels as usize, &samples)?;
+
+    Ok(AudioSignal {
+        data_matrix: downmix_to_mono(&sample_matrix, &DownmixConfig::default())?,
+        sample_rate: format.sample_rate,
+    })
+}
+
+/// Decodes the samples in `bytes` and normalizes them like the samples of a wav file.
+fn decode_raw_samples(bytes: &[u8], sample_format: RawSampleFormat) -> Vec<f64> {
+    let samples = bytes.chunks_exact(sample_format.bytes_per_sample());
+    let int16 = |samples: Vec<i16>| math_utils::normalize_int16_to_double(&samples);
+    // The 24 bit samples are placed in the upper bytes, so the shift sign-extends them.
+    let int24 = |samples: Vec<i32>| {
+        let samples = samples.into_iter().map(|sample| sample >> 8).collect::<Vec<i32>>();
+        math_utils::normalize_int24_to_double(&samples)
+    };
+    let float32 = |samples: Vec<f32>| samples.into_iter().map(f64::from).collect();
+
+    match sample_format {
+        RawSampleFormat::I16Le => {
+            int16(samples.map(|s| i16::from_le_bytes([s[0], s[1]])).collect())
+        }
+        RawSampleFormat::I16Be => {
+            int16(samples.map(|s| i16::from_be_bytes([s[0], s[1]])).collect())
+        }
+        RawSampleFormat::I24Le => {
+            int24(samples.map(|s| i32::from_le_bytes([0, s[0], s[1], s[2]])).collect())
+        }
+        RawSampleFormat::I24Be => {
+            int24(samples.map(|s| i32::from_be_bytes([s[0], s[1], s[2], 0])).collect())
+        }
+        RawSampleFormat::F32Le => {
+            float32(samples.map(|s| f32::from_le_bytes([s[0], s[1], s[2], s[3]])).collect())
+        }
+        RawSampleFormat::F32Be => {
+            float32(samples.map(|s| f32::from_be_bytes([s[0], s[1], s[2], s[3]])).collect())
+        }
+    }
+}
+
+/// Loads audio data provided by `reader`, e.g. a `std::io::Cursor` over an in-memory buffer, without touching the filesystem.
+/// The data is decoded according to `hint` and multiple channels are summed and normalized to 1 mono channel.
+pub fn load_as_mono_from_reader<R: Read + Seek>(
+    reader: R,
+    hint: FormatHint,
+) -> Result<AudioSignal, VisqolError> {
+    load_as_mono_from_reader_with(reader, hint, &DownmixConfig::default())
+}
+
+/// Like `load_as_mono_from_reader`, but multiple channels are combined according to `downmix`.
+pub fn load_as_mono_from_reader_with<R: Read + Seek>(
+    mut reader: R,
+    hint: FormatHint,
+    downmix: &DownmixConfig,
+) -> Result<AudioSignal, VisqolError> {
+    let format = match hint {
+        FormatHint::Detect => {
+            AudioFormat::detect(&AudioFormat::read_magic_bytes(&mut reader)?, None)
+        }
+        FormatHint::Wav => AudioFormat::Wav,
+        #[cfg(feature = "flac")]
+        FormatHint::Flac => AudioFormat::Flac,
+        #[cfg(any(feature = "mp3", feature = "symphonia"))]
+        FormatHint::Mp3 => AudioFormat::Symphonia {
+            extension: Some(String::from("mp3")),
+        },
+    };
+    decode_as_mono(reader, format, downmix)
+}
+
+/// Decodes the contents of `reader` using the decoder for `format` and combines all channels to a mono signal.
+fn decode_as_mono<R: Read>(
+    reader: R,
+    format: AudioFormat,
+    downmix: &DownmixConfig,
+) -> Result<AudioSignal, VisqolError> {
+    let (sample_matrix, sample_rate) = decode_multichannel(reader, format)?;
+
+    Ok(AudioSignal {
+        data_matrix: downmix_to_mono(&sample_matrix, downmix)?,
+        sample_rate,
+    })
+}
+
+/// Decodes the contents of `reader` using the decoder for `format`.
+/// Returns the normalized samples with 1 column per channel and the sample rate.
+fn decode_multichannel<R: Read>(
+    reader: R,
+    format: AudioFormat,
+) -> Result<(Array2<f64>, u32), VisqolError> {
+    let (num_channels, sample_rate, samples) = match format {
+        AudioFormat::Wav => {
+            let wav_file = WavFile::from_reader(reader)?;
+            (wav_file.num_channels, wav_file.sample_rate, wav_file.samples)
+        }
+        #[cfg(feature = "flac")]
+        AudioFormat::Flac => {
+            let flac_file = FlacFile::from_reader(reader)?;
+            (flac_file.num_channels, flac_file.sample_rate, flac_file.samples)
+        }
+        #[cfg(any(feature = "mp3", feature = "symphonia"))]
+        AudioFormat::Symphonia { extension } => {
+            let decoded_file = SymphoniaFile::from_reader(reader, extension.as_deref())?;
+            (
+                decoded_file.num_channels,
+                decoded_file.sample_rate,
+                decoded_file.samples.iter().map(|&sample| sample as f64).collect(),
+            )
+        }
+    };
+
+    Ok((
+        extract_multichannel(num_channels as usize, &samples)?,
+        sample_rate,
+    ))
+}
+
 /// De-interleave an interleaved signal and returns them in a matrix. 1 row represents 1 channel.
-fn extract_multichannel(num_channels: usize, interleaved_vector: &[f64]) -> Array2<f64> {
+/// Returns `VisqolError::NoAudioTrack` if there are no channels.
+fn extract_multichannel(
+    num_channels: usize,
+    interleaved_vector: &[f64],
+) -> Result<Array2<f64>, VisqolError> {
+    if num_channels == 0 {
+        return Err(VisqolError::NoAudioTrack);
+    }
     assert!(interleaved_vector.len().is_multiple_of(num_channels));
     let sub_vector_size = interleaved_vector.len() / num_channels;
-    Array2::from_shape_vec(
+    Ok(Array2::from_shape_vec(
         (sub_vector_size, num_channels).strides((num_channels, 1)),
         interleaved_vector.to_vec(),
     )
-    .expect("Failed to sum multichannel signal to mono signal!")
+    .expect("Failed to sum multichannel signal to mono signal!"))
 }
 
 /// Scales 2 spectrograms to match their sound pressure levels.
@@ -125,6 +815,14 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn zero_channels_are_rejected() {
+        assert!(matches!(
+            extract_multichannel(0, &[]),
+            Err(VisqolError::NoAudioTrack)
+        ));
+    }
+
     #[test]
     fn mono_file_is_read_successfully() {
         let expected_mono_test_sample_rate = 48000;
@@ -174,6 +872,412 @@ mod tests {
         );
     }
 
+    #[test]
+    fn wav_header_is_probed() {
+        let info = probe("test_data/clean_speech/reference_signal_16k_stereo.wav").unwrap();
+        let info_24_bits = probe("test_data/clean_speech/CA01_01_24bits.wav").unwrap();
+
+        assert_eq!(info.sample_rate, 16000);
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.bit_depth, Some(16));
+        assert_abs_diff_eq!(info.duration.unwrap(), 2.7384375, epsilon = 1e-9);
+        assert_eq!(info_24_bits.bit_depth, Some(24));
+        assert!(matches!(
+            probe("test_data/does_not_exist.wav"),
+            Err(VisqolError::FileNotFound { .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "flac")]
+    fn flac_stream_info_is_probed() {
+        let info = probe("test_data/CA01_01.flac").unwrap();
+
+        assert_eq!(info.sample_rate, 48000);
+        assert_eq!(info.channels, 1);
+        assert_eq!(info.bit_depth, Some(16));
+        assert_abs_diff_eq!(info.duration.unwrap(), 131072.0 / 48000.0, epsilon = 1e-9);
+    }
+
+    #[test]
+    #[cfg(feature = "flac")]
+    fn flac_file_matches_wav_file() {
+        // The flac fixture holds the first 131072 samples of the wav fixture.
+        let expected_flac_num_samples = 131072;
+
+        let wav_signal = load_as_mono("test_data/CA01_01.wav").unwrap();
+        let flac_signal = load_as_mono("test_data/CA01_01.flac").unwrap();
+
+        assert_eq!(flac_signal.sample_rate, wav_signal.sample_rate);
+        assert_eq!(flac_signal.len(), expected_flac_num_samples);
+        assert_eq!(
+            flac_signal.data_matrix,
+            wav_signal.data_matrix.slice(s![..expected_flac_num_samples])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "flac")]
+    fn flac_24_bit_file_matches_wav_file() {
+        // The flac fixture holds the samples of the 24 bit wav fixture.
+        let wav_signal = load_as_mono("test_data/clean_speech/CA01_01_24bits.wav").unwrap();
+        let flac_signal = load_as_mono("test_data/CA01_01_24bits.flac").unwrap();
+
+        assert_eq!(probe("test_data/CA01_01_24bits.flac").unwrap().bit_depth, Some(24));
+        assert_eq!(flac_signal.sample_rate, wav_signal.sample_rate);
+        assert_eq!(flac_signal.data_matrix, wav_signal.data_matrix);
+    }
+
+    #[test]
+    #[cfg(any(feature = "mp3", feature = "symphonia"))]
+    fn mp3_file_is_decoded() {
+        let wav_signal = load_as_mono("test_data/CA01_01.wav").unwrap();
+        let mp3_signal = load_as_mono("test_data/CA01_01.mp3").unwrap();
+
+        assert_eq!(mp3_signal.sample_rate, wav_signal.sample_rate);
+        // The decoded signal is quantized to full mp3 frames.
+        assert_abs_diff_eq!(
+            mp3_signal.get_duration(),
+            wav_signal.get_duration(),
+            epsilon = 0.1
+        );
+    }
+
+    #[test]
+    fn wav_buffer_matches_wav_file() {
+        let path = "test_data/conformance_testdata_subset/guitar48_stereo.wav";
+        let contents = std::io::Cursor::new(std::fs::read(path).unwrap());
+
+        let signal_from_file = load_as_mono(path).unwrap();
+        let signal_from_buffer = load_as_mono_from_reader(contents, FormatHint::Wav).unwrap();
+
+        assert_eq!(signal_from_buffer.sample_rate, signal_from_file.sample_rate);
+        assert_eq!(signal_from_buffer.data_matrix, signal_from_file.data_matrix);
+    }
+
+    #[test]
+    fn format_of_buffer_is_detected() {
+        let path = "test_data/CA01_01.wav";
+        let contents = std::io::Cursor::new(std::fs::read(path).unwrap());
+
+        let signal_from_buffer = load_as_mono_from_reader(contents, FormatHint::Detect).unwrap();
+
+        assert_eq!(signal_from_buffer.data_matrix, load_as_mono(path).unwrap().data_matrix);
+    }
+
+    #[test]
+    fn resampled_sine_matches_sine_at_target_rate() {
+        let frequency = 440.0;
+        let sine = |sample_rate: u32, num_samples: usize| -> Vec<f64> {
+            (0..num_samples)
+                .map(|n| {
+                    (2.0 * std::f64::consts::PI * frequency * n as f64 / sample_rate as f64).sin()
+                })
+                .collect()
+        };
+        let signal = AudioSignal::new(sine(48000, 48000), 48000);
+
+        let resampled = resample(&signal, 16000).unwrap();
+
+        assert_eq!(resampled.sample_rate, 16000);
+        assert_eq!(resampled.len(), 16000);
+        let expected = sine(16000, 16000);
+        // Skip the edges, where the resampler's filter rings.
+        for index in 1000..15000 {
+            assert_abs_diff_eq!(resampled[index], expected[index], epsilon = 0.01);
+        }
+    }
+
+    #[test]
+    fn resampling_to_same_rate_is_identity() {
+        let signal = load_as_mono("test_data/CA01_01.wav").unwrap();
+
+        let resampled = resample(&signal, signal.sample_rate).unwrap();
+
+        assert_eq!(resampled.sample_rate, signal.sample_rate);
+        assert_eq!(resampled.data_matrix, signal.data_matrix);
+    }
+
+    #[test]
+    fn equal_downmix_weights_match_default_downmix() {
+        let path = "test_data/conformance_testdata_subset/castanets48_stereo.wav";
+        let downmix = DownmixConfig {
+            weights: vec![0.5, 0.5],
+        };
+
+        let default_signal = load_as_mono(path).unwrap();
+        let weighted_signal = load_as_mono_with(path, &downmix).unwrap();
+
+        assert_eq!(weighted_signal.data_matrix, default_signal.data_matrix);
+    }
+
+    #[test]
+    fn downmix_weights_select_channels() {
+        let path = "test_data/conformance_testdata_subset/castanets48_stereo.wav";
+        let left_only = DownmixConfig {
+            weights: vec![1.0, 0.0],
+        };
+        let right_only = DownmixConfig {
+            weights: vec![0.0, 1.0],
+        };
+
+        let default_signal = load_as_mono(path).unwrap();
+        let left = load_as_mono_with(path, &left_only).unwrap();
+        let right = load_as_mono_with(path, &right_only).unwrap();
+
+        assert_ne!(left.data_matrix, right.data_matrix);
+        for index in 0..default_signal.len() {
+            assert_abs_diff_eq!(
+                left[index] + right[index],
+                2.0 * default_signal[index],
+                epsilon = 1e-12
+            );
+        }
+    }
+
+    #[test]
+    fn missing_file_is_reported_with_its_path() {
+        let error = load_as_mono("test_data/does_not_exist.wav").err().unwrap();
+
+        assert!(matches!(
+            &error,
+            VisqolError::FileNotFound { path } if path == "test_data/does_not_exist.wav"
+        ));
+        assert!(error.to_string().contains("test_data/does_not_exist.wav"));
+    }
+
+    #[test]
+    fn undecodable_file_is_reported_with_its_path() {
+        let error = load_channels("Cargo.toml").err().unwrap();
+
+        assert!(matches!(
+            &error,
+            VisqolError::DecodeFailed { path, .. } if path == "Cargo.toml"
+        ));
+        assert!(error.to_string().contains("Cargo.toml"));
+    }
+
+    #[test]
+    fn wrong_number_of_downmix_weights_is_rejected() {
+        let downmix = DownmixConfig {
+            weights: vec![1.0, 0.0, 0.0],
+        };
+
+        let error = load_as_mono_with(
+            "test_data/conformance_testdata_subset/castanets48_stereo.wav",
+            &downmix,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(
+            error,
+            VisqolError::InvalidDownmixWeights {
+                num_weights: 3,
+                num_channels: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn chunk_matches_slice_of_whole_file() {
+        let path = "test_data/clean_speech/reference_signal_16k_stereo.wav";
+        let whole_file = load_as_mono(path).unwrap();
+
+        let mut reader = open_wav_chunks(path).unwrap();
+        let chunk = read_mono_chunk(&mut reader, 8000, 4000, &DownmixConfig::default()).unwrap();
+
+        assert_eq!(reader.num_frames() as usize, whole_file.len());
+        assert_eq!(chunk.sample_rate, 16000);
+        assert_eq!(chunk.data_matrix, whole_file.data_matrix.slice(s![8000..12000]));
+        assert!(matches!(
+            open_wav_chunks("test_data/does_not_exist.wav"),
+            Err(VisqolError::FileNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn raw_pcm_is_decoded_in_all_formats() {
+        let format = |sample_format| RawPcmFormat {
+            sample_rate: 8000,
+            channels: 2,
+            sample_format,
+        };
+        let samples = [0.5, -0.25, 1.0, 0.0];
+        let int16 = samples.map(|sample| (sample * 32767.0) as i16);
+        let int24 = samples.map(|sample| (sample * 8388607.0) as i32);
+        let encode = |encode_sample: &dyn Fn(usize) -> Vec<u8>| {
+            (0..samples.len()).flat_map(encode_sample).collect::<Vec<u8>>()
+        };
+        let encoded = [
+            (RawSampleFormat::I16Le, encode(&|i| int16[i].to_le_bytes().to_vec())),
+            (RawSampleFormat::I16Be, encode(&|i| int16[i].to_be_bytes().to_vec())),
+            (RawSampleFormat::I24Le, encode(&|i| int24[i].to_le_bytes()[..3].to_vec())),
+            (RawSampleFormat::I24Be, encode(&|i| int24[i].to_be_bytes()[1..].to_vec())),
+            (RawSampleFormat::F32Le, encode(&|i| (samples[i] as f32).to_le_bytes().to_vec())),
+            (RawSampleFormat::F32Be, encode(&|i| (samples[i] as f32).to_be_bytes().to_vec())),
+        ];
+
+        for (sample_format, bytes) in encoded {
+            let signal = load_raw_pcm(&bytes, format(sample_format)).unwrap();
+            assert_eq!(signal.sample_rate, 8000);
+            assert_eq!(signal.data_matrix.len(), 2);
+            assert_abs_diff_eq!(signal.data_matrix[0], 0.125, epsilon = 1e-4);
+            assert_abs_diff_eq!(signal.data_matrix[1], 0.5, epsilon = 1e-4);
+        }
+        assert!(matches!(
+            load_raw_pcm(&[0; 6], format(RawSampleFormat::I16Le)),
+            Err(VisqolError::InvalidRawPcm {
+                num_bytes: 6,
+                bytes_per_frame: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn channels_are_loaded_separately() {
+        let path = "test_data/conformance_testdata_subset/castanets48_stereo.wav";
+        let left_only = DownmixConfig {
+            weights: vec![1.0, 0.0],
+        };
+
+        let channels = load_channels(path).unwrap();
+
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].sample_rate, 48000);
+        assert_eq!(
+            channels[0].data_matrix,
+            load_as_mono_with(path, &left_only).unwrap().data_matrix
+        );
+    }
+
+    #[test]
+    fn saved_wav_is_loaded_back() {
+        let signal = load_as_mono("test_data/clean_speech/reference_signal_16k.wav").unwrap();
+        let int_path = crate::test_utility::temp_path("visqol_save_wav_int16.wav");
+        let float_path = crate::test_utility::temp_path("visqol_save_wav_float32.wav");
+        let loud_signal = AudioSignal::new(vec![0.5, 1.5, -2.0], 16000);
+
+        save_wav(&signal, int_path.to_str().unwrap()).unwrap();
+        save_wav_with(&loud_signal, float_path.to_str().unwrap(), WavSampleFormat::Float32)
+            .unwrap();
+
+        let saved = load_as_mono(int_path.to_str().unwrap()).unwrap();
+        assert_eq!(saved.sample_rate, signal.sample_rate);
+        assert_eq!(saved.data_matrix, signal.data_matrix);
+        let saved_loud = load_as_mono(float_path.to_str().unwrap()).unwrap();
+        assert_eq!(saved_loud.data_matrix, loud_signal.data_matrix);
+    }
+
+    #[test]
+    fn higher_bit_depths_match_16_bit_file() {
+        let signal_16_bit = load_as_mono("test_data/clean_speech/CA01_01.wav").unwrap();
+
+        for path in [
+            "test_data/clean_speech/CA01_01_24bits.wav",
+            "test_data/clean_speech/CA01_01_float.wav",
+        ] {
+            let signal = load_as_mono(path).unwrap();
+            assert_eq!(signal.sample_rate, signal_16_bit.sample_rate);
+            assert_eq!(signal.len(), 16384);
+            for index in 0..signal.len() {
+                assert_abs_diff_eq!(signal[index], signal_16_bit[index], epsilon = 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn signal_is_normalized_to_rms_target() {
+        let mut signal = load_as_mono("test_data/CA01_01.wav").unwrap();
+
+        normalize_loudness(&mut signal, NormalizationTarget::Rms { target_dbfs: -20.0 });
+
+        let rms = (signal.data_matrix.iter().map(|x| x.powi(2)).sum::<f64>()
+            / signal.len() as f64)
+            .sqrt();
+        assert_abs_diff_eq!(rms, 0.1, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn signal_is_normalized_to_ebu_r128_target() {
+        let mut signal = load_as_mono("test_data/CA01_01.wav").unwrap();
+
+        normalize_loudness(&mut signal, NormalizationTarget::EbuR128);
+
+        assert_abs_diff_eq!(
+            loudness::integrated_loudness(&signal).unwrap(),
+            constants::EBU_R128_TARGET_LUFS,
+            epsilon = 1e-6
+        );
+    }
+
+    #[test]
+    fn silence_is_not_normalized() {
+        let mut signal = AudioSignal::new([0.0; 48000], 48000);
+
+        normalize_loudness(&mut signal, NormalizationTarget::Rms { target_dbfs: -20.0 });
+        normalize_loudness(&mut signal, NormalizationTarget::EbuR128);
+
+        assert!(signal.data_matrix.iter().all(|&sample| sample == 0.0));
+    }
+
+    #[test]
+    fn leading_and_trailing_silence_is_trimmed() {
+        let mut samples = vec![0.0; 100];
+        samples.extend((0..1000).map(|n| (n as f64 * 0.1).cos()));
+        samples.extend(vec![1e-6; 50]);
+        let mut signal = AudioSignal::new(samples, 48000);
+
+        let (leading, trailing) = trim_silence(&mut signal, -60.0);
+
+        assert_eq!((leading, trailing), (100, 50));
+        assert_eq!(signal.len(), 1000);
+        assert_eq!(signal[0], 1.0);
+    }
+
+    #[test]
+    fn silent_signal_is_trimmed_completely() {
+        let mut signal = AudioSignal::new([0.0; 100], 48000);
+
+        assert_eq!(trim_silence(&mut signal, -60.0), (100, 0));
+        assert!(signal.is_empty());
+    }
+
+    #[test]
+    fn silent_degraded_signal_is_not_scaled() {
+        let reference = AudioSignal::new([0.5, -0.5, 0.25], 48000);
+        let silence = AudioSignal::new([0.0; 3], 48000);
+
+        let scaled = scale_to_match_sound_pressure_level(&reference, &silence);
+
+        assert!(scaled.data_matrix.iter().all(|&sample| sample == 0.0));
+        assert!(is_silent(&silence));
+        assert!(!is_silent(&reference));
+    }
+
+    #[test]
+    fn dc_offset_is_removed() {
+        // 100 periods of a sine at 1 kHz, so that only the bias contributes to the mean.
+        let samples = (0..4800)
+            .map(|n| (2.0 * std::f64::consts::PI * n as f64 / 48.0).sin() * 0.5 + 0.2)
+            .collect::<Vec<f64>>();
+        let mut signal = AudioSignal::new(samples.as_slice(), 48000);
+        let offset = dc_offset(&signal);
+
+        remove_dc_offset(&mut signal);
+
+        assert_abs_diff_eq!(offset, 0.2, epsilon = 1e-9);
+        assert_abs_diff_eq!(dc_offset(&signal), 0.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(signal[100], samples[100] - offset, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn clipped_samples_are_counted() {
+        let signal = AudioSignal::new([0.5, 1.0, -0.2, -1.0, 0.999, 1.2, 0.0, 0.3], 48000);
+
+        assert_eq!(clipped_fraction(&signal), 0.375);
+        assert_eq!(clipped_fraction(&AudioSignal::new([0.0; 0], 48000)), 0.0);
+    }
+
     #[test]
     #[should_panic]
     fn loading_32_bit_quantization_fails() {
@@ -185,4 +1289,23 @@ mod tests {
     fn loading_8_bit_quantization_fails() {
         load_as_mono("test_data/clean_speech/CA01_01_8bits.wav").unwrap();
     }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn mapped_wav_matches_buffered_decoding() {
+        let path = "test_data/conformance_testdata_subset/guitar48_stereo.wav";
+        let downmix = DownmixConfig {
+            weights: vec![0.7, 0.3],
+        };
+
+        let mapped = load_mapped_wav_as_mono(path, &downmix).unwrap().unwrap();
+        let (sample_matrix, sample_rate) = decode_file(path).unwrap();
+        assert_eq!(mapped.sample_rate, sample_rate);
+        assert_eq!(mapped.data_matrix, downmix_to_mono(&sample_matrix, &downmix).unwrap());
+        assert!(load_mapped_wav_as_mono("test_data/does_not_exist.wav", &downmix).is_none());
+        assert!(matches!(
+            load_mapped_wav_as_mono(path, &DownmixConfig { weights: vec![1.0] }),
+            Some(Err(VisqolError::InvalidDownmixWeights { .. }))
+        ));
+    }
 }
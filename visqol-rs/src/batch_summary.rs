@@ -0,0 +1,159 @@
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::similarity_result::SimilarityResult;
+use crate::visqol_error::VisqolError;
+
+/// Width of the bins of `BatchSummary::mos_histogram`.
+const HISTOGRAM_BIN_WIDTH: f64 = 0.5;
+/// Lowest MOS of the first bin of `BatchSummary::mos_histogram`.
+const HISTOGRAM_MIN_MOS: f64 = 1.0;
+
+/// Statistics of the MOS-LQO of the scored results of a batch.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MosStatistics {
+    pub mean: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Aggregate statistics over the results of a batch, e.g. returned by `VisqolManager::run_batch`.
+/// Results with `SimilarityResult::is_reliable` set to `false` are counted but excluded from the statistics and the histogram.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchSummary {
+    /// Number of results, including failures.
+    pub num_results: usize,
+    /// Number of results which failed with an error.
+    pub num_failed: usize,
+    /// Number of results which were scored but are unreliable.
+    pub num_unreliable: usize,
+    /// Statistics of the reliable results, or `None` if there are none.
+    pub mos: Option<MosStatistics>,
+    /// Number of reliable results per MOS bin of 0.5, the first bin counting MOS-LQO in [1, 1.5) and the last one in [4.5, 5].
+    /// Scores outside of [1, 5] are counted in the first or last bin.
+    pub mos_histogram: [usize; 8],
+}
+
+impl BatchSummary {
+    /// Summarizes `results`, which are typically returned by `VisqolManager::run_batch`.
+    pub fn from_results(results: &[Result<SimilarityResult, VisqolError>]) -> Self {
+        let scored = results.iter().filter_map(|result| result.as_ref().ok());
+        let mut mos_values = scored
+            .clone()
+            .filter(|result| result.is_reliable)
+            .map(|result| result.moslqo)
+            .collect::<Vec<f64>>();
+        mos_values.sort_by(f64::total_cmp);
+
+        let mut mos_histogram = [0; 8];
+        for &mos in &mos_values {
+            let bin = ((mos - HISTOGRAM_MIN_MOS) / HISTOGRAM_BIN_WIDTH).max(0.0) as usize;
+            mos_histogram[bin.min(mos_histogram.len() - 1)] += 1;
+        }
+
+        let num_scored = scored.clone().count();
+        Self {
+            num_results: results.len(),
+            num_failed: results.len() - num_scored,
+            num_unreliable: scored.filter(|result| !result.is_reliable).count(),
+            mos: Self::statistics(&mos_values),
+            mos_histogram,
+        }
+    }
+
+    /// Returns the statistics of the sorted `mos_values`.
+    fn statistics(mos_values: &[f64]) -> Option<MosStatistics> {
+        let (&min, &max) = (mos_values.first()?, mos_values.last()?);
+        let middle = mos_values.len() / 2;
+        let median = if mos_values.len() % 2 == 1 {
+            mos_values[middle]
+        } else {
+            (mos_values[middle - 1] + mos_values[middle]) / 2.0
+        };
+        Some(MosStatistics {
+            mean: mos_values.iter().sum::<f64>() / mos_values.len() as f64,
+            median,
+            min,
+            max,
+        })
+    }
+}
+
+/// Prints the counts, the MOS statistics and the histogram as a plain text report, one line per histogram bin.
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} results: {} failed, {} unreliable",
+            self.num_results, self.num_failed, self.num_unreliable
+        )?;
+        match &self.mos {
+            Some(mos) => write!(
+                f,
+                "MOS-LQO mean {:.3}, median {:.3}, min {:.3}, max {:.3}",
+                mos.mean, mos.median, mos.min, mos.max
+            )?,
+            None => write!(f, "No reliable results")?,
+        }
+        for (index, count) in self.mos_histogram.iter().enumerate() {
+            let low = HISTOGRAM_MIN_MOS + index as f64 * HISTOGRAM_BIN_WIDTH;
+            let bar = "#".repeat(*count);
+            write!(f, "\n{:.1}-{:.1}: {:>4} {}", low, low + HISTOGRAM_BIN_WIDTH, count, bar)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    /// Returns a reliable or unreliable result with the MOS `moslqo`.
+    fn result(moslqo: f64, is_reliable: bool) -> Result<SimilarityResult, VisqolError> {
+        let mut result =
+            SimilarityResult::new(moslqo, 0.5, vec![], vec![], vec![], vec![], vec![]);
+        result.is_reliable = is_reliable;
+        Ok(result)
+    }
+
+    #[test]
+    fn batch_is_summarized() {
+        let results = vec![
+            result(1.2, true),
+            result(4.0, true),
+            result(3.1, true),
+            result(5.0, true),
+            result(1.0, false),
+            Err(VisqolError::SignalsTooDifferent),
+        ];
+
+        let summary = BatchSummary::from_results(&results);
+
+        assert_eq!(summary.num_results, 6);
+        assert_eq!(summary.num_failed, 1);
+        assert_eq!(summary.num_unreliable, 1);
+        let mos = summary.mos.unwrap();
+        assert_abs_diff_eq!(mos.mean, 3.325, epsilon = 1e-9);
+        assert_abs_diff_eq!(mos.median, 3.55, epsilon = 1e-9);
+        assert_eq!((mos.min, mos.max), (1.2, 5.0));
+        assert_eq!(summary.mos_histogram, [1, 0, 0, 0, 1, 0, 1, 1]);
+        assert!(summary.to_string().starts_with("6 results: 1 failed, 1 unreliable\n"));
+    }
+
+    #[test]
+    fn batch_without_reliable_results_has_no_statistics() {
+        let results = vec![result(2.0, false), Err(VisqolError::FailedToAlignSignals)];
+
+        let summary = BatchSummary::from_results(&results);
+
+        assert_eq!(summary.mos, None);
+        assert_eq!(summary.mos_histogram, [0; 8]);
+        assert!(summary.to_string().contains("No reliable results"));
+    }
+}
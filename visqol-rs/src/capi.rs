@@ -0,0 +1,275 @@
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::variant::Variant;
+use crate::visqol_error::VisqolError;
+use crate::visqol_manager::DynamicVisqolManager;
+
+/// Selects the variant of a manager created by `visqol_create`, which takes the values as `int`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisqolVariant {
+    /// `Variant::Wideband` with the scaled MOS mapping, for speech sampled at 16 kHz.
+    Speech = 0,
+    /// `Variant::Fullband`, for audio sampled at 48 kHz.
+    Audio = 1,
+}
+
+/// Status codes returned by the C API. Every `VisqolError` is mapped to one of them.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisqolStatus {
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// A path was not valid UTF-8.
+    InvalidString = 2,
+    /// The manager is configured with invalid spectrogram, frequency or downmix settings, or an invalid model.
+    InvalidConfiguration = 3,
+    FileNotFound = 4,
+    /// A file could not be decoded, e.g. because of an unsupported format or bit depth.
+    DecodeFailed = 5,
+    Io = 6,
+    /// The signals have different or unsupported sample rates or channel counts, or hold non-finite samples.
+    UnsupportedSignal = 7,
+    /// A signal is too short to be scored, or the durations of both signals differ too much.
+    SignalTooShort = 8,
+    /// The signals could not be aligned or compared.
+    ScoringFailed = 9,
+    /// An unexpected internal error occurred.
+    InternalError = 10,
+}
+
+impl From<&VisqolError> for VisqolStatus {
+    fn from(error: &VisqolError) -> Self {
+        match error {
+            VisqolError::InvalidFrameSizes { .. }
+            | VisqolError::InvalidFrequencyRange { .. }
+            | VisqolError::InvalidDownmixWeights { .. }
+            | VisqolError::InvalidBandWeights { .. }
+            | VisqolError::InvalidNumBands { .. }
+            | VisqolError::InvalidPatchStride { .. }
+            | VisqolError::InvalidChunkSize { .. }
+            | VisqolError::UnsupportedInChunkedMode { .. }
+            | VisqolError::InvalidSegmentDuration { .. }
+            | VisqolError::InvalidTimeRange { .. }
+            | VisqolError::InvalidConfig { .. }
+            | VisqolError::InvalidGlobPattern { .. }
+            | VisqolError::GlobCountMismatch { .. }
+            | VisqolError::InvalidModel { .. } => VisqolStatus::InvalidConfiguration,
+            VisqolError::FileNotFound { .. } => VisqolStatus::FileNotFound,
+            VisqolError::InvalidBitsPerSample { .. }
+            | VisqolError::InvalidRawPcm { .. }
+            | VisqolError::DecodeFailed { .. }
+            | VisqolError::NoAudioTrack
+            | VisqolError::Wav(_) => VisqolStatus::DecodeFailed,
+            #[cfg(feature = "flac")]
+            VisqolError::Flac(_) => VisqolStatus::DecodeFailed,
+            #[cfg(any(feature = "mp3", feature = "symphonia"))]
+            VisqolError::Symphonia(_) => VisqolStatus::DecodeFailed,
+            VisqolError::Io(_) => VisqolStatus::Io,
+            #[cfg(feature = "image")]
+            VisqolError::Image(_) => VisqolStatus::Io,
+            VisqolError::DifferentSampleRates { .. }
+            | VisqolError::UnsupportedSampleRate { .. }
+            | VisqolError::SampleRateVariantMismatch { .. }
+            | VisqolError::DifferentChannelCounts { .. }
+            | VisqolError::NonFiniteSamples { .. } => VisqolStatus::UnsupportedSignal,
+            VisqolError::TooFewSamples { .. }
+            | VisqolError::EmptySignal { .. }
+            | VisqolError::ReferenceSpectrogramTooSmall { .. }
+            | VisqolError::SignalTooShort { .. }
+            | VisqolError::DurationMismatch { .. } => VisqolStatus::SignalTooShort,
+            VisqolError::SignalsTooDifferent
+            | VisqolError::FailedToAlignSignals
+            | VisqolError::FailedToComputeVad => VisqolStatus::ScoringFailed,
+            VisqolError::ResamplerConstruction(_) | VisqolError::Resampling(_) => {
+                VisqolStatus::InternalError
+            }
+        }
+    }
+}
+
+/// Opaque handle to a manager, created by `visqol_create` and released by `visqol_destroy`.
+pub struct VisqolHandle {
+    manager: DynamicVisqolManager,
+}
+
+/// Converts a nul-terminated C string to a `&str`.
+///
+/// # Safety
+/// `string` must be null or point to a nul-terminated string which outlives the returned reference.
+unsafe fn to_str<'a>(string: *const c_char) -> Result<&'a str, VisqolStatus> {
+    if string.is_null() {
+        return Err(VisqolStatus::NullPointer);
+    }
+    CStr::from_ptr(string)
+        .to_str()
+        .map_err(|_| VisqolStatus::InvalidString)
+}
+
+/// Creates a manager for `variant`, one of the `VisqolVariant` values, which searches `window_size` frames for the best matching patch.
+/// `model_path` is the path to the SVR model used by `VisqolVariant::Audio`. If it is null, the model embedded in the library is used. It is ignored for `VisqolVariant::Speech`.
+/// Returns null if the arguments are invalid or the model cannot be loaded, e.g. with `VisqolStatus::InvalidConfiguration` for an unknown `variant`.
+/// The status is written to `out_status` unless it is null. The handle must be released with `visqol_destroy`.
+///
+/// # Safety
+/// `model_path` must be null or point to a nul-terminated string, `out_status` must be null or point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn visqol_create(
+    variant: c_int,
+    model_path: *const c_char,
+    window_size: usize,
+    out_status: *mut VisqolStatus,
+) -> *mut VisqolHandle {
+    let (handle, status) = match create(variant, model_path, window_size) {
+        Ok(handle) => (Box::into_raw(handle), VisqolStatus::Ok),
+        Err(status) => (ptr::null_mut(), status),
+    };
+    if !out_status.is_null() {
+        *out_status = status;
+    }
+    handle
+}
+
+/// Creates the manager of `visqol_create`.
+///
+/// # Safety
+/// `model_path` must be null or point to a nul-terminated string.
+unsafe fn create(
+    variant: c_int,
+    model_path: *const c_char,
+    window_size: usize,
+) -> Result<Box<VisqolHandle>, VisqolStatus> {
+    let variant = match variant {
+        variant if variant == VisqolVariant::Speech as c_int => Variant::Wideband {
+            use_unscaled_mos_mapping: false,
+        },
+        variant if variant == VisqolVariant::Audio as c_int => Variant::Fullband {
+            model_path: if model_path.is_null() {
+                None
+            } else {
+                Some(to_str(model_path)?.to_string())
+            },
+        },
+        _ => return Err(VisqolStatus::InvalidConfiguration),
+    };
+
+    let build = || {
+        DynamicVisqolManager::builder()
+            .variant(variant)
+            .search_window(window_size)
+            .try_build()
+    };
+    match panic::catch_unwind(build) {
+        Ok(Ok(manager)) => Ok(Box::new(VisqolHandle { manager })),
+        Ok(Err(error)) => Err(VisqolStatus::from(&error)),
+        Err(_) => Err(VisqolStatus::InternalError),
+    }
+}
+
+/// Scores the degraded file in `deg_path` against the reference file in `ref_path` and writes the MOS to `out_mos`.
+/// `out_mos` is only written if `VisqolStatus::Ok` is returned.
+///
+/// # Safety
+/// `handle` must be null or have been returned by `visqol_create` and not yet been destroyed.
+/// `ref_path` and `deg_path` must be null or point to nul-terminated strings, `out_mos` must be null or point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn visqol_run(
+    handle: *const VisqolHandle,
+    ref_path: *const c_char,
+    deg_path: *const c_char,
+    out_mos: *mut f64,
+) -> VisqolStatus {
+    if handle.is_null() || out_mos.is_null() {
+        return VisqolStatus::NullPointer;
+    }
+    let (ref_path, deg_path) = match (to_str(ref_path), to_str(deg_path)) {
+        (Ok(ref_path), Ok(deg_path)) => (ref_path, deg_path),
+        (Err(status), _) | (_, Err(status)) => return status,
+    };
+
+    let manager = &(*handle).manager;
+    match panic::catch_unwind(AssertUnwindSafe(|| manager.run(ref_path, deg_path))) {
+        Ok(Ok(result)) => {
+            *out_mos = result.moslqo;
+            VisqolStatus::Ok
+        }
+        Ok(Err(error)) => VisqolStatus::from(&error),
+        Err(_) => VisqolStatus::InternalError,
+    }
+}
+
+/// Releases a manager created by `visqol_create`. Passing null does nothing.
+///
+/// # Safety
+/// `handle` must be null or have been returned by `visqol_create` and not yet been destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn visqol_destroy(handle: *mut VisqolHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use std::ffi::CString;
+
+    #[test]
+    fn speech_files_are_scored() {
+        let ref_path = CString::new("test_data/clean_speech/reference_signal_16k.wav").unwrap();
+        let deg_path = CString::new("test_data/clean_speech/degraded_signal_16k.wav").unwrap();
+        let mut mos = 0.0;
+
+        unsafe {
+            let mut status = VisqolStatus::InternalError;
+            let speech = VisqolVariant::Speech as c_int;
+            let handle = visqol_create(speech, ptr::null(), 60, &mut status);
+            assert!(!handle.is_null());
+            assert_eq!(status, VisqolStatus::Ok);
+            let status = visqol_run(handle, ref_path.as_ptr(), deg_path.as_ptr(), &mut mos);
+            visqol_destroy(handle);
+            assert_eq!(status, VisqolStatus::Ok);
+        }
+        assert_abs_diff_eq!(mos, 2.35, epsilon = 0.01);
+    }
+
+    #[test]
+    fn errors_are_mapped_to_status_codes() {
+        let ref_path = CString::new("test_data/clean_speech/reference_signal_16k.wav").unwrap();
+        let missing_path = CString::new("test_data/does_not_exist.wav").unwrap();
+        let mut mos = 0.0;
+
+        unsafe {
+            let invalid_path = [0xffu8, 0];
+            let invalid_path = invalid_path.as_ptr() as *const c_char;
+            let mut status = VisqolStatus::Ok;
+            let audio = VisqolVariant::Audio as c_int;
+            assert!(visqol_create(audio, invalid_path, 60, &mut status).is_null());
+            assert_eq!(status, VisqolStatus::InvalidString);
+            assert!(visqol_create(2, ptr::null(), 60, &mut status).is_null());
+            assert_eq!(status, VisqolStatus::InvalidConfiguration);
+            assert!(visqol_create(-1, ptr::null(), 60, ptr::null_mut()).is_null());
+            let speech = VisqolVariant::Speech as c_int;
+            let handle = visqol_create(speech, ptr::null(), 60, ptr::null_mut());
+            assert_eq!(
+                visqol_run(handle, ref_path.as_ptr(), missing_path.as_ptr(), &mut mos),
+                VisqolStatus::FileNotFound
+            );
+            assert_eq!(
+                visqol_run(handle, ref_path.as_ptr(), ptr::null(), &mut mos),
+                VisqolStatus::NullPointer
+            );
+            visqol_destroy(handle);
+            assert_eq!(
+                visqol_run(ptr::null(), ref_path.as_ptr(), ref_path.as_ptr(), &mut mos),
+                VisqolStatus::NullPointer
+            );
+        }
+        assert_eq!(mos, 0.0);
+    }
+}
@@ -1,8 +1,5 @@
-use std::error::Error;
 
 use crate::alignment::align_and_truncate;
-use crate::constants;
-use crate::gammatone_filterbank::GammatoneFilterbank;
 use crate::gammatone_spectrogram_builder::GammatoneSpectrogramBuilder;
 use crate::{
     analysis_window::AnalysisWindow,
@@ -11,17 +8,39 @@ use crate::{
     neurogram_similiarity_index_measure::NeurogramSimiliarityIndexMeasure,
     patch_similarity_comparator::{PatchSimilarityComparator, PatchSimilarityResult},
     spectrogram_builder::SpectrogramBuilder,
+    visqol::SpectrogramConfig,
     visqol_error::VisqolError,
 };
-use ndarray::{concatenate, s, Array1, Array2, Axis};
+use ndarray::{concatenate, s, Array1, Array2, Axis, CowArray, Ix2};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::ops::Range;
+
+/// The best matching degraded patch for each reference patch, the frame offset of each of them and the optional similarity matrix.
+pub type PatchMatches = (Vec<PatchSimilarityResult>, Vec<usize>, Option<Array2<f64>>);
+
 pub struct ComparisonPatchesSelector {
     sim_comparator: NeurogramSimiliarityIndexMeasure,
+    record_similarity_matrix: bool,
 }
 
 impl ComparisonPatchesSelector {
-    pub fn new(sim_comparator: NeurogramSimiliarityIndexMeasure) -> Self { Self { sim_comparator } }
+    pub fn new(sim_comparator: NeurogramSimiliarityIndexMeasure) -> Self {
+        Self {
+            sim_comparator,
+            record_similarity_matrix: false,
+        }
+    }
+
+    /// If enabled, `find_most_optimal_deg_patches` also returns the similarity of every reference patch to every degraded patch within its search window,
+    /// see `similarity_matrix`. Disabled by default, as the matrix holds a value per reference patch and degraded frame.
+    pub fn set_record_similarity_matrix(&mut self, record_similarity_matrix: bool) {
+        self.record_similarity_matrix = record_similarity_matrix;
+    }
 
     /// This function composes the most suitable patches in a degraded signal given a reference signal.
+    /// Along with the similarity results, the frame offset of the degraded patch chosen for each reference patch is returned,
+    /// and the similarity matrix if it is recorded, see `set_record_similarity_matrix`.
     pub fn find_most_optimal_deg_patches(
         &self,
         ref_patches: &mut [Array2<f64>],
@@ -29,7 +48,7 @@ impl ComparisonPatchesSelector {
         spectrogram_data: &Array2<f64>,
         frame_duration: f64,
         search_window_radius: i32,
-    ) -> Result<Vec<PatchSimilarityResult>, VisqolError> {
+    ) -> Result<PatchMatches, VisqolError> {
         let num_frames_per_patch = ref_patches[0].ncols();
         let num_frames_in_deg_spectro = spectrogram_data.ncols();
         let patch_duration = frame_duration * num_frames_per_patch as f64;
@@ -43,7 +62,7 @@ impl ComparisonPatchesSelector {
         if num_patches == 0 {
             return Err(VisqolError::SignalsTooDifferent);
         } else if num_patches < ref_patch_indices.len() {
-            log::warn!(
+            log_warn!(
                 "Warning: Dropping {} (of {}) reference patches 
             due to the degraded file being misaligned or too short. If too many 
             patches are dropped, the score will be less meaningful.",
@@ -55,27 +74,41 @@ impl ComparisonPatchesSelector {
         // The vector to store the similarity results
         let mut best_deg_patches = Vec::<PatchSimilarityResult>::new();
         best_deg_patches.resize(num_patches, PatchSimilarityResult::default());
+        let mut best_deg_patch_offsets = vec![0usize; num_patches];
 
         let mut cumulative_similarity_dp =
             vec![vec![0.0f64; spectrogram_data.ncols()]; ref_patch_indices.len()];
         let mut backtrace = vec![vec![0usize; spectrogram_data.ncols()]; ref_patch_indices.len()];
 
-        let mut deg_patches = Vec::<Array2<f64>>::with_capacity(spectrogram_data.ncols());
-
-        for slide_offset in 0..spectrogram_data.ncols() {
-            deg_patches.push(Self::build_degraded_patch(
-                spectrogram_data,
-                slide_offset,
-                slide_offset + ref_patches[0].ncols(),
-            ));
-        }
+        let deg_patches = (0..spectrogram_data.ncols())
+            .map(|slide_offset| {
+                Self::build_degraded_patch(
+                    spectrogram_data,
+                    slide_offset,
+                    slide_offset + num_frames_per_patch,
+                )
+            })
+            .collect::<Vec<CowArray<'_, f64, Ix2>>>();
+
+        let patch_similarities = self.measure_patch_similarities(
+            ref_patches,
+            &deg_patches,
+            ref_patch_indices,
+            search_window,
+        );
+        let similarity_matrix = self.record_similarity_matrix.then(|| {
+            Self::similarity_matrix(
+                &patch_similarities,
+                ref_patch_indices,
+                search_window,
+                deg_patches.len(),
+            )
+        });
 
         // Attempt to get a good alignment with backtracking.
-        for (index, ref_patch) in ref_patches.iter_mut().enumerate() {
-            self.find_most_optimal_deg_patch(
-                spectrogram_data,
-                ref_patch,
-                &mut deg_patches,
+        for (index, similarities) in patch_similarities.iter().enumerate() {
+            Self::find_most_optimal_deg_patch(
+                similarities,
                 &mut cumulative_similarity_dp,
                 &mut backtrace,
                 ref_patch_indices,
@@ -97,16 +130,18 @@ impl ComparisonPatchesSelector {
         // The for loop is used to find the offset which maximizes the similarity
         // score across all the patches.
         // +1 for including last
-        for slide_offset in lower_limit..ref_patch_indices[last_index] + search_window as usize + 1
+        // The frame offset for degraded start patch cannot be more than the
+        // number of frames in the degraded spectrogram.
+        let upper_limit = (ref_patch_indices[last_index] + search_window as usize + 1)
+            .min(num_frames_in_deg_spectro);
+        for (slide_offset, &similarity) in cumulative_similarity_dp[last_index]
+            .iter()
+            .enumerate()
+            .take(upper_limit)
+            .skip(lower_limit)
         {
-            if slide_offset >= num_frames_in_deg_spectro {
-                // The frame offset for degraded start patch cannot be more than the
-                // number of frames in the degraded spectrogram.
-                break;
-            }
-
-            if cumulative_similarity_dp[last_index][slide_offset] > max_similarity_score {
-                max_similarity_score = cumulative_similarity_dp[last_index][slide_offset];
+            if similarity > max_similarity_score {
+                max_similarity_score = similarity;
                 last_offset = slide_offset;
             }
         }
@@ -114,17 +149,13 @@ impl ComparisonPatchesSelector {
         let mut patch_index: i32 = (num_patches - 1) as i32;
         while patch_index >= 0 {
             // This sets the reference and degraded patch start and end times.
-            let mut ref_patch = ref_patches[patch_index as usize].clone();
-
-            let mut deg_patch = Self::build_degraded_patch(
-                spectrogram_data,
-                last_offset,
-                last_offset + ref_patch.ncols(),
-            );
+            let ref_patch = &ref_patches[patch_index as usize];
+            let deg_patch = &deg_patches[last_offset];
 
             best_deg_patches[patch_index as usize] = self
                 .sim_comparator
-                .measure_patch_similarity(&mut ref_patch, &mut deg_patch);
+                .measure_patch_similarity(ref_patch, deg_patch);
+            best_deg_patch_offsets[patch_index as usize] = last_offset;
 
             // This condition is true only if no matching patch was found for the given
             // reference patch. In this case, the matched patch is essentially set to
@@ -151,43 +182,89 @@ impl ComparisonPatchesSelector {
 
             patch_index -= 1;
         }
-        Ok(best_deg_patches)
+        Ok((best_deg_patches, best_deg_patch_offsets, similarity_matrix))
     }
 
-    /// This function finds the most suitable patch in a degraded signal given a reference patch.
-    pub fn find_most_optimal_deg_patch(
+    /// Arranges the `similarities` of each reference patch to the degraded patches within its search window in a matrix,
+    /// with a row per reference patch and a column per offset of a degraded patch in the `num_frames` frames of the degraded spectrogram.
+    /// Offsets outside the search window of a reference patch, which were not compared, are NaN.
+    fn similarity_matrix(
+        similarities: &[Vec<f64>],
+        ref_patch_indices: &[usize],
+        search_window: i32,
+        num_frames: usize,
+    ) -> Array2<f64> {
+        let mut matrix = Array2::from_elem((similarities.len(), num_frames), f64::NAN);
+        for ((mut row, patch_similarities), &ref_frame_index) in
+            matrix.rows_mut().into_iter().zip(similarities).zip(ref_patch_indices)
+        {
+            let search_range = Self::search_range(ref_frame_index, search_window, num_frames);
+            for (offset, &similarity) in search_range.zip(patch_similarities) {
+                row[offset] = similarity;
+            }
+        }
+        matrix
+    }
+
+    /// Compares every reference patch with each degraded patch within its search window and returns the similarity scores per reference patch.
+    /// The comparisons are independent of each other and run in parallel if the `parallel` feature is enabled.
+    fn measure_patch_similarities(
         &self,
-        spectrogram_data: &Array2<f64>,
-        ref_patch: &mut Array2<f64>,
-        deg_patches: &mut [Array2<f64>],
+        ref_patches: &[Array2<f64>],
+        deg_patches: &[CowArray<'_, f64, Ix2>],
+        ref_patch_indices: &[usize],
+        search_window: i32,
+    ) -> Vec<Vec<f64>> {
+        let measure = |(ref_patch, &ref_frame_index): (&Array2<f64>, &usize)| {
+            Self::search_range(ref_frame_index, search_window, deg_patches.len())
+                .map(|slide_offset| {
+                    self.sim_comparator
+                        .measure_patch_similarity(ref_patch, &deg_patches[slide_offset])
+                        .similarity
+                })
+                .collect::<Vec<f64>>()
+        };
+
+        #[cfg(feature = "parallel")]
+        let similarities = ref_patches
+            .par_iter()
+            .zip(ref_patch_indices.par_iter())
+            .map(measure)
+            .collect();
+        #[cfg(not(feature = "parallel"))]
+        let similarities = ref_patches
+            .iter()
+            .zip(ref_patch_indices.iter())
+            .map(measure)
+            .collect();
+        similarities
+    }
+
+    /// Returns the offsets of the degraded patches that are compared with the reference patch starting at `ref_frame_index`.
+    fn search_range(ref_frame_index: usize, search_window: i32, num_frames: usize) -> Range<usize> {
+        let lower_limit = 0.max(ref_frame_index as i32 - search_window) as usize;
+        let upper_limit = (ref_frame_index + search_window as usize + 1).min(num_frames);
+        lower_limit..upper_limit.max(lower_limit)
+    }
+
+    /// This function finds the most suitable patch in a degraded signal given the similarities of a reference patch to the degraded patches within its search window.
+    pub fn find_most_optimal_deg_patch(
+        similarities: &[f64],
         cumulative_similarity_dp: &mut [Vec<f64>],
         backtrace: &mut [Vec<usize>],
         ref_patch_indices: &[usize],
         patch_index: usize,
         search_window: i32,
     ) {
-        let ref_frame_index = ref_patch_indices[patch_index];
-
-        let mut sim_result;
-
-        let mut slide_offset = ref_frame_index as i32 - search_window;
-        while slide_offset <= ref_frame_index as i32 + search_window {
-            if slide_offset < 0 {
-                // The degraded patch index cannot be less than 0.
-                slide_offset = 0;
-                continue;
-            }
-
-            if slide_offset == spectrogram_data.ncols() as i32 {
-                // The start of the degraded is past the end of the spectrogram, so
-                // nothing left to compare.
+        let search_range = Self::search_range(
+            ref_patch_indices[patch_index],
+            search_window,
+            cumulative_similarity_dp[patch_index].len(),
+        );
 
-                break;
-            }
-            let deg_patch = &mut deg_patches[slide_offset as usize];
-            sim_result = self
-                .sim_comparator
-                .measure_patch_similarity(ref_patch, deg_patch);
+        for (slide_offset, &patch_similarity) in search_range.zip(similarities) {
+            let slide_offset = slide_offset as i32;
+            let mut similarity = patch_similarity;
             let mut past_slide_offset = -1;
             let mut highest_sim = f64::MIN;
 
@@ -220,24 +297,20 @@ impl ComparisonPatchesSelector {
                     back_offset -= 1;
                 }
 
-                sim_result.similarity += highest_sim;
+                similarity += highest_sim;
 
                 // If the current reference patch experienced a packet loss, then the
                 // cumulative similarity score till the previous patch might be more and
                 // in that case no matching patch for the current reference patch is found
                 // in the degraded window.
 
-                if cumulative_similarity_dp[patch_index - 1][slide_offset as usize]
-                    > sim_result.similarity
-                {
-                    sim_result.similarity =
-                        cumulative_similarity_dp[patch_index - 1][slide_offset as usize];
+                if cumulative_similarity_dp[patch_index - 1][slide_offset as usize] > similarity {
+                    similarity = cumulative_similarity_dp[patch_index - 1][slide_offset as usize];
                     past_slide_offset = slide_offset;
                 }
             }
-            cumulative_similarity_dp[patch_index][slide_offset as usize] = sim_result.similarity;
+            cumulative_similarity_dp[patch_index][slide_offset as usize] = similarity;
             backtrace[patch_index][slide_offset as usize] = past_slide_offset as usize;
-            slide_offset += 1;
         }
     }
 
@@ -249,19 +322,18 @@ impl ComparisonPatchesSelector {
     ) -> usize {
         let mut num_patches = ref_patch_indices.len();
 
-        if num_patches != 0 {
-            while (ref_patch_indices[num_patches - 1] - (num_frames_per_patch / 2))
+        while num_patches > 0
+            && ref_patch_indices[num_patches - 1].saturating_sub(num_frames_per_patch / 2)
                 > num_frames_in_deg_spectro
-            {
-                num_patches -= 1;
-            }
+        {
+            num_patches -= 1;
         }
         num_patches
     }
 
     /// Given an `AudioSignal` and the desired start and end times in seconds, this function returns a copy of the segment in the audio signal ranging from `start_time` to `end_time`
     pub fn slice(in_signal: &AudioSignal, start_time: f64, end_time: f64) -> AudioSignal {
-        let start_index = ((start_time * in_signal.sample_rate as f64) as usize).max(0);
+        let start_index = (start_time * in_signal.sample_rate as f64) as usize;
         let end_index =
             ((end_time * in_signal.sample_rate as f64) as usize).min(in_signal.data_matrix.len());
 
@@ -294,17 +366,17 @@ impl ComparisonPatchesSelector {
         )
     }
 
+    /// Returns the frames `window_beginning..window_end` of `spectrogram_data`, zero-padding the frames beyond its end.
+    /// Patches within the spectrogram are returned as views, only zero-padded patches are copied.
     pub fn build_degraded_patch(
         spectrogram_data: &Array2<f64>,
         window_beginning: usize,
         window_end: usize,
-    ) -> Array2<f64> {
-        let first_real_frame = 0.max(window_beginning);
+    ) -> CowArray<'_, f64, Ix2> {
+        let first_real_frame = window_beginning;
         let last_real_frame = window_end.min(spectrogram_data.ncols());
 
-        let mut deg_patch = spectrogram_data
-            .slice(s![.., first_real_frame..last_real_frame])
-            .to_owned();
+        let deg_patch = spectrogram_data.slice(s![.., first_real_frame..last_real_frame]);
 
         if window_end > spectrogram_data.ncols() {
             let append_matrix = Array2::<f64>::zeros((
@@ -312,20 +384,24 @@ impl ComparisonPatchesSelector {
                 window_end - spectrogram_data.ncols(),
             ));
 
-            deg_patch = concatenate(Axis(1), &[deg_patch.view(), append_matrix.view()])
-                .expect("Could not zero-pad patch!");
+            return concatenate(Axis(1), &[deg_patch.view(), append_matrix.view()])
+                .expect("Could not zero-pad patch!")
+                .into();
         }
-        deg_patch
+        deg_patch.into()
     }
 
     /// Performs alignment on a per-patch level.
-    pub fn finely_align_and_recreate_patches<const NUM_BANDS: usize>(
+    /// `on_patch_aligned` is called with the number of patches processed so far after each patch.
+    pub fn finely_align_and_recreate_patches(
         &self,
+        spectrogram_config: &SpectrogramConfig,
         sim_results: &mut [PatchSimilarityResult],
         ref_signal: &AudioSignal,
         deg_signal: &AudioSignal,
         analysis_window: &AnalysisWindow,
-    ) -> Result<Vec<PatchSimilarityResult>, Box<dyn Error>> {
+        on_patch_aligned: &mut dyn FnMut(usize),
+    ) -> Result<Vec<PatchSimilarityResult>, VisqolError> {
         // Case: The patches are already matched.  Iterate over each pair.
         let mut realigned_results = Vec::<PatchSimilarityResult>::with_capacity(sim_results.len());
         realigned_results.resize(sim_results.len(), PatchSimilarityResult::default());
@@ -334,6 +410,7 @@ impl ComparisonPatchesSelector {
                 && result.deg_patch_start_time == 0.0
             {
                 realigned_results[i] = result.clone();
+                on_patch_aligned(i + 1);
                 continue;
             }
             // 1. The sim results keep track of the start and end points of each matched
@@ -358,9 +435,8 @@ impl ComparisonPatchesSelector {
             let new_ref_duration = ref_audio_aligned.get_duration();
             let new_deg_duration = deg_audio_aligned.get_duration();
             // 3. Compute a new spectrogram for the degraded audio.
-            let mut spect_builder = GammatoneSpectrogramBuilder::<NUM_BANDS>::new(
-                GammatoneFilterbank::new(constants::MINIMUM_FREQ),
-            );
+            let mut spect_builder =
+                GammatoneSpectrogramBuilder::new(spectrogram_config.filterbank());
             let mut ref_spectrogram = spect_builder.build(&ref_audio_aligned, analysis_window)?;
             let mut deg_spectrogram = spect_builder.build(&deg_audio_aligned, analysis_window)?;
             // 4. Recreate an aligned degraded patch from the new spectrogram.
@@ -373,7 +449,7 @@ impl ComparisonPatchesSelector {
 
             let mut new_sim_result = self
                 .sim_comparator
-                .measure_patch_similarity(&mut ref_spectrogram.data, &mut deg_spectrogram.data);
+                .measure_patch_similarity(&ref_spectrogram.data, &deg_spectrogram.data);
             // Compare to the old result and take the max.
             if new_sim_result.similarity < result.similarity {
                 realigned_results[i] = result.clone();
@@ -391,6 +467,7 @@ impl ComparisonPatchesSelector {
                     new_sim_result.deg_patch_start_time + new_deg_duration;
                 realigned_results[i] = new_sim_result;
             }
+            on_patch_aligned(i + 1);
         }
         Ok(realigned_results)
     }
@@ -399,6 +476,7 @@ impl ComparisonPatchesSelector {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use approx::assert_abs_diff_eq;
     use crate::{
         audio_signal::AudioSignal, image_patch_creator::ImagePatchCreator,
         neurogram_similiarity_index_measure::NeurogramSimiliarityIndexMeasure,
@@ -475,7 +553,7 @@ mod tests {
         let sim_measurer = NeurogramSimiliarityIndexMeasure::default();
         let selector = ComparisonPatchesSelector::new(sim_measurer);
 
-        let res = selector
+        let (res, _, _) = selector
             .find_most_optimal_deg_patches(
                 &mut ref_patches,
                 &mut patch_indices,
@@ -509,7 +587,7 @@ mod tests {
         let sim_measurer = NeurogramSimiliarityIndexMeasure::default();
         let selector = ComparisonPatchesSelector::new(sim_measurer);
 
-        let res = selector
+        let (res, _, _) = selector
             .find_most_optimal_deg_patches(
                 &mut ref_patches,
                 &mut patch_indices,
@@ -549,7 +627,7 @@ mod tests {
         let sim_measurer = NeurogramSimiliarityIndexMeasure::default();
         let selector = ComparisonPatchesSelector::new(sim_measurer);
 
-        let res = selector
+        let (res, _, _) = selector
             .find_most_optimal_deg_patches(
                 &mut ref_patches,
                 &mut patch_indices,
@@ -599,7 +677,7 @@ mod tests {
         let sim_measurer = NeurogramSimiliarityIndexMeasure::default();
         let selector = ComparisonPatchesSelector::new(sim_measurer);
 
-        let res = selector
+        let (res, offsets, _) = selector
             .find_most_optimal_deg_patches(
                 &mut ref_patches,
                 &mut patch_indices,
@@ -615,5 +693,107 @@ mod tests {
         assert_eq!(res[3].deg_patch_start_time, 14.0);
         assert_eq!(res[4].deg_patch_start_time, 16.0);
         assert_eq!(res[5].deg_patch_start_time, 22.0);
+        assert_eq!(offsets, vec![6, 8, 12, 14, 16, 22]);
+    }
+
+    #[test]
+    fn patch_similarities_match_serial_comparison() {
+        let ref_matrix = Array2::from_shape_fn((3, 40), |(row, col)| ((row * 7 + col) % 5) as f64);
+        let deg_matrix = Array2::from_shape_fn((3, 44), |(row, col)| ((row * 3 + col) % 4) as f64);
+        let patch_indices: Vec<usize> = vec![0, 8, 16, 24, 32];
+        let search_window = 8;
+
+        let patch_creator = ImagePatchCreator::new(4);
+        let ref_patches = patch_creator.create_patches_from_indices(&ref_matrix, &patch_indices);
+        let deg_patches = (0..deg_matrix.ncols())
+            .map(|offset| {
+                ComparisonPatchesSelector::build_degraded_patch(&deg_matrix, offset, offset + 4)
+            })
+            .collect::<Vec<CowArray<f64, Ix2>>>();
+
+        let sim_measurer = NeurogramSimiliarityIndexMeasure::default();
+        let selector = ComparisonPatchesSelector::new(sim_measurer);
+        let similarities = selector.measure_patch_similarities(
+            &ref_patches,
+            &deg_patches,
+            &patch_indices,
+            search_window,
+        );
+
+        for (index, ref_patch) in ref_patches.iter().enumerate() {
+            let search_range = ComparisonPatchesSelector::search_range(
+                patch_indices[index],
+                search_window,
+                deg_patches.len(),
+            );
+            let expected_similarities = search_range
+                .map(|offset| {
+                    NeurogramSimiliarityIndexMeasure::default()
+                        .measure_patch_similarity(ref_patch, &deg_patches[offset])
+                        .similarity
+                })
+                .collect::<Vec<f64>>();
+            assert_eq!(similarities[index], expected_similarities);
+        }
+    }
+
+    #[test]
+    fn degraded_patches_within_spectrogram_are_views() {
+        let deg_matrix = Array2::from_shape_fn((3, 10), |(row, col)| (row * 10 + col) as f64);
+
+        let inner_patch = ComparisonPatchesSelector::build_degraded_patch(&deg_matrix, 2, 6);
+        let padded_patch = ComparisonPatchesSelector::build_degraded_patch(&deg_matrix, 8, 12);
+
+        assert!(inner_patch.is_view());
+        assert_eq!(inner_patch, deg_matrix.slice(s![.., 2..6]));
+        assert!(padded_patch.is_owned());
+        assert_eq!(padded_patch.slice(s![.., ..2]), deg_matrix.slice(s![.., 8..]));
+        assert!(padded_patch.slice(s![.., 2..]).iter().all(|&value| value == 0.0));
+    }
+
+    #[test]
+    fn similarity_matrix_holds_compared_offsets() {
+        let ref_matrix = Array2::from_shape_fn((3, 40), |(row, col)| ((row * 7 + col) % 5) as f64);
+        let deg_matrix = ref_matrix.clone();
+        let mut patch_indices: Vec<usize> = vec![0, 8, 16, 24, 32];
+        let search_window = 1;
+
+        let patch_creator = ImagePatchCreator::new(4);
+        let mut ref_patches =
+            patch_creator.create_patches_from_indices(&ref_matrix, &patch_indices);
+        let mut selector =
+            ComparisonPatchesSelector::new(NeurogramSimiliarityIndexMeasure::default());
+        let (_, _, without_matrix) = selector
+            .find_most_optimal_deg_patches(
+                &mut ref_patches,
+                &mut patch_indices,
+                &deg_matrix,
+                1.0,
+                search_window,
+            )
+            .unwrap();
+        selector.set_record_similarity_matrix(true);
+        let (_, offsets, matrix) = selector
+            .find_most_optimal_deg_patches(
+                &mut ref_patches,
+                &mut patch_indices,
+                &deg_matrix,
+                1.0,
+                search_window,
+            )
+            .unwrap();
+
+        let matrix = matrix.unwrap();
+        assert!(without_matrix.is_none());
+        assert_eq!(matrix.dim(), (5, 40));
+        // The search window spans one patch of 4 frames on each side of the reference patch.
+        assert!(matrix[(1, 3)].is_nan());
+        assert!(!matrix[(1, 4)].is_nan());
+        assert!(!matrix[(1, 12)].is_nan());
+        assert!(matrix[(1, 13)].is_nan());
+        for (row, &offset) in offsets.iter().enumerate() {
+            assert_eq!(offset, patch_indices[row]);
+            assert_abs_diff_eq!(matrix[(row, offset)], 1.0, epsilon = 1e-9);
+        }
     }
 }
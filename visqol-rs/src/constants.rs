@@ -1,8 +1,26 @@
 pub const DURATION_MISMATCH_TOLERANCE: f64 = 1.0;
+/// Integrated loudness in LUFS which `NormalizationTarget::EbuR128` normalizes to, as recommended by EBU R128.
+pub const EBU_R128_TARGET_LUFS: f64 = -23.0;
 
 pub const NUM_BANDS_SPEECH: usize = 21;
 pub const NUM_BANDS_AUDIO: usize = 32;
+/// Number of bands of `Variant::Narrowband`, which spans 50 Hz to 4 kHz with about the band spacing of `NUM_BANDS_SPEECH` bands up to 8 kHz.
+pub const NUM_BANDS_NARROWBAND: usize = 17;
 pub const DEFAULT_WINDOW_SIZE: usize = 32;
+/// Minimum number of compared patches for a result to be considered reliable, which a few seconds of active speech or audio provide.
+pub const MIN_RELIABLE_PATCHES: usize = 3;
+/// Level in dB relative to full scale which no sample of a silent signal reaches, just above the least significant bit of 16 bit audio.
+pub const SILENCE_LEVEL_DB: f64 = -90.0;
+/// Magnitude of a sample, scaled from -1.0 to 1.0, at or above which it is considered clipped.
+pub const CLIPPING_LEVEL: f64 = 1.0;
+/// Fraction of clipped samples in the degraded signal above which `VisqolWarning::Clipping` is raised.
+pub const CLIPPING_WARNING_FRACTION: f64 = 0.001;
+/// Magnitude of the mean of a signal, scaled from -1.0 to 1.0, above which `VisqolWarning::DcOffset` is raised. This is -40 dBFS.
+pub const DC_OFFSET_WARNING_LEVEL: f64 = 0.01;
+/// Lowest MOS on the scale of the ViSQOL quality mappers, to which `VisqolManager::set_clamp_mos` clamps results.
+pub const MIN_MOS: f64 = 1.0;
+/// Highest MOS on the scale of the ViSQOL quality mappers, to which `VisqolManager::set_clamp_mos` clamps results.
+pub const MAX_MOS: f64 = 5.0;
 
 pub const PATCH_SIZE_AUDIO: usize = 30;
 pub const PATCH_SIZE_SPEECH: usize = 20;
@@ -11,3 +29,5 @@ pub const OVERLAP: f64 = 0.25;
 pub const WINDOW_DURATION: f64 = 0.08;
 
 pub const NUM_FILTER_CONDITIONS: usize = 2;
+/// RMS of a frame of 16 bit samples, relative to the peak of the reference signal, below which `VadPatchCreator` considers the frame silent.
+pub const VAD_RMS_THRESHOLD: f64 = 5000.0;
@@ -1,9 +1,13 @@
-use ndarray::{Array2, ShapeBuilder};
-
-/// Computes the convolution of `input_matrix` with `fir_filter`
-pub fn perform_valid_2d_conv_with_boundary(
+use ndarray::{Array2, ArrayBase, Data, Ix2};
+#[cfg(any(test, not(feature = "simd")))]
+use ndarray::ShapeBuilder;
+
+/// Computes the convolution of `input_matrix` with `fir_filter`.
+/// With the `simd` feature, the NSIM accumulates the convolutions in `local_statistics` instead.
+#[cfg(any(test, not(feature = "simd")))]
+pub fn perform_valid_2d_conv_with_boundary<S: Data<Elem = f64>>(
     fir_filter: &Array2<f64>,
-    input_matrix: &mut Array2<f64>,
+    input_matrix: &ArrayBase<S, Ix2>,
 ) -> Array2<f64> {
     let padded_matrix = add_matrix_boundary(input_matrix);
     let padded_flattened_matrix = flatten_matrix(&padded_matrix);
@@ -38,6 +42,7 @@ pub fn perform_valid_2d_conv_with_boundary(
     out_matrix
 }
 
+#[cfg(any(test, not(feature = "simd")))]
 fn flatten_matrix(input_matrix: &Array2<f64>) -> Vec<f64> {
     let mut res = Vec::<f64>::new();
     for i in 0..input_matrix.nrows() {
@@ -49,7 +54,7 @@ fn flatten_matrix(input_matrix: &Array2<f64>) -> Vec<f64> {
 }
 
 /// Compute zero-padded matrix and fill zero-padded boundaries with the adjacent non-zero rows and columns
-pub fn add_matrix_boundary(input_matrix: &mut Array2<f64>) -> Array2<f64> {
+pub fn add_matrix_boundary<S: Data<Elem = f64>>(input_matrix: &ArrayBase<S, Ix2>) -> Array2<f64> {
     let mut output_matrix = copy_matrix_within_padding(input_matrix, 1, 1, 1, 1);
 
     for i in 0..output_matrix.ncols() {
@@ -67,8 +72,8 @@ pub fn add_matrix_boundary(input_matrix: &mut Array2<f64>) -> Array2<f64> {
 }
 
 /// Returns a copy of `input matrix` which is zero-padded by the specified amounts.
-pub fn copy_matrix_within_padding(
-    input_matrix: &Array2<f64>,
+pub fn copy_matrix_within_padding<S: Data<Elem = f64>>(
+    input_matrix: &ArrayBase<S, Ix2>,
     row_prepad_amount: usize,
     row_postpad_amount: usize,
     col_prepad_amount: usize,
@@ -114,9 +119,9 @@ mod tests {
             43.6190, 41.0119, 40.4244, 41.5932, 43.6027, 42.6204, 43.0624, 42.2610, 42.4725,
             43.4258, 42.9079,
         ];
-        let mut matrix = Array::from_shape_vec((5, 4).f(), m).unwrap();
+        let matrix = Array::from_shape_vec((5, 4).f(), m).unwrap();
 
-        let result = perform_valid_2d_conv_with_boundary(&window, &mut matrix);
+        let result = perform_valid_2d_conv_with_boundary(&window, &matrix);
 
         let r = vec![
             40.6634, 42.8407, 40.6395, 41.0129, 41.5407, 42.4677, 44.2760, 44.2031, 41.2263,
@@ -140,8 +145,8 @@ mod tests {
             43.6190, 41.0119, 40.4244, 41.5932, 43.6027, 42.6204, 43.0624, 42.2610, 42.4725,
             43.4258, 42.9079,
         ];
-        let mut matrix = Array::from_shape_vec((5, 4).f(), m).unwrap();
-        let result = add_matrix_boundary(&mut matrix);
+        let matrix = Array::from_shape_vec((5, 4).f(), m).unwrap();
+        let result = add_matrix_boundary(&matrix);
 
         let mut r = Vec::new();
         for i in 0..result.dim().0 {
@@ -127,6 +127,7 @@ mod tests {
         let result = calculate_best_lag(
             ref_signal.data_matrix.as_slice().unwrap(),
             deg_signal.data_matrix.as_slice().unwrap(),
+            None,
         )
         .unwrap();
 
@@ -11,24 +11,25 @@ const MIN_BW: f64 = 24.7f64;
 const ERB_ORDER: f64 = 1.0;
 
 /// Computes the coefficients for an ERB filterbank.
-pub fn make_filters<const NUM_BANDS: usize>(
+pub fn make_filters(
+    num_bands: usize,
     sample_rate: usize,
     low_freq: f64,
     high_freq: f64,
 ) -> (Array2<f64>, Vec<f64>) {
     let mut high_freq = high_freq;
     if (high_freq > sample_rate as f64 / 2.0) {
-        log::warn!("EquivalentRectangularBandwidth::MakeFilters: high_freq >= (sample_rate / 2), for sample_rate={}, high_freq={}. Falling back to (sample_rate / 2)", sample_rate, high_freq);
+        log_warn!("EquivalentRectangularBandwidth::MakeFilters: high_freq >= (sample_rate / 2), for sample_rate={}, high_freq={}. Falling back to (sample_rate / 2)", sample_rate, high_freq);
         high_freq = sample_rate as f64 / 2.0;
     }
 
     let pi = std::f64::consts::PI;
     let cf = float_vec_to_real_valued_complex_vec(
-        &calculate_uniform_center_freqs::<{ NUM_BANDS }>(low_freq, high_freq),
+        &calculate_uniform_center_freqs(num_bands, low_freq, high_freq),
     );
 
-    let mut B = [Complex64::zero(); NUM_BANDS];
-    let mut B1 = [Complex64::zero(); NUM_BANDS];
+    let mut B = vec![Complex64::zero(); num_bands];
+    let mut B1 = vec![Complex64::zero(); num_bands];
 
     for (B_element, cf_element) in B.iter_mut().zip(&cf) {
         let erb =
@@ -37,13 +38,13 @@ pub fn make_filters<const NUM_BANDS: usize>(
     }
     let t = 1.0 / sample_rate as f64;
 
-    let mut exp_bt = [Complex64::zero(); NUM_BANDS];
+    let mut exp_bt = vec![Complex64::zero(); num_bands];
 
     for (exp, b_element) in exp_bt.iter_mut().zip(&B) {
         *exp = (*b_element * t).exp();
     }
 
-    let mut B1 = [Complex64::zero(); NUM_BANDS];
+    let mut B1 = vec![Complex64::zero(); num_bands];
     for i in 0..B1.len() {
         B1[i] = -2.0 * (2.0 * cf[i] * pi * t).cos() / exp_bt[i];
     }
@@ -185,12 +186,12 @@ pub fn make_filters<const NUM_BANDS: usize>(
         gain[i] = ((x1[i] * x2[i] * x3[i] * x4[i]) / x5[i].powf(4.0)).norm();
     }
 
-    let A0 = [t; NUM_BANDS];
-    let A2 = [0.0f64; NUM_BANDS];
-    let B0 = [1.0f64; NUM_BANDS];
-    let mut vf_coeffs = ndarray::Array2::<f64>::zeros((NUM_BANDS, 10));
+    let A0 = vec![t; num_bands];
+    let A2 = vec![0.0f64; num_bands];
+    let B0 = vec![1.0f64; num_bands];
+    let mut vf_coeffs = ndarray::Array2::<f64>::zeros((num_bands, 10));
     // Setup matrix
-    for i in 0..NUM_BANDS {
+    for i in 0..num_bands {
         vf_coeffs[(i, 0)] = A0[i];
         vf_coeffs[(i, 1)] = A11[i].re;
         vf_coeffs[(i, 2)] = A12[i].re;
@@ -207,18 +208,15 @@ pub fn make_filters<const NUM_BANDS: usize>(
 }
 
 /// Given a lower frequency boundary, a higher frequency boundary and the number of bands, this function calculates the center frequencies on an ERB scale.
-fn calculate_uniform_center_freqs<const NUM_BANDS: usize>(
-    low_freq: f64,
-    high_freq: f64,
-) -> [f64; NUM_BANDS] {
+fn calculate_uniform_center_freqs(num_bands: usize, low_freq: f64, high_freq: f64) -> Vec<f64> {
     // Glasberg and Moore Parameters
 
     let a = -(EAR_Q * MIN_BW);
     let b = -((high_freq + EAR_Q * MIN_BW).ln());
     let c = (low_freq + EAR_Q * MIN_BW).ln();
     let d = high_freq + EAR_Q * MIN_BW;
-    let e = (b + c) / NUM_BANDS as f64;
-    let mut coefficients = [0.0; NUM_BANDS];
+    let e = (b + c) / num_bands as f64;
+    let mut coefficients = vec![0.0; num_bands];
     for (i, coefficient) in coefficients.iter_mut().enumerate() {
         let f = ((i as f64 + 1.0) * e).exp() * d;
         *coefficient = a + f;
@@ -238,7 +236,7 @@ mod tests {
         const NUM_BANDS: usize = 32;
         let min_freq = 50.0f64;
 
-        let (mut filter_coeffs, _) = make_filters::<NUM_BANDS>(fs, min_freq, fs as f64 / 2.0);
+        let (mut filter_coeffs, _) = make_filters(NUM_BANDS, fs, min_freq, fs as f64 / 2.0);
 
         let expected_filter_coefficients = vec![
             2.08333e-05,
@@ -2,15 +2,25 @@ use crate::audio_utils;
 use crate::math_utils;
 use num::complex::Complex64;
 use num::Zero;
-use rustfft::FftPlanner;
+use rustfft::{Fft, FftPlanner};
+use std::sync::{Arc, Mutex, OnceLock};
 
 // Constants
 const MIN_FFT_SIZE: usize = 32;
 
+/// Returns the planner shared by all fft managers. It caches the plans of each fft size, so that scoring many files,
+/// e.g. with `VisqolManager::run_batch`, plans each size only once instead of once per alignment and envelope.
+fn shared_planner() -> &'static Mutex<FftPlanner<f64>> {
+    static PLANNER: OnceLock<Mutex<FftPlanner<f64>>> = OnceLock::new();
+    PLANNER.get_or_init(|| Mutex::new(FftPlanner::new()))
+}
+
 /// Wrapper around the `rustfft` library to perform basic fft operations.
 pub struct FftManager {
-    /// Planner to perform fft operations
-    planner: FftPlanner<f64>,
+    /// Plan of the forward fft, shared with all managers of the same size
+    forward: Arc<dyn Fft<f64>>,
+    /// Plan of the inverse fft, shared with all managers of the same size
+    inverse: Arc<dyn Fft<f64>>,
     /// Length of the fft
     pub fft_size: usize,
     /// Scale factor to apply after inverse fft
@@ -23,9 +33,14 @@ impl FftManager {
     /// Creates a new fft manager, computes internal variables from `samples_per_channel`
     pub fn new(samples_per_channel: usize) -> Self {
         let fft_size = math_utils::next_pow_two(samples_per_channel).max(MIN_FFT_SIZE);
+        // The planner is only used to look up or create plans, so a poisoned lock leaves it in a valid state.
+        let mut planner = shared_planner()
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
 
         Self {
-            planner: FftPlanner::<f64>::new(),
+            forward: planner.plan_fft_forward(fft_size),
+            inverse: planner.plan_fft_inverse(fft_size),
             fft_size,
             samples_per_channel,
             inverse_fft_scale: 1.0f64 / (fft_size as f64),
@@ -38,7 +53,7 @@ impl FftManager {
         time_channel: &mut Vec<f64>,
         freq_channel: &mut [Complex64],
     ) {
-        let real_to_complex = self.planner.plan_fft_forward(self.fft_size);
+        let real_to_complex = &self.forward;
         if time_channel.len() == self.fft_size {
             let mut complex_time_domain =
                 audio_utils::float_vec_to_real_valued_complex_vec(time_channel);
@@ -69,7 +84,7 @@ impl FftManager {
         freq_channel: &mut [Complex64],
         time_channel: &mut Vec<f64>,
     ) {
-        let complex_to_real = self.planner.plan_fft_inverse(self.fft_size);
+        let complex_to_real = &self.inverse;
 
         if time_channel.len() == self.fft_size {
             let mut scratch_buffer =
@@ -102,3 +117,19 @@ impl FftManager {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans_are_shared_between_managers() {
+        let first = FftManager::new(1000);
+        let second = FftManager::new(1024);
+        let other_size = FftManager::new(2048);
+
+        assert!(Arc::ptr_eq(&first.forward, &second.forward));
+        assert!(Arc::ptr_eq(&first.inverse, &second.inverse));
+        assert!(!Arc::ptr_eq(&first.forward, &other_size.forward));
+    }
+}
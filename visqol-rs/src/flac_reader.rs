@@ -0,0 +1,62 @@
+use crate::audio_utils::AudioInfo;
+use crate::math_utils;
+use crate::visqol_error::VisqolError;
+use claxon::FlacReader;
+use std::io::Read;
+/// Represents the metadata and contents of a flac file.
+/// Simple wrapper around the `claxon` library.
+pub struct FlacFile {
+    /// The number of channels in the flac file
+    pub num_channels: u16,
+    /// The sample rate of the flac file
+    pub sample_rate: u32,
+    /// The interleaved samples in the flac file, scaled from -1.0 to 1.0.
+    pub samples: Vec<f64>,
+}
+
+impl FlacFile {
+    /// given a `reader` providing the contents of a flac file, the decoded contents of the flac file are returned.
+    /// 16 bit and 24 bit integer samples are supported, any other decoding errors are reported by `claxon`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, VisqolError> {
+        let mut reader = FlacReader::new(reader)?;
+        let stream_info = reader.streaminfo();
+
+        let samples = match stream_info.bits_per_sample {
+            16 => math_utils::normalize_int16_to_double(
+                &reader
+                    .samples()
+                    .map(|sample| sample.map(|s| s as i16))
+                    .collect::<Result<Vec<i16>, _>>()?,
+            ),
+            24 => math_utils::normalize_int24_to_double(
+                &reader.samples().collect::<Result<Vec<i32>, _>>()?,
+            ),
+            bits_per_sample => {
+                return Err(VisqolError::InvalidBitsPerSample {
+                    bits_per_sample: bits_per_sample as u16,
+                })
+            }
+        };
+
+        Ok(Self {
+            num_channels: stream_info.channels as u16,
+            sample_rate: stream_info.sample_rate,
+            samples,
+        })
+    }
+
+    /// Reads the format from the stream info of the flac file provided by `reader` without decoding any samples, see `audio_utils::probe`.
+    /// The duration is unknown if the stream info does not store the number of samples.
+    pub fn probe<R: Read>(reader: R) -> Result<AudioInfo, VisqolError> {
+        let stream_info = FlacReader::new(reader)?.streaminfo();
+
+        Ok(AudioInfo {
+            sample_rate: stream_info.sample_rate,
+            channels: stream_info.channels as u16,
+            bit_depth: Some(stream_info.bits_per_sample as u16),
+            duration: stream_info
+                .samples
+                .map(|num_frames| num_frames as f64 / stream_info.sample_rate as f64),
+        })
+    }
+}
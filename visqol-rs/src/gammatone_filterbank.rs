@@ -2,13 +2,16 @@ use crate::{constants, signal_filter};
 
 /// Bank of gammatone filters on each frame of a time domain signal to construct a spectrogram representation.
 /// This implementation is fixed to a 4th order filterbank.
-pub struct GammatoneFilterbank<const NUM_BANDS: usize> {
+pub struct GammatoneFilterbank {
+    pub num_bands: usize,
     pub min_freq: f64,
+    /// Upper bound of the center frequencies. If `None`, it is chosen by the spectrogram builder.
+    pub max_freq: Option<f64>,
 
-    filter_conditions_1: [[f64; constants::NUM_FILTER_CONDITIONS]; NUM_BANDS],
-    filter_conditions_2: [[f64; constants::NUM_FILTER_CONDITIONS]; NUM_BANDS],
-    filter_conditions_3: [[f64; constants::NUM_FILTER_CONDITIONS]; NUM_BANDS],
-    filter_conditions_4: [[f64; constants::NUM_FILTER_CONDITIONS]; NUM_BANDS],
+    filter_conditions_1: Vec<[f64; constants::NUM_FILTER_CONDITIONS]>,
+    filter_conditions_2: Vec<[f64; constants::NUM_FILTER_CONDITIONS]>,
+    filter_conditions_3: Vec<[f64; constants::NUM_FILTER_CONDITIONS]>,
+    filter_conditions_4: Vec<[f64; constants::NUM_FILTER_CONDITIONS]>,
 
     filter_coeff_a0: Vec<f64>,
     filter_coeff_a11: Vec<f64>,
@@ -22,15 +25,17 @@ pub struct GammatoneFilterbank<const NUM_BANDS: usize> {
     filter_coeff_gain: Vec<f64>,
 }
 
-impl<const NUM_BANDS: usize> GammatoneFilterbank<NUM_BANDS> {
-    /// Creates a new gammatone filterbank with the desired number of frequency bands and the minimum frequency.
-    pub fn new(min_freq: f64) -> Self {
+impl GammatoneFilterbank {
+    /// Creates a new gammatone filterbank with the desired number of frequency bands whose center frequencies are spread from `min_freq` to `max_freq`.
+    pub fn new(num_bands: usize, min_freq: f64, max_freq: Option<f64>) -> Self {
         Self {
+            num_bands,
             min_freq,
-            filter_conditions_1: [[0.0; constants::NUM_FILTER_CONDITIONS]; NUM_BANDS],
-            filter_conditions_2: [[0.0; constants::NUM_FILTER_CONDITIONS]; NUM_BANDS],
-            filter_conditions_3: [[0.0; constants::NUM_FILTER_CONDITIONS]; NUM_BANDS],
-            filter_conditions_4: [[0.0; constants::NUM_FILTER_CONDITIONS]; NUM_BANDS],
+            max_freq,
+            filter_conditions_1: vec![[0.0; constants::NUM_FILTER_CONDITIONS]; num_bands],
+            filter_conditions_2: vec![[0.0; constants::NUM_FILTER_CONDITIONS]; num_bands],
+            filter_conditions_3: vec![[0.0; constants::NUM_FILTER_CONDITIONS]; num_bands],
+            filter_conditions_4: vec![[0.0; constants::NUM_FILTER_CONDITIONS]; num_bands],
             filter_coeff_a0: Vec::new(),
             filter_coeff_a11: Vec::new(),
             filter_coeff_a12: Vec::new(),
@@ -46,10 +51,10 @@ impl<const NUM_BANDS: usize> GammatoneFilterbank<NUM_BANDS> {
 
     /// Sets all internal states of the filterbank to 0.
     pub fn reset_filter_conditions(&mut self) {
-        self.filter_conditions_1 = [[0.0, 0.0]; NUM_BANDS];
-        self.filter_conditions_2 = [[0.0, 0.0]; NUM_BANDS];
-        self.filter_conditions_3 = [[0.0, 0.0]; NUM_BANDS];
-        self.filter_conditions_4 = [[0.0, 0.0]; NUM_BANDS];
+        self.filter_conditions_1 = vec![[0.0, 0.0]; self.num_bands];
+        self.filter_conditions_2 = vec![[0.0, 0.0]; self.num_bands];
+        self.filter_conditions_3 = vec![[0.0, 0.0]; self.num_bands];
+        self.filter_conditions_4 = vec![[0.0, 0.0]; self.num_bands];
     }
 
     /// Populates the filter coefficients with `filter_coeffs`.
@@ -75,8 +80,8 @@ impl<const NUM_BANDS: usize> GammatoneFilterbank<NUM_BANDS> {
         let mut a4 = [0.0; 3];
         let mut b = [0.0; 3];
 
-        let mut output = ndarray::Array2::<f64>::zeros((NUM_BANDS, input_signal.len()));
-        for band in 0..NUM_BANDS {
+        let mut output = ndarray::Array2::<f64>::zeros((self.num_bands, input_signal.len()));
+        for band in 0..self.num_bands {
             a1[0] = self.filter_coeff_a0[band] / self.filter_coeff_gain[band];
             a1[1] = self.filter_coeff_a11[band] / self.filter_coeff_gain[band];
             a1[2] = self.filter_coeff_a2[band] / self.filter_coeff_gain[band];
@@ -156,7 +161,8 @@ mod tests {
 
         let ten_samples = vec![0.2, 0.4, 0.6, 0.8, 0.9, 0.1, 0.3, 0.5, 0.7, 0.9];
 
-        let (mut filter_coeffs, _) = equivalent_rectangular_bandwidth::make_filters::<NUM_BANDS>(
+        let (mut filter_coeffs, _) = equivalent_rectangular_bandwidth::make_filters(
+            NUM_BANDS,
             fs,
             min_freq,
             fs as f64 / 2.0,
@@ -167,7 +173,7 @@ mod tests {
         let epsilon = 0.0001;
 
         // Check if filtering works as intended.
-        let mut filterbank = GammatoneFilterbank::<{ NUM_BANDS }>::new(min_freq);
+        let mut filterbank = GammatoneFilterbank::new(NUM_BANDS, min_freq, None);
         filterbank.reset_filter_conditions();
         filterbank.set_filter_coefficients(&filter_coeffs);
 
@@ -1,18 +1,18 @@
-use crate::analysis_window::AnalysisWindow;
+use crate::analysis_window::{AnalysisWindow, WindowFunction};
 use crate::constants::NUM_BANDS_SPEECH;
 use crate::equivalent_rectangular_bandwidth;
 use crate::gammatone_filterbank::GammatoneFilterbank;
 use crate::spectrogram::Spectrogram;
 use crate::spectrogram_builder::SpectrogramBuilder;
 use crate::{audio_signal::AudioSignal, visqol_error::VisqolError};
-use ndarray::{Array2, Axis};
+use ndarray::{Array1, Array2, Axis};
 
 /// Produces a frequency domain representation from a time domain signal using a gammatone filterbank.
-pub struct GammatoneSpectrogramBuilder<const NUM_BANDS: usize> {
-    filter_bank: GammatoneFilterbank<NUM_BANDS>,
+pub struct GammatoneSpectrogramBuilder {
+    filter_bank: GammatoneFilterbank,
 }
 
-impl<const NUM_BANDS: usize> SpectrogramBuilder for GammatoneSpectrogramBuilder<NUM_BANDS> {
+impl SpectrogramBuilder for GammatoneSpectrogramBuilder {
     fn build(
         &mut self,
         signal: &AudioSignal,
@@ -20,24 +20,22 @@ impl<const NUM_BANDS: usize> SpectrogramBuilder for GammatoneSpectrogramBuilder<
     ) -> Result<Spectrogram, VisqolError> {
         let time_domain_signal = &signal.data_matrix;
         let sample_rate = signal.sample_rate;
-        let max_freq = if NUM_BANDS == NUM_BANDS_SPEECH {
-            Self::SPEECH_MODE_MAX_FREQ
-        } else {
-            sample_rate / 2
-        };
+        let num_bands = self.filter_bank.num_bands;
+        let max_freq = self.max_freq(sample_rate);
 
         // get gammatone coefficients
         let (mut filter_coeffs, mut center_freqs) =
-            equivalent_rectangular_bandwidth::make_filters::<NUM_BANDS>(
+            equivalent_rectangular_bandwidth::make_filters(
+                num_bands,
                 sample_rate as usize,
                 self.filter_bank.min_freq,
-                max_freq as f64,
+                max_freq,
             );
         filter_coeffs.invert_axis(Axis(0));
         self.filter_bank.set_filter_coefficients(&filter_coeffs);
         self.filter_bank.reset_filter_conditions();
 
-        let hop_size = (window.size as f64 * window.overlap) as usize;
+        let hop_size = window.hop_size();
 
         if time_domain_signal.len() < window.size {
             return Err(VisqolError::TooFewSamples {
@@ -47,7 +45,11 @@ impl<const NUM_BANDS: usize> SpectrogramBuilder for GammatoneSpectrogramBuilder<
         }
 
         let num_cols = 1 + ((time_domain_signal.len() - window.size) / hop_size);
-        let mut out_matrix = Array2::<f64>::zeros((NUM_BANDS, num_cols));
+        let mut out_matrix = Array2::<f64>::zeros((num_bands, num_cols));
+
+        // The rectangular window leaves the frames unchanged, so applying it is skipped.
+        let window_coefficients = (window.window_function != WindowFunction::Rectangular)
+            .then(|| Array1::from(window.window_function.coefficients(window.size)));
 
         for (index, frame) in time_domain_signal
             .windows(window.size)
@@ -56,9 +58,13 @@ impl<const NUM_BANDS: usize> SpectrogramBuilder for GammatoneSpectrogramBuilder<
             .enumerate()
         {
             self.filter_bank.reset_filter_conditions();
+            let windowed_frame = window_coefficients
+                .as_ref()
+                .map(|coefficients| &frame * coefficients);
             let mut filtered_signal = self.filter_bank.apply_filter(
-                frame
-                    .as_slice()
+                windowed_frame
+                    .as_ref()
+                    .map_or(frame.as_slice(), |windowed_frame| windowed_frame.as_slice())
                     .expect("Failed to convert audio frame to slice"),
             );
 
@@ -85,12 +91,38 @@ impl<const NUM_BANDS: usize> SpectrogramBuilder for GammatoneSpectrogramBuilder<
     }
 }
 
-impl<const NUM_BANDS: usize> GammatoneSpectrogramBuilder<NUM_BANDS> {
+impl GammatoneSpectrogramBuilder {
     const SPEECH_MODE_MAX_FREQ: u32 = 8000;
 
     /// Creates a new gammatone spectrogram builder with the given gammatone filterbank.
-    /// If `use_speech_mode` is set to `true`, the maximum frequency is determined to be 8000 Hz.
-    pub fn new(filter_bank: GammatoneFilterbank<NUM_BANDS>) -> Self { Self { filter_bank } }
+    /// Unless the filterbank sets a maximum frequency, it is 8000 Hz for `NUM_BANDS_SPEECH` bands and half the sample rate otherwise.
+    pub fn new(filter_bank: GammatoneFilterbank) -> Self { Self { filter_bank } }
+
+    /// Returns the sorted center frequencies of the spectrograms built for signals sampled at `sample_rate`, without filtering a signal.
+    pub fn center_freq_bands(&self, sample_rate: u32) -> Vec<f64> {
+        let (_, mut center_freqs) = equivalent_rectangular_bandwidth::make_filters(
+            self.filter_bank.num_bands,
+            sample_rate as usize,
+            self.filter_bank.min_freq,
+            self.max_freq(sample_rate),
+        );
+        center_freqs.sort_by(|a, b| {
+            a.partial_cmp(b)
+                .expect("Failed to sort center frequencies!")
+        });
+        center_freqs
+    }
+
+    /// Returns the upper bound of the center frequencies for signals sampled at `sample_rate`.
+    fn max_freq(&self, sample_rate: u32) -> f64 {
+        match self.filter_bank.max_freq {
+            Some(max_freq) => max_freq,
+            None if self.filter_bank.num_bands == NUM_BANDS_SPEECH => {
+                Self::SPEECH_MODE_MAX_FREQ as f64
+            }
+            None => (sample_rate / 2) as f64,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -114,11 +146,10 @@ mod tests {
             "test_data/conformance_testdata_subset/contrabassoon48_stereo.wav",
         )
         .unwrap();
-        let filter_bank = GammatoneFilterbank::<{ NUM_BANDS }>::new(MINIMUM_FREQ);
+        let filter_bank = GammatoneFilterbank::new(NUM_BANDS, MINIMUM_FREQ, None);
         let window = AnalysisWindow::new(signal_ref.sample_rate, OVERLAP, 0.08);
 
-        let mut spectro_builder: GammatoneSpectrogramBuilder<NUM_BANDS> =
-            GammatoneSpectrogramBuilder::new(filter_bank);
+        let mut spectro_builder = GammatoneSpectrogramBuilder::new(filter_bank);
         let spectrogram_ref = spectro_builder.build(&signal_ref, &window).unwrap();
 
         // Check 1st element
@@ -126,4 +157,50 @@ mod tests {
         // Check dimensions
         assert_eq!(spectrogram_ref.data.ncols(), REF_SPECTRO_NUM_COLS);
     }
+
+    #[test]
+    fn window_function_is_applied_to_frames() {
+        let samples = (0..8000)
+            .map(|n| (n as f64 * 0.3).sin())
+            .collect::<Vec<f64>>();
+        let signal = AudioSignal::new(samples, 16000);
+        let build = |window: AnalysisWindow| {
+            GammatoneSpectrogramBuilder::new(GammatoneFilterbank::new(32, 50.0, None))
+                .build(&signal, &window)
+                .unwrap()
+        };
+
+        let rectangular = build(AnalysisWindow::new(16000, 0.25, 0.08));
+        let hann = build(
+            AnalysisWindow::new(16000, 0.25, 0.08).with_window_function(WindowFunction::Hann),
+        );
+
+        assert_eq!(rectangular.data.dim(), hann.data.dim());
+        assert_ne!(rectangular.data, hann.data);
+    }
+
+    #[test]
+    fn frequency_range_bounds_center_frequencies() {
+        let samples = (0..4000)
+            .map(|n| (n as f64 * 0.3).sin())
+            .collect::<Vec<f64>>();
+        let signal = AudioSignal::new(samples, 16000);
+        let window = AnalysisWindow::new(16000, 0.25, 0.08);
+        let center_freqs = |min_freq: f64, max_freq: Option<f64>| {
+            GammatoneSpectrogramBuilder::new(GammatoneFilterbank::new(32, min_freq, max_freq))
+                .build(&signal, &window)
+                .unwrap()
+                .center_freq_bands
+        };
+
+        let default_freqs = center_freqs(50.0, None);
+        let limited_freqs = center_freqs(200.0, Some(4000.0));
+
+        assert_eq!(default_freqs, center_freqs(50.0, Some(8000.0)));
+        assert_eq!(limited_freqs.len(), default_freqs.len());
+        assert!(limited_freqs[0] > default_freqs[0]);
+        assert!(limited_freqs[31] < default_freqs[31]);
+        assert_abs_diff_eq!(limited_freqs[0], 200.0, epsilon = 1e-6);
+        assert!(limited_freqs[31] < 4000.0);
+    }
 }
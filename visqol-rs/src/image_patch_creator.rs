@@ -1,12 +1,15 @@
 use crate::{
-    analysis_window::AnalysisWindow, audio_signal::AudioSignal, patch_creator::PatchCreator,
+    analysis_window::AnalysisWindow,
+    audio_signal::AudioSignal,
+    patch_creator::{Patch, PatchCreator},
     visqol_error::VisqolError,
 };
-use ndarray::{s, Array2};
+use ndarray::Array2;
 
 /// Creates patches from a spectrogram by segmenting it into equally-sized matrices.
 pub struct ImagePatchCreator {
     patch_size: usize,
+    stride: usize,
 }
 
 impl PatchCreator for ImagePatchCreator {
@@ -19,29 +22,41 @@ impl PatchCreator for ImagePatchCreator {
         self.create_ref_patch_indices_from_spectrogram(spectrogram)
     }
 
-    fn create_patches_from_indices(
-        &self,
-        spectrogram: &Array2<f64>,
-        patch_indices: &[usize],
-    ) -> Vec<Array2<f64>> {
-        let mut end_col: usize;
-
-        let mut patches = Vec::<Array2<f64>>::new();
+    fn patches<'a>(
+        &'a self,
+        spectrogram: &'a Array2<f64>,
+        patch_indices: &'a [usize],
+    ) -> Box<dyn Iterator<Item = Patch> + 'a> {
+        Box::new(patch_indices.iter().map(move |&start_frame| {
+            Patch::from_spectrogram(spectrogram, start_frame, self.patch_size)
+        }))
+    }
 
-        let mut patch: Array2<f64>;
+    fn patch_size(&self) -> Option<usize> { Some(self.patch_size) }
+}
 
-        for start_col in patch_indices {
-            end_col = start_col + self.patch_size;
-            patch = spectrogram.slice(s![.., *start_col..end_col]).to_owned();
-            patches.push(patch);
+impl ImagePatchCreator {
+    /// Returns a new `ImagePatchCreator` with the desired patch size, whose patches do not overlap.
+    pub fn new(patch_size: usize) -> Self {
+        Self {
+            patch_size,
+            stride: patch_size,
         }
-        patches
     }
-}
 
-impl ImagePatchCreator {
-    /// Returns a new `ImagePatchCreator` with the desired patch size.
-    pub fn new(patch_size: usize) -> Self { Self { patch_size } }
+    /// Starts a patch every `stride` frames instead of every `patch_size` frames, so that consecutive patches overlap if `stride` is smaller.
+    /// Overlapping patches give a finer time resolution, but each of them is compared with the degraded spectrogram, which takes longer.
+    /// Returns `VisqolError::InvalidPatchStride` unless `stride` is between 1 and the patch size.
+    pub fn with_stride(mut self, stride: usize) -> Result<Self, VisqolError> {
+        if stride == 0 || stride > self.patch_size {
+            return Err(VisqolError::InvalidPatchStride {
+                stride,
+                patch_size: self.patch_size,
+            });
+        }
+        self.stride = stride;
+        Ok(self)
+    }
 
     /// Computes the start indices of each patch by segmenting the entire spectrogram into equally-sized patches.
     fn create_ref_patch_indices_from_spectrogram(
@@ -64,11 +79,58 @@ impl ImagePatchCreator {
             init_patch_index + 1
         };
 
-        let mut ref_patch_indices = Vec::<usize>::with_capacity(spectrum_length / self.patch_size);
+        let mut ref_patch_indices = Vec::<usize>::with_capacity(spectrum_length / self.stride);
 
-        for i in (init_patch_index..max_index).step_by(self.patch_size) {
+        for i in (init_patch_index..max_index).step_by(self.stride) {
             ref_patch_indices.push(i - 1);
         }
         Ok(ref_patch_indices)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smaller_stride_creates_overlapping_patches() {
+        let spectrogram = Array2::<f64>::zeros((4, 100));
+
+        let patches = ImagePatchCreator::new(20)
+            .create_ref_patch_indices_from_spectrogram(&spectrogram)
+            .unwrap();
+        let overlapping = ImagePatchCreator::new(20)
+            .with_stride(10)
+            .unwrap()
+            .create_ref_patch_indices_from_spectrogram(&spectrogram)
+            .unwrap();
+
+        assert_eq!(patches, vec![9, 29, 49, 69]);
+        assert_eq!(overlapping, vec![9, 19, 29, 39, 49, 59, 69]);
+        assert!(matches!(
+            ImagePatchCreator::new(20).with_stride(21),
+            Err(VisqolError::InvalidPatchStride {
+                stride: 21,
+                patch_size: 20
+            })
+        ));
+        assert!(ImagePatchCreator::new(20).with_stride(0).is_err());
+    }
+
+    #[test]
+    fn patches_are_iterated_like_the_batch() {
+        let spectrogram =
+            Array2::from_shape_fn((4, 100), |(band, frame)| (band * 100 + frame) as f64);
+        let creator = ImagePatchCreator::new(20);
+        let indices = creator.create_ref_patch_indices_from_spectrogram(&spectrogram).unwrap();
+
+        let mut patches = creator.patches(&spectrogram, &indices);
+        let first = patches.next().unwrap();
+        let remaining = patches.map(Patch::into_data).collect::<Vec<Array2<f64>>>();
+
+        let batch = creator.create_patches_from_indices(&spectrogram, &indices);
+        assert_eq!(first.data(), &batch[0]);
+        assert_eq!(first.data()[(1, 0)], 109.0);
+        assert_eq!(remaining, batch[1..]);
+    }
+}
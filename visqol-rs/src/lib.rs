@@ -7,13 +7,13 @@
 //! use visqol_rs::variant::Variant;
 //! use visqol_rs::*;
 //!
-//! let path_to_reference_file = "./test_data/clean_speech/reference_signal.wav";
-//! let path_to_degraded_file = "./test_data/clean_speech/degraded_signal.wav";
+//! let path_to_reference_file = "./test_data/clean_speech/reference_signal_16k.wav";
+//! let path_to_degraded_file = "./test_data/clean_speech/degraded_signal_16k.wav";
 //!
 //! let variant = Variant::Wideband {
 //!     use_unscaled_mos_mapping: true,
 //! };
-//! let mut visqol =
+//! let visqol =
 //!     visqol_manager::VisqolManager::<NUM_BANDS_SPEECH>::new(variant, DEFAULT_WINDOW_SIZE);
 //!
 //! let similarity_result = visqol
@@ -26,10 +26,25 @@
 //! );
 //! ```
 
-mod alignment;
-mod analysis_window;
-mod audio_signal;
+/// Emits a warning using `log::warn!` if the `logging` feature is enabled. Otherwise, the message is only type-checked.
+macro_rules! log_warn {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "logging")]
+        log::warn!($($arg)+);
+        #[cfg(not(feature = "logging"))]
+        {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+pub mod alignment;
+pub mod analysis_window;
+pub mod audio_signal;
 pub mod audio_utils;
+pub mod batch_summary;
+#[cfg(feature = "capi")]
+pub mod capi;
 mod comparison_patches_selector;
 pub mod constants;
 mod convolution_2d;
@@ -37,29 +52,49 @@ mod envelope;
 mod equivalent_rectangular_bandwidth;
 mod fast_fourier_transform;
 mod fft_manager;
+#[cfg(feature = "flac")]
+mod flac_reader;
 mod gammatone_filterbank;
 mod gammatone_spectrogram_builder;
 mod image_patch_creator;
+mod local_statistics;
+mod loudness;
 mod math_utils;
-mod neurogram_similiarity_index_measure;
-mod patch_creator;
+pub mod neurogram_similiarity_index_measure;
+pub mod patch_creator;
 mod patch_similarity_comparator;
+#[cfg(feature = "python")]
+mod python;
 mod rms_vad;
 mod signal_filter;
 pub mod similarity_result;
-mod similarity_to_quality_mapper;
-mod spectrogram;
+pub mod similarity_to_quality_mapper;
+pub mod spectrogram;
 mod spectrogram_builder;
-mod speech_similarity_to_quality_mapper;
+#[cfg(feature = "image")]
+pub mod spectrogram_image;
+pub mod speech_similarity_to_quality_mapper;
 mod support_vector_regression_model;
-mod svr_similarity_to_quality_mapper;
-mod vad_patch_creator;
+pub mod svr_similarity_to_quality_mapper;
+#[cfg(any(feature = "mp3", feature = "symphonia"))]
+mod symphonia_reader;
+pub mod time_range;
+pub mod vad_patch_creator;
 pub mod variant;
 mod visqol;
-mod visqol_error;
+#[cfg(feature = "config")]
+pub mod visqol_config;
+pub mod visqol_error;
 pub mod visqol_manager;
+pub mod visqol_warning;
 mod wav_reader;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod weighting;
 mod xcorr;
 
+#[cfg(all(feature = "wasm", feature = "parallel", target_arch = "wasm32"))]
+compile_error!("The `parallel` feature spawns threads, which are not available on wasm32. Disable the default features to build with `wasm`.");
+
 #[cfg(test)]
 mod test_utility;
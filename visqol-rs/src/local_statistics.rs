@@ -0,0 +1,179 @@
+use ndarray::{Array2, ArrayBase, Data, Ix2};
+#[cfg(feature = "simd")]
+use wide::f64x4;
+
+#[cfg(feature = "simd")]
+use crate::convolution_2d::add_matrix_boundary;
+#[cfg(any(test, not(feature = "simd")))]
+use crate::convolution_2d::perform_valid_2d_conv_with_boundary;
+
+/// Number of output points computed at once by `local_statistics_simd`.
+#[cfg(feature = "simd")]
+const LANES: usize = 4;
+
+/// Local means of two patches, of their squares and of their product, each weighted by the same window.
+/// The NSIM derives the local means, variances and the covariance of both patches from them.
+pub struct LocalStatistics {
+    pub mean_ref: Array2<f64>,
+    pub mean_deg: Array2<f64>,
+    pub mean_ref_squared: Array2<f64>,
+    pub mean_deg_squared: Array2<f64>,
+    pub mean_ref_deg: Array2<f64>,
+}
+
+/// Computes the local statistics of `ref_patch` and `deg_patch` by convolving them with `window`, see `perform_valid_2d_conv_with_boundary`.
+/// With the `simd` feature, all statistics are accumulated in a single pass over the patches, several points at once.
+pub fn local_statistics<R: Data<Elem = f64>, D: Data<Elem = f64>>(
+    window: &Array2<f64>,
+    ref_patch: &ArrayBase<R, Ix2>,
+    deg_patch: &ArrayBase<D, Ix2>,
+) -> LocalStatistics {
+    #[cfg(feature = "simd")]
+    return local_statistics_simd(window, ref_patch, deg_patch);
+    #[cfg(not(feature = "simd"))]
+    return local_statistics_scalar(window, ref_patch, deg_patch);
+}
+
+/// Computes each statistic by a separate convolution.
+#[cfg(any(test, not(feature = "simd")))]
+fn local_statistics_scalar<R: Data<Elem = f64>, D: Data<Elem = f64>>(
+    window: &Array2<f64>,
+    ref_patch: &ArrayBase<R, Ix2>,
+    deg_patch: &ArrayBase<D, Ix2>,
+) -> LocalStatistics {
+    let ref_neuro_sq = ref_patch * ref_patch;
+    let deg_neuro_sq = deg_patch * deg_patch;
+    let ref_neuro_deg = ref_patch * deg_patch;
+
+    LocalStatistics {
+        mean_ref: perform_valid_2d_conv_with_boundary(window, ref_patch),
+        mean_deg: perform_valid_2d_conv_with_boundary(window, deg_patch),
+        mean_ref_squared: perform_valid_2d_conv_with_boundary(window, &ref_neuro_sq),
+        mean_deg_squared: perform_valid_2d_conv_with_boundary(window, &deg_neuro_sq),
+        mean_ref_deg: perform_valid_2d_conv_with_boundary(window, &ref_neuro_deg),
+    }
+}
+
+/// Accumulates all statistics in one pass over the padded patches, `LANES` adjacent output points of a row at once.
+/// The taps are accumulated in the order of `perform_valid_2d_conv_with_boundary`, so that both agree up to rounding.
+#[cfg(feature = "simd")]
+fn local_statistics_simd<R: Data<Elem = f64>, D: Data<Elem = f64>>(
+    window: &Array2<f64>,
+    ref_patch: &ArrayBase<R, Ix2>,
+    deg_patch: &ArrayBase<D, Ix2>,
+) -> LocalStatistics {
+    let padded_ref = add_matrix_boundary(ref_patch);
+    let padded_deg = add_matrix_boundary(deg_patch);
+    let num_rows = padded_ref.nrows() - window.nrows() + 1;
+    let num_cols = padded_ref.ncols() - window.ncols() + 1;
+
+    // Tap `(f_row, f_col)` is weighted by the window element which `perform_valid_2d_conv_with_boundary` pairs with it,
+    // counting down from the last element of the window flattened in row-major order.
+    let (f_r_c, f_c_c) = window.dim();
+    let taps = (0..f_c_c)
+        .flat_map(|f_col| (0..f_r_c).map(move |f_row| (f_row, f_col)))
+        .map(|(f_row, f_col)| {
+            let filter_index = f_r_c * f_c_c - 1 - (f_col * f_r_c + f_row);
+            (f_row, f_col, window[(filter_index / f_c_c, filter_index % f_c_c)])
+        })
+        .collect::<Vec<(usize, usize, f64)>>();
+
+    let mut statistics = [(); 5].map(|_| Array2::<f64>::zeros((num_rows, num_cols)));
+    for row in 0..num_rows {
+        let mut col = 0;
+        while col + LANES <= num_cols {
+            let mut sums = [f64x4::ZERO; 5];
+            for &(f_row, f_col, weight) in &taps {
+                let lanes = |padded: &Array2<f64>| {
+                    let start = col + f_col;
+                    let row = padded.row(row + f_row);
+                    f64x4::new([row[start], row[start + 1], row[start + 2], row[start + 3]])
+                };
+                let (r, d) = (lanes(&padded_ref), lanes(&padded_deg));
+                let weight = f64x4::splat(weight);
+                for (sum, value) in sums.iter_mut().zip([r, d, r * r, d * d, r * d]) {
+                    *sum += value * weight;
+                }
+            }
+            for (statistic, sum) in statistics.iter_mut().zip(sums) {
+                for (lane, value) in sum.to_array().iter().enumerate() {
+                    statistic[(row, col + lane)] = *value;
+                }
+            }
+            col += LANES;
+        }
+
+        for col in col..num_cols {
+            let mut sums = [0.0; 5];
+            for &(f_row, f_col, weight) in &taps {
+                let r = padded_ref[(row + f_row, col + f_col)];
+                let d = padded_deg[(row + f_row, col + f_col)];
+                for (sum, value) in sums.iter_mut().zip([r, d, r * r, d * d, r * d]) {
+                    *sum += value * weight;
+                }
+            }
+            for (statistic, sum) in statistics.iter_mut().zip(sums) {
+                statistic[(row, col)] = sum;
+            }
+        }
+    }
+
+    let [mean_ref, mean_deg, mean_ref_squared, mean_deg_squared, mean_ref_deg] = statistics;
+    LocalStatistics {
+        mean_ref,
+        mean_deg,
+        mean_ref_squared,
+        mean_deg_squared,
+        mean_ref_deg,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+    use ndarray::arr2;
+
+    #[test]
+    fn statistics_match_products_of_patches() {
+        let window = arr2(&[[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0]]);
+        let ref_patch = arr2(&[[1.0, 2.0], [3.0, 4.0]]);
+        let deg_patch = arr2(&[[0.5, 1.0], [2.0, -1.0]]);
+
+        let statistics = local_statistics(&window, &ref_patch, &deg_patch);
+
+        assert_eq!(statistics.mean_ref, ref_patch);
+        assert_eq!(statistics.mean_deg, deg_patch);
+        assert_eq!(statistics.mean_ref_squared, &ref_patch * &ref_patch);
+        assert_eq!(statistics.mean_deg_squared, &deg_patch * &deg_patch);
+        assert_eq!(statistics.mean_ref_deg, &ref_patch * &deg_patch);
+    }
+
+    #[test]
+    fn statistics_match_scalar_convolutions() {
+        let window = arr2(&[[0.1, 0.2, 0.05], [0.05, 0.3, 0.1], [0.05, 0.1, 0.05]]);
+        let ref_patch = Array2::from_shape_fn((21, 30), |(row, col)| {
+            ((row * 7 + col * 3) as f64 * 0.37).sin() * 40.0 + 40.0
+        });
+        let deg_patch = Array2::from_shape_fn((21, 30), |(row, col)| {
+            ((row * 5 + col * 11) as f64 * 0.23).cos() * 35.0 + 38.0
+        });
+
+        let statistics = local_statistics(&window, &ref_patch, &deg_patch);
+        let expected = local_statistics_scalar(&window, &ref_patch, &deg_patch);
+
+        let pairs = [
+            (&statistics.mean_ref, &expected.mean_ref),
+            (&statistics.mean_deg, &expected.mean_deg),
+            (&statistics.mean_ref_squared, &expected.mean_ref_squared),
+            (&statistics.mean_deg_squared, &expected.mean_deg_squared),
+            (&statistics.mean_ref_deg, &expected.mean_ref_deg),
+        ];
+        for (statistic, expected) in pairs {
+            assert_eq!(statistic.dim(), expected.dim());
+            for (value, expected_value) in statistic.iter().zip(expected.iter()) {
+                assert_abs_diff_eq!(*value, *expected_value, epsilon = 1e-9);
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::audio_signal::AudioSignal;
+use crate::signal_filter;
+use std::f64::consts::PI;
+
+/// Duration of a gating block in seconds according to ITU-R BS.1770-4.
+const BLOCK_DURATION: f64 = 0.4;
+/// Overlap of consecutive gating blocks.
+const BLOCK_OVERLAP: f64 = 0.75;
+/// Blocks quieter than this loudness in LUFS are discarded.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Blocks quieter than the ungated loudness minus this value in LU are discarded.
+const RELATIVE_GATE_LU: f64 = 10.0;
+/// Offset compensating the gain of the K-weighting filter at 1 kHz.
+const LOUDNESS_OFFSET: f64 = -0.691;
+
+/// Computes the integrated loudness of `signal` in LUFS as specified by ITU-R BS.1770-4, which EBU R128 is based on.
+/// Returns `None` if the signal is shorter than one gating block or if all blocks are gated, e.g. for digital silence.
+pub fn integrated_loudness(signal: &AudioSignal) -> Option<f64> {
+    let weighted_signal = apply_k_weighting(signal);
+
+    let block_size = (BLOCK_DURATION * signal.sample_rate as f64).round() as usize;
+    let hop_size = (block_size as f64 * (1.0 - BLOCK_OVERLAP)).round() as usize;
+    if block_size == 0 || weighted_signal.len() < block_size {
+        return None;
+    }
+
+    let block_energies = (0..=(weighted_signal.len() - block_size) / hop_size)
+        .map(|block_index| {
+            let block = &weighted_signal[block_index * hop_size..][..block_size];
+            block.iter().map(|sample| sample.powi(2)).sum::<f64>() / block_size as f64
+        })
+        .filter(|&energy| energy_to_loudness(energy) > ABSOLUTE_GATE_LUFS)
+        .collect::<Vec<f64>>();
+    if block_energies.is_empty() {
+        return None;
+    }
+
+    let relative_gate = energy_to_loudness(mean(&block_energies)) - RELATIVE_GATE_LU;
+    let gated_energies = block_energies
+        .into_iter()
+        .filter(|&energy| energy_to_loudness(energy) > relative_gate)
+        .collect::<Vec<f64>>();
+
+    Some(energy_to_loudness(mean(&gated_energies)))
+}
+
+/// Applies the two stage K-weighting filter, a high shelf followed by a high pass, to `signal`.
+fn apply_k_weighting(signal: &AudioSignal) -> Vec<f64> {
+    let sample_rate = signal.sample_rate as f64;
+
+    // Stage 1: High shelf modelling the acoustic effects of the head.
+    let shelf_frequency = 1681.974450955533;
+    let shelf_gain_db = 3.999843853973347;
+    let shelf_q = 0.7071752369554196;
+    let k = (PI * shelf_frequency / sample_rate).tan();
+    let vh = 10.0f64.powf(shelf_gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / shelf_q + k * k;
+    let shelf_numerator = [
+        (vh + vb * k / shelf_q + k * k) / a0,
+        2.0 * (k * k - vh) / a0,
+        (vh - vb * k / shelf_q + k * k) / a0,
+    ];
+    let shelf_denominator = [
+        1.0,
+        2.0 * (k * k - 1.0) / a0,
+        (1.0 - k / shelf_q + k * k) / a0,
+    ];
+
+    // Stage 2: High pass modelling the RLB weighting curve.
+    let high_pass_frequency = 38.13547087602444;
+    let high_pass_q = 0.5003270373238773;
+    let k = (PI * high_pass_frequency / sample_rate).tan();
+    let a0 = 1.0 + k / high_pass_q + k * k;
+    let high_pass_numerator = [1.0, -2.0, 1.0];
+    let high_pass_denominator = [
+        1.0,
+        2.0 * (k * k - 1.0) / a0,
+        (1.0 - k / high_pass_q + k * k) / a0,
+    ];
+
+    let samples = signal.data_matrix.to_vec();
+    let shelved = signal_filter::filter_signal(
+        &shelf_numerator,
+        &shelf_denominator,
+        &samples,
+        &mut [0.0; signal_filter::FilterResults::NUM_FILTER_CONDITIONS],
+    );
+    signal_filter::filter_signal(
+        &high_pass_numerator,
+        &high_pass_denominator,
+        &shelved.filtered_signal,
+        &mut [0.0; signal_filter::FilterResults::NUM_FILTER_CONDITIONS],
+    )
+    .filtered_signal
+}
+
+/// Converts the mean square `energy` of a K-weighted block to its loudness in LUFS.
+fn energy_to_loudness(energy: f64) -> f64 { LOUDNESS_OFFSET + 10.0 * energy.log10() }
+
+fn mean(values: &[f64]) -> f64 { values.iter().sum::<f64>() / values.len() as f64 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn full_scale_sine_measures_minus_3_lufs() {
+        let sample_rate = 48000;
+        let samples = (0..sample_rate * 5)
+            .map(|n| (2.0 * PI * 997.0 * n as f64 / sample_rate as f64).sin())
+            .collect::<Vec<f64>>();
+        let signal = AudioSignal::new(samples, sample_rate as u32);
+
+        let loudness = integrated_loudness(&signal).unwrap();
+
+        assert_abs_diff_eq!(loudness, -3.01, epsilon = 0.05);
+    }
+
+    #[test]
+    fn silence_has_no_loudness() {
+        let signal = AudioSignal::new([0.0; 48000], 48000);
+
+        assert!(integrated_loudness(&signal).is_none());
+    }
+}
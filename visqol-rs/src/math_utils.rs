@@ -28,6 +28,14 @@ pub fn normalize_int16_to_double(input: &[i16]) -> Vec<f64> {
         .collect::<Vec<f64>>()
 }
 
+/// Scales 24 bit integer samples, stored in the lower bits of `input`, to the range -1.0 to 1.0.
+pub fn normalize_int24_to_double(input: &[i32]) -> Vec<f64> {
+    input
+        .iter()
+        .map(|x| *x as f64 / 8388607.0f64)
+        .collect::<Vec<f64>>()
+}
+
 /// Returns the maximum of an `ndarray::Array1<f64>`
 fn get_max(mat: &Array1<f64>) -> f64 { *mat.max().expect("Failed to compute maximum of matrix!") }
 
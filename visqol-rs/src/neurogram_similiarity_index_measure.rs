@@ -1,70 +1,108 @@
-use crate::convolution_2d::perform_valid_2d_conv_with_boundary;
+use crate::local_statistics::local_statistics;
 use crate::patch_similarity_comparator::{PatchSimilarityComparator, PatchSimilarityResult};
-use ndarray::{arr2, Array1, Axis};
+use ndarray::{arr2, Array1, Array2, ArrayBase, Axis, Data, Ix2, Zip};
+
+/// Stabilization constants of the NSIM, which are added to the numerator and the denominator of each term.
+/// They keep the terms defined for silent or flat patches and reduce their sensitivity to differences in patches of low intensity or variance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NsimConstants {
+    /// Constant of the intensity (luminance) term, which compares the local means of both patches.
+    pub intensity: f64,
+    /// Constant of the contrast term, which compares the local standard deviations of both patches.
+    /// ViSQOL omits this term, so it is only included in the similarity if set, as in SSIM.
+    pub contrast: Option<f64>,
+    /// Constant of the structure term, which is the correlation of both patches.
+    pub structure: f64,
+}
+
+impl NsimConstants {
+    /// Derives the constants from the dynamic range of the spectrogram values as in SSIM, omitting the contrast term.
+    pub fn from_intensity_range(intensity_range: f64) -> Self {
+        let k = [0.01, 0.03];
+        Self {
+            intensity: (k[0] * intensity_range).powf(2.0),
+            contrast: None,
+            structure: (k[1] * intensity_range).powf(2.0) / 2.0,
+        }
+    }
+}
+
+impl Default for NsimConstants {
+    /// The constants used by ViSQOL, which assumes an intensity range of 1.
+    fn default() -> Self { Self::from_intensity_range(1.0) }
+}
 
 /// Provides a neurogram similarity index measure (NSIM) implementation for a
 /// patch similarity comparator. NSIM is a distance metric, adapted from the
 /// image processing technique called structural similarity (SSIM) and is here
 /// used to compare two patches taken from the reference and degraded
 /// spectrograms.
+#[derive(Default)]
 pub struct NeurogramSimiliarityIndexMeasure {
-    intensity_range: f64,
+    constants: NsimConstants,
 }
 
-#[allow(unused)]
 impl NeurogramSimiliarityIndexMeasure {
-    pub fn new(intensity_range: f64) -> Self { Self { intensity_range } }
+    /// Creates an NSIM comparator which uses the given stabilization `constants`.
+    pub fn new(constants: NsimConstants) -> Self { Self { constants } }
 }
 
-impl Default for NeurogramSimiliarityIndexMeasure {
-    fn default() -> Self {
-        Self {
-            intensity_range: 1.0,
-        }
-    }
+/// The maps of the SSIM-style terms of the NSIM between two patches, computed for each point of the patches.
+pub struct NsimComponents {
+    /// Intensity (luminance) term, which compares the local means of both patches.
+    pub luminance: Array2<f64>,
+    /// Contrast term, which compares the local standard deviations of both patches.
+    /// Unless `NsimConstants::contrast` is set, it is computed with twice the structure constant as in SSIM, but not part of the similarity.
+    pub contrast: Array2<f64>,
+    /// Structure term, which is the local correlation of both patches.
+    pub structure: Array2<f64>,
+    /// The combined NSIM, which is the product of the luminance and structure terms and of the contrast term if configured.
+    pub similarity: Array2<f64>,
 }
 
-impl PatchSimilarityComparator for NeurogramSimiliarityIndexMeasure {
-    /// Computes the NSIM between `ref_patch` and `deg_patch` and returns the mean and standard deviation of each frequency band, the energy of the degraded patch and the similarity score.
-    fn measure_patch_similarity(
+impl NeurogramSimiliarityIndexMeasure {
+    /// Computes the luminance, contrast and structure terms of the NSIM between `ref_patch` and `deg_patch` as well as the combined similarity.
+    /// This shows whether a low similarity is caused by a difference in energy or in the structure of the patches.
+    /// The patches can be owned arrays or views, e.g. into a spectrogram.
+    pub fn measure_patch_components<R: Data<Elem = f64>, D: Data<Elem = f64>>(
         &self,
-        ref_patch: &mut ndarray::Array2<f64>,
-        deg_patch: &mut ndarray::Array2<f64>,
-    ) -> PatchSimilarityResult {
+        ref_patch: &ArrayBase<R, Ix2>,
+        deg_patch: &ArrayBase<D, Ix2>,
+    ) -> NsimComponents {
+        self.compute_components(ref_patch, deg_patch, true)
+    }
+
+    /// Computes the terms of the NSIM like `measure_patch_components`. The contrast term is only computed if `with_contrast` is set
+    /// or the similarity includes it, otherwise `NsimComponents::contrast` is empty. This saves its cost when matching patches by default.
+    fn compute_components<R: Data<Elem = f64>, D: Data<Elem = f64>>(
+        &self,
+        ref_patch: &ArrayBase<R, Ix2>,
+        deg_patch: &ArrayBase<D, Ix2>,
+        with_contrast: bool,
+    ) -> NsimComponents {
         let window = arr2(&[
             [0.0113033910173052, 0.0838251475442633, 0.0113033910173052],
             [0.0838251475442633, 0.619485845753726, 0.0838251475442633],
             [0.0113033910173052, 0.0838251475442633, 0.0113033910173052],
         ]);
 
-        let k = [0.01, 0.03];
-        let c1 = (k[0] * self.intensity_range).powf(2.0);
-        let c3 = (k[1] * self.intensity_range).powf(2.0) / 2.0;
+        let c1 = self.constants.intensity;
+        let c2 = self.constants.contrast.unwrap_or(2.0 * self.constants.structure);
+        let c3 = self.constants.structure;
 
         // Compute mu
-        let mu_ref = perform_valid_2d_conv_with_boundary(&window, ref_patch);
-        let mu_deg = perform_valid_2d_conv_with_boundary(&window, deg_patch);
+        let statistics = local_statistics(&window, ref_patch, deg_patch);
+        let mu_ref = &statistics.mean_ref;
+        let mu_deg = &statistics.mean_deg;
 
-        let ref_mu_squared = &mu_ref * &mu_ref;
-        let deg_mu_squared = &mu_deg * &mu_deg;
-        let mu_r_mu_d = &mu_ref * &mu_deg;
-
-        let mut ref_neuro_sq = ref_patch.clone() * ref_patch.clone();
-        let mut deg_neuro_sq = deg_patch.clone() * deg_patch.clone();
+        let ref_mu_squared = mu_ref * mu_ref;
+        let deg_mu_squared = mu_deg * mu_deg;
+        let mu_r_mu_d = mu_ref * mu_deg;
 
         // Compute sigmas
-        let conv2_ref_neuro_squared =
-            perform_valid_2d_conv_with_boundary(&window, &mut ref_neuro_sq);
-        let sigma_ref_squared = &conv2_ref_neuro_squared - &ref_mu_squared;
-
-        let conv2_deg_neuro_squared =
-            perform_valid_2d_conv_with_boundary(&window, &mut deg_neuro_sq);
-        let sigma_deg_squared = &conv2_deg_neuro_squared - &deg_mu_squared;
-
-        let mut ref_neuro_deg = ref_patch.clone() * deg_patch.clone();
-        let conv2_ref_neuro_deg = perform_valid_2d_conv_with_boundary(&window, &mut ref_neuro_deg);
-
-        let sigma_r_d = &conv2_ref_neuro_deg - &mu_r_mu_d;
+        let sigma_ref_squared = &statistics.mean_ref_squared - &ref_mu_squared;
+        let sigma_deg_squared = &statistics.mean_deg_squared - &deg_mu_squared;
+        let sigma_r_d = &statistics.mean_ref_deg - &mu_r_mu_d;
 
         // Compute intensity
         let intensity_numerator = &mu_r_mu_d * 2.0 + c1;
@@ -72,6 +110,18 @@ impl PatchSimilarityComparator for NeurogramSimiliarityIndexMeasure {
 
         let intensity = &intensity_numerator / &intensity_denominator;
 
+        // Compute contrast
+        let contrast = if with_contrast || self.constants.contrast.is_some() {
+            Zip::from(&sigma_ref_squared)
+                .and(&sigma_deg_squared)
+                .map_collect(|&ref_var, &deg_var| {
+                    (2.0 * ref_var.max(0.0).sqrt() * deg_var.max(0.0).sqrt() + c2)
+                        / (ref_var.max(0.0) + deg_var.max(0.0) + c2)
+                })
+        } else {
+            Array2::zeros((0, 0))
+        };
+
         // Compute structure
         let structure_numerator = &sigma_r_d + c3;
         let mut structure_denominator = &sigma_ref_squared * &sigma_deg_squared;
@@ -86,7 +136,29 @@ impl PatchSimilarityComparator for NeurogramSimiliarityIndexMeasure {
         });
 
         let structure = &structure_numerator / &structure_denominator;
-        let sim_map = &intensity * &structure;
+        let mut similarity = &intensity * &structure;
+
+        if self.constants.contrast.is_some() {
+            similarity *= &contrast;
+        }
+
+        NsimComponents {
+            luminance: intensity,
+            contrast,
+            structure,
+            similarity,
+        }
+    }
+}
+
+impl PatchSimilarityComparator for NeurogramSimiliarityIndexMeasure {
+    /// Computes the NSIM between `ref_patch` and `deg_patch` and returns the mean and standard deviation of each frequency band, the energy of the degraded patch and the similarity score.
+    fn measure_patch_similarity<R: Data<Elem = f64>, D: Data<Elem = f64>>(
+        &self,
+        ref_patch: &ArrayBase<R, Ix2>,
+        deg_patch: &ArrayBase<D, Ix2>,
+    ) -> PatchSimilarityResult {
+        let sim_map = self.compute_components(ref_patch, deg_patch, false).similarity;
 
         let freq_band_deg_energy: Array1<f64> = deg_patch
             .mean_axis(Axis(1))
@@ -112,22 +184,20 @@ impl PatchSimilarityComparator for NeurogramSimiliarityIndexMeasure {
 mod tests {
 
     use approx::assert_abs_diff_eq;
-    use ndarray::Array2;
 
     use super::*;
 
     #[test]
     fn test_neurogram_measure() {
         let ref_patch = vec![1.0, 0.0, 0.0];
-        let mut ref_patch_mat = Array2::from_shape_vec((3, 1), ref_patch).unwrap();
+        let ref_patch_mat = Array2::from_shape_vec((3, 1), ref_patch).unwrap();
         let deg_patch = vec![0.0, 0.0, 0.0];
-        let mut deg_patch_mat = Array2::from_shape_vec((3, 1), deg_patch).unwrap();
+        let deg_patch_mat = Array2::from_shape_vec((3, 1), deg_patch).unwrap();
         let expected_result = [0.000125225, 0.00875062, 1.0];
 
         let sim_comparator = NeurogramSimiliarityIndexMeasure::default();
 
-        let result =
-            sim_comparator.measure_patch_similarity(&mut ref_patch_mat, &mut deg_patch_mat);
+        let result = sim_comparator.measure_patch_similarity(&ref_patch_mat, &deg_patch_mat);
 
         assert_abs_diff_eq!(
             result.freq_band_means[0],
@@ -145,4 +215,73 @@ mod tests {
             epsilon = 0.0001
         );
     }
+
+    #[test]
+    fn default_constants_are_derived_from_unit_intensity_range() {
+        let constants = NsimConstants::default();
+
+        assert_abs_diff_eq!(constants.intensity, 0.0001, epsilon = 1e-12);
+        assert_abs_diff_eq!(constants.structure, 0.00045, epsilon = 1e-12);
+        assert_eq!(constants.contrast, None);
+    }
+
+    #[test]
+    fn larger_constants_increase_similarity_of_different_patches() {
+        let ref_patch_mat = Array2::from_shape_fn((4, 6), |(row, col)| ((row + col) % 3) as f64);
+        let deg_patch_mat = Array2::from_shape_fn((4, 6), |(row, col)| ((row * col) % 2) as f64);
+
+        let default_result = NeurogramSimiliarityIndexMeasure::default()
+            .measure_patch_similarity(&ref_patch_mat, &deg_patch_mat);
+        let stabilized_result = NeurogramSimiliarityIndexMeasure::new(NsimConstants {
+            intensity: 1.0,
+            contrast: None,
+            structure: 1.0,
+        })
+        .measure_patch_similarity(&ref_patch_mat, &deg_patch_mat);
+
+        assert!(stabilized_result.similarity > default_result.similarity);
+    }
+
+    #[test]
+    fn contrast_term_penalizes_scaled_patches() {
+        let ref_patch_mat = Array2::from_shape_fn((4, 6), |(row, col)| ((row + col) % 3) as f64);
+        let deg_patch_mat = &ref_patch_mat * 3.0;
+        let contrast_constants = NsimConstants {
+            contrast: Some(0.0009),
+            ..NsimConstants::default()
+        };
+
+        let identical_result = NeurogramSimiliarityIndexMeasure::new(contrast_constants)
+            .measure_patch_similarity(&ref_patch_mat, &ref_patch_mat);
+        let default_result = NeurogramSimiliarityIndexMeasure::default()
+            .measure_patch_similarity(&ref_patch_mat, &deg_patch_mat);
+        let contrast_result = NeurogramSimiliarityIndexMeasure::new(contrast_constants)
+            .measure_patch_similarity(&ref_patch_mat, &deg_patch_mat);
+
+        assert_abs_diff_eq!(identical_result.similarity, 1.0, epsilon = 1e-9);
+        assert!(contrast_result.similarity < default_result.similarity);
+    }
+
+    #[test]
+    fn components_separate_energy_from_structure() {
+        let ref_patch_mat = Array2::from_shape_fn((4, 6), |(row, col)| ((row + col) % 3) as f64);
+        let louder_patch_mat = &ref_patch_mat + 2.0;
+        let sim_comparator = NeurogramSimiliarityIndexMeasure::default();
+
+        let components = sim_comparator.measure_patch_components(&ref_patch_mat, &louder_patch_mat);
+        let result = sim_comparator.measure_patch_similarity(&ref_patch_mat, &louder_patch_mat);
+
+        assert_eq!(components.similarity, &components.luminance * &components.structure);
+        assert_eq!(
+            result.similarity,
+            components.similarity.mean_axis(Axis(1)).unwrap().mean().unwrap()
+        );
+        for &structure in components.structure.iter() {
+            assert_abs_diff_eq!(structure, 1.0, epsilon = 1e-9);
+        }
+        for &contrast in components.contrast.iter() {
+            assert_abs_diff_eq!(contrast, 1.0, epsilon = 1e-9);
+        }
+        assert!(components.luminance.iter().all(|&luminance| luminance < 1.0));
+    }
 }
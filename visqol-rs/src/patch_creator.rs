@@ -1,4 +1,6 @@
-use ndarray::Array2;
+use std::ops::Range;
+
+use ndarray::{s, Array2};
 
 use crate::{
     analysis_window::AnalysisWindow, audio_signal::AudioSignal, visqol_error::VisqolError,
@@ -6,7 +8,9 @@ use crate::{
 
 /// This trait enables the creation of patches from a spectrogram.
 /// The term `patch` here refers to a segment of 2-dimensional data. How the data is segmented is determined by the individual implementation of this trait.
-pub trait PatchCreator {
+/// This is a stable extension point: custom implementations can be passed to `VisqolManagerBuilder::patch_creator` to replace the patch creator selected by the variant.
+/// Implementations must be `Send + Sync`, so that a `VisqolManager` can be shared across threads.
+pub trait PatchCreator: Send + Sync {
     /// Given a spectrogram, this function returns 0-indexed indices of each patch.
     fn create_ref_patch_indices(
         &self,
@@ -15,10 +19,99 @@ pub trait PatchCreator {
         window: &AnalysisWindow,
     ) -> Result<Vec<usize>, VisqolError>;
 
+    /// Returns an iterator over the patches of `spectrogram` starting at each of `patch_indices`, e.g. those returned by `create_ref_patch_indices`.
+    /// Each patch is only copied from the spectrogram when the iterator reaches it, so the patches can be consumed one at a time.
+    fn patches<'a>(
+        &'a self,
+        spectrogram: &'a Array2<f64>,
+        patch_indices: &'a [usize],
+    ) -> Box<dyn Iterator<Item = Patch> + 'a>;
+
     /// Given a spectrogram and the corresponding indices, this function performs the segmentation and returns each patch in a vector of 2-dimensional arrays.
+    /// By default, the data of all `patches` is collected.
     fn create_patches_from_indices(
         &self,
         spectrogram: &Array2<f64>,
         patch_indices: &[usize],
-    ) -> Vec<Array2<f64>>;
+    ) -> Vec<Array2<f64>> {
+        self.patches(spectrogram, patch_indices)
+            .map(Patch::into_data)
+            .collect()
+    }
+
+    /// Returns the number of spectrogram frames per patch, if it is fixed.
+    /// If provided, `VisqolManager` rejects signals which are too short to contain a single patch with `VisqolError::SignalTooShort`.
+    fn patch_size(&self) -> Option<usize> { None }
+}
+
+/// A patch of a spectrogram, which holds all of its bands over a range of consecutive frames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patch {
+    start_frame: usize,
+    data: Array2<f64>,
+}
+
+impl Patch {
+    /// Creates a patch holding `data`, with a row per band and a column per frame, which starts at frame `start_frame` of its spectrogram.
+    pub fn new(start_frame: usize, data: Array2<f64>) -> Self { Self { start_frame, data } }
+
+    /// Copies the `num_frames` frames of `spectrogram` starting at `start_frame` into a new patch.
+    pub fn from_spectrogram(
+        spectrogram: &Array2<f64>,
+        start_frame: usize,
+        num_frames: usize,
+    ) -> Self {
+        let data = spectrogram
+            .slice(s![.., start_frame..start_frame + num_frames])
+            .to_owned();
+        Self::new(start_frame, data)
+    }
+
+    /// Returns the index of the first frame of the patch in its spectrogram.
+    pub fn start_frame(&self) -> usize { self.start_frame }
+
+    /// Returns the index of the frame after the last frame of the patch in its spectrogram.
+    pub fn end_frame(&self) -> usize { self.start_frame + self.data.ncols() }
+
+    /// Returns the time in seconds at which the patch starts, for spectrogram frames of `frame_duration` seconds,
+    /// i.e. the hop size of the analysis window divided by the sample rate. For reference patches, this is `PatchSimilarityResult::ref_patch_start_time`.
+    pub fn start_time(&self, frame_duration: f64) -> f64 {
+        self.start_frame as f64 * frame_duration
+    }
+
+    /// Returns the time in seconds at which the patch ends, for spectrogram frames of `frame_duration` seconds, see `start_time`.
+    pub fn end_time(&self, frame_duration: f64) -> f64 { self.end_frame() as f64 * frame_duration }
+
+    /// Returns the bands of the spectrogram which the patch covers. Patches hold all bands of their spectrogram, from the lowest frequency to the highest.
+    pub fn bands(&self) -> Range<usize> { 0..self.data.nrows() }
+
+    /// Returns the center frequencies in Hz of the bands of the patch, given those of its spectrogram, e.g. `Spectrogram::center_freq_bands`.
+    pub fn center_freqs<'a>(&self, center_freq_bands: &'a [f64]) -> &'a [f64] {
+        &center_freq_bands[self.bands()]
+    }
+
+    /// Returns the spectrogram data of the patch, with a row per band and a column per frame.
+    pub fn data(&self) -> &Array2<f64> { &self.data }
+
+    /// Consumes the patch and returns its spectrogram data.
+    pub fn into_data(self) -> Array2<f64> { self.data }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patch_position_is_reported_in_frames_and_seconds() {
+        let spectrogram = Array2::<f64>::zeros((3, 100));
+        let center_freq_bands = [50.0, 150.0, 250.0];
+
+        let patch = Patch::from_spectrogram(&spectrogram, 40, 20);
+
+        assert_eq!((patch.start_frame(), patch.end_frame()), (40, 60));
+        assert_eq!(patch.start_time(0.5), 20.0);
+        assert_eq!(patch.end_time(0.5), 30.0);
+        assert_eq!(patch.bands(), 0..3);
+        assert_eq!(patch.center_freqs(&center_freq_bands), center_freq_bands);
+    }
 }
@@ -1,6 +1,8 @@
-use ndarray::Array2 as ImagePatch;
-use serde::Serialize;
-#[derive(Debug, Clone, Serialize)]
+use ndarray::{ArrayBase, Data, Ix2};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Bundles similarity information of a single patch.
 /// The term `Patch` here refers to a single of spectrogram data produced by a PatchCreator)
 pub struct PatchSimilarityResult {
@@ -59,10 +61,11 @@ impl Default for PatchSimilarityResult {
 }
 
 /// If implemented, this trait allows for computing a similarity score of 2 patches
+/// The patches can be owned arrays or views, so that patches of a spectrogram can be compared without copying them.
 pub trait PatchSimilarityComparator {
-    fn measure_patch_similarity(
+    fn measure_patch_similarity<R: Data<Elem = f64>, D: Data<Elem = f64>>(
         &self,
-        ref_patch: &mut ImagePatch<f64>,
-        deg_patch: &mut ImagePatch<f64>,
+        ref_patch: &ArrayBase<R, Ix2>,
+        deg_patch: &ArrayBase<D, Ix2>,
     ) -> PatchSimilarityResult;
 }
@@ -0,0 +1,167 @@
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+
+use crate::constants::DEFAULT_WINDOW_SIZE;
+use crate::similarity_result::SimilarityResult;
+use crate::variant::Variant;
+use crate::visqol_error::VisqolError;
+use crate::visqol_manager::DynamicVisqolManager;
+
+create_exception!(
+    visqol,
+    VisqolException,
+    PyException,
+    "Raised if ViSQOL fails to score a pair of files."
+);
+
+impl From<VisqolError> for PyErr {
+    fn from(error: VisqolError) -> Self { VisqolException::new_err(error.to_string()) }
+}
+
+/// Selects the variant for `mode`, which is either `"speech"` for 16 kHz speech or `"audio"` for 48 kHz audio.
+/// The audio mode uses the SVR model in `model_path`, or the embedded model if it is `None`.
+fn variant_for_mode(mode: &str, model_path: Option<String>) -> PyResult<Variant> {
+    match (mode, model_path) {
+        ("speech", _) => Ok(Variant::Wideband {
+            use_unscaled_mos_mapping: false,
+        }),
+        ("audio", model_path) => Ok(Variant::Fullband { model_path }),
+        (mode, _) => Err(PyValueError::new_err(format!(
+            "unknown mode {mode:?}, expected \"speech\" or \"audio\""
+        ))),
+    }
+}
+
+/// Python view of a `SimilarityResult`.
+#[pyclass(name = "SimilarityResult", module = "visqol", frozen)]
+pub struct PySimilarityResult {
+    #[pyo3(get)]
+    moslqo: f64,
+    #[pyo3(get)]
+    vnsim: f64,
+    #[pyo3(get)]
+    fvnsim: Vec<f64>,
+    #[pyo3(get)]
+    fstdnsim: Vec<f64>,
+    #[pyo3(get)]
+    center_freq_bands: Vec<f64>,
+    #[pyo3(get)]
+    patch_scores: Vec<f64>,
+    #[pyo3(get)]
+    alignment_lag_seconds: f64,
+    /// Start and end in seconds of the reference region the degraded signal was scored against, if only a region was scored.
+    #[pyo3(get)]
+    matched_reference_range: Option<(f64, f64)>,
+    #[pyo3(get)]
+    num_patches: usize,
+    #[pyo3(get)]
+    num_reference_patches: usize,
+    #[pyo3(get)]
+    is_reliable: bool,
+    #[pyo3(get)]
+    warnings: Vec<String>,
+}
+
+impl From<SimilarityResult> for PySimilarityResult {
+    fn from(result: SimilarityResult) -> Self {
+        Self {
+            moslqo: result.moslqo,
+            vnsim: result.vnsim,
+            fvnsim: result.fvnsim,
+            fstdnsim: result.fstdnsim,
+            center_freq_bands: result.center_freq_bands,
+            patch_scores: result.patch_scores,
+            alignment_lag_seconds: result.alignment_lag_seconds,
+            matched_reference_range: result
+                .matched_reference_range
+                .map(|range| (range.start, range.end)),
+            num_patches: result.num_patches,
+            num_reference_patches: result.num_reference_patches,
+            is_reliable: result.is_reliable,
+            warnings: result.warnings.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+#[pymethods]
+impl PySimilarityResult {
+    fn __repr__(&self) -> String {
+        format!("SimilarityResult(moslqo={}, vnsim={})", self.moslqo, self.vnsim)
+    }
+}
+
+/// Python view of a `DynamicVisqolManager`.
+#[pyclass(name = "VisqolManager", module = "visqol", frozen)]
+pub struct PyVisqolManager {
+    manager: DynamicVisqolManager,
+}
+
+#[pymethods]
+impl PyVisqolManager {
+    #[new]
+    #[pyo3(signature = (mode = "speech", model_path = None, search_window = DEFAULT_WINDOW_SIZE))]
+    fn new(mode: &str, model_path: Option<String>, search_window: usize) -> PyResult<Self> {
+        let variant = variant_for_mode(mode, model_path)?;
+        let manager = DynamicVisqolManager::builder()
+            .variant(variant)
+            .search_window(search_window)
+            .try_build()?;
+        Ok(Self { manager })
+    }
+
+    /// Scores the degraded file in `deg_path` against the reference file in `ref_path`.
+    /// The GIL is released while scoring, so multiple files can be scored from Python threads.
+    fn run(&self, py: Python<'_>, ref_path: &str, deg_path: &str) -> PyResult<PySimilarityResult> {
+        let result = py.allow_threads(|| self.manager.run(ref_path, deg_path))?;
+        Ok(result.into())
+    }
+}
+
+/// Scores the degraded file in `deg_path` against the reference file in `ref_path` and returns its MOS.
+#[pyfunction]
+#[pyo3(signature = (ref_path, deg_path, mode = "speech", model_path = None))]
+fn measure(
+    py: Python<'_>,
+    ref_path: &str,
+    deg_path: &str,
+    mode: &str,
+    model_path: Option<String>,
+) -> PyResult<f64> {
+    let manager = PyVisqolManager::new(mode, model_path, DEFAULT_WINDOW_SIZE)?;
+    Ok(manager.run(py, ref_path, deg_path)?.moslqo)
+}
+
+#[pymodule]
+#[pyo3(name = "visqol")]
+fn visqol_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(measure, module)?)?;
+    module.add_class::<PyVisqolManager>()?;
+    module.add_class::<PySimilarityResult>()?;
+    module.add("VisqolException", module.py().get_type::<VisqolException>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mode_selects_variant() {
+        assert!(matches!(
+            variant_for_mode("speech", None),
+            Ok(Variant::Wideband {
+                use_unscaled_mos_mapping: false
+            })
+        ));
+        assert!(matches!(
+            variant_for_mode("audio", Some("model.txt".to_string())),
+            Ok(Variant::Fullband { model_path: Some(model_path) }) if model_path == "model.txt"
+        ));
+        assert!(matches!(
+            variant_for_mode("audio", None),
+            Ok(Variant::Fullband { model_path: None })
+        ));
+        assert!(variant_for_mode("music", None).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::constants;
+
 /// Calculates the root mean square of a signal.
 /// Based on a threshold, it computes a binary signal for a single chunk of data.
 /// When returning the VAD results, previous chunks are analyzed to make the VAD less erratic
@@ -21,12 +23,20 @@ impl Default for RmsVad {
             voice_activity_present: 1.0,
             voice_activity_absent: 0.0,
             silent_chunk_count: 3,
-            rms_threshold: 5000.0,
+            rms_threshold: constants::VAD_RMS_THRESHOLD,
             results_per_chunk: Vec::new(),
         }
     }
 }
 impl RmsVad {
+    /// Creates a new `RmsVad` which detects activity in chunks whose RMS is at least `rms_threshold`.
+    pub fn new(rms_threshold: f64) -> Self {
+        Self {
+            rms_threshold,
+            ..Self::default()
+        }
+    }
+
     /// Given a chunk of data this function determines whether or not voice acitivity is present, storing its result in `each_chunk_result`
     pub fn process_chunk(&mut self, chunk: &[i16]) -> f64 {
         let rms = self.calc_root_mean_square(chunk);
@@ -1,15 +1,41 @@
-use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::time::Duration;
+
+use ndarray::Array2;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::patch_similarity_comparator::PatchSimilarityResult;
+use crate::time_range::TimeRange;
+use crate::visqol_error::VisqolError;
+use crate::visqol_warning::VisqolWarning;
 
-#[derive(Debug, Serialize)]
+/// Time spent in each stage of scoring a pair of signals, see `VisqolManager::set_record_timings`.
+/// Stages which did not run, e.g. decoding for `VisqolManager::run_from_samples`, take zero time.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timings {
+    /// Decoding both files and downmixing them to mono.
+    pub decode: Duration,
+    /// Globally aligning the degraded signal to the reference.
+    pub alignment: Duration,
+    /// Building the gammatone spectrograms of both signals.
+    pub spectrogram: Duration,
+    /// Matching and finely aligning the patches, measuring their NSIM and predicting the MOS.
+    pub nsim: Duration,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 /// Contains information for the similarity of 2 signals
 pub struct SimilarityResult {
     /// Predicted MOS: 1 = bad, 5 = excellent
     pub moslqo: f64,
     /// Mean of fvnsim scores
     pub vnsim: f64,
-    /// Similarity score of reference and degraded file per frequency band, ordered from lowest to highest frequency
+    /// Similarity score of reference and degraded file per frequency band, ordered from lowest to highest frequency.
+    /// This is the mean NSIM of all patches in each band and holds `NUM_BANDS` values.
     pub fvnsim: Vec<f64>,
     /// Standard deviation of similarity per frequency band
     pub fstdnsim: Vec<f64>,
@@ -19,6 +45,37 @@ pub struct SimilarityResult {
     pub center_freq_bands: Vec<f64>,
     /// Similarity data for each patch in the signal
     pub patch_sims: Vec<PatchSimilarityResult>,
+    /// NSIM score of each matched patch, in the same order as `patch_sims`
+    pub patch_scores: Vec<f64>,
+    /// Frame offset of the degraded patch which was matched to each reference patch, in the same order as `patch_sims`.
+    /// Comparing these with the frame offsets of the reference patches shows how local timing differences were compensated.
+    pub patch_offsets: Vec<usize>,
+    /// Offset in seconds which the degraded signal was shifted by during global alignment.
+    /// A negative value means that the degraded signal lags behind the reference and its beginning was truncated,
+    /// a positive value means that the degraded signal is ahead of the reference and was delayed by zero-padding.
+    pub alignment_lag_seconds: f64,
+    /// Time range of the reference which the degraded signal was located in and scored against, see `VisqolManager::set_find_best_subregion`.
+    /// `None` if the complete reference was scored.
+    pub matched_reference_range: Option<TimeRange>,
+    /// Number of patches whose similarity contributed to the result, i.e. the length of `patch_sims`.
+    pub num_patches: usize,
+    /// Number of patches selected from the reference signal, e.g. those with voice activity for speech.
+    /// Reference patches beyond the end of the degraded signal cannot be compared, so if this is much larger than `num_patches`,
+    /// the degraded signal is likely truncated or misaligned, which explains a low score.
+    pub num_reference_patches: usize,
+    /// NSIM of each reference patch to the degraded patch at each frame offset, with a row per reference patch and a column per frame of the degraded spectrogram.
+    /// Offsets outside the search window of a patch are NaN. Plotted as a heatmap, it shows how confidently each patch was aligned.
+    /// Only recorded if enabled by `VisqolManager::set_record_similarity_matrix`, as it is large for long signals, and not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub similarity_matrix: Option<Array2<f64>>,
+    /// Time spent in each stage, only measured if enabled by `VisqolManager::set_record_timings`.
+    pub timings: Option<Timings>,
+    /// Conditions which did not prevent scoring but make the result less meaningful, e.g. a duration mismatch or dropped reference patches.
+    /// They are also logged if the `logging` feature is enabled, but can be checked here to e.g. flag the result.
+    pub warnings: Vec<VisqolWarning>,
+    /// `false` if too few patches contributed for the MOS to be meaningful, e.g. because the signal is too short or the VAD found little speech in a quiet signal.
+    /// See `VisqolManager::set_min_reliable_patches` for the threshold.
+    pub is_reliable: bool,
 }
 
 impl SimilarityResult {
@@ -38,7 +95,213 @@ impl SimilarityResult {
             fstdnsim,
             fvdegenergy,
             center_freq_bands,
+            patch_scores: patch_sims.iter().map(|patch| patch.similarity).collect(),
+            num_patches: patch_sims.len(),
+            num_reference_patches: patch_sims.len(),
             patch_sims,
+            patch_offsets: Vec::new(),
+            alignment_lag_seconds: 0.0,
+            matched_reference_range: None,
+            similarity_matrix: None,
+            timings: None,
+            warnings: Vec::new(),
+            is_reliable: true,
+        }
+    }
+
+    /// Returns the NSIM score of each matched patch together with the time in seconds of the center of its reference patch, ordered by time.
+    /// Plotted over the reference waveform, this shows where in the signal the quality drops, e.g. because of a short glitch.
+    pub fn vnsim_over_time(&self) -> Vec<(f64, f64)> {
+        let mut curve = self
+            .patch_sims
+            .iter()
+            .map(|patch| {
+                let center = (patch.ref_patch_start_time + patch.ref_patch_end_time) / 2.0;
+                (center, patch.similarity)
+            })
+            .collect::<Vec<(f64, f64)>>();
+        curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+        curve
+    }
+
+    /// Writes the curve of `vnsim_over_time` to `writer` as CSV, one `time,nsim` row per patch, preceded by a `time_seconds,nsim` header row if `header` is set.
+    /// The rows are written one by one, so `writer` should be buffered, e.g. by a `std::io::BufWriter`.
+    pub fn write_patch_csv<W: Write>(
+        &self,
+        mut writer: W,
+        header: bool,
+    ) -> Result<(), VisqolError> {
+        if header {
+            writeln!(writer, "time_seconds,nsim")?;
+        }
+        for (time, nsim) in self.vnsim_over_time() {
+            writeln!(writer, "{},{}", time, nsim)?;
         }
+        Ok(())
+    }
+}
+
+/// Prints the MOS, the mean NSIM and the number of patches, followed by the range of the band similarities and the band scoring worst.
+/// Unreliable results are marked as such.
+/// The alternate form `{:#}` prints the similarity of every band on a separate line instead.
+impl fmt::Display for SimilarityResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let num_patches = self.num_patches;
+        write!(
+            f,
+            "MOS-LQO {:.3}, NSIM {:.3} over {} {}",
+            self.moslqo,
+            self.vnsim,
+            num_patches,
+            if num_patches == 1 { "patch" } else { "patches" }
+        )?;
+        if !self.is_reliable {
+            write!(f, " (unreliable)")?;
+        }
+        let bands = self.center_freq_bands.iter().zip(&self.fvnsim);
+        if f.alternate() {
+            for (center_freq, nsim) in bands {
+                write!(f, "\n{:>8.0} Hz: {:.3}", center_freq, nsim)?;
+            }
+            return Ok(());
+        }
+        let worst_band = bands.clone().min_by(|a, b| a.1.total_cmp(b.1));
+        let best_nsim = bands.map(|(_, nsim)| *nsim).max_by(f64::total_cmp);
+        match (worst_band, best_nsim) {
+            (Some((center_freq, worst_nsim)), Some(best_nsim)) => write!(
+                f,
+                ", band NSIM {:.3} to {:.3} (worst at {:.0} Hz)",
+                worst_nsim, best_nsim, center_freq
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Like a derived `Debug`, but `patch_sims`, which holds several vectors per patch, is summarized by the number of patches.
+impl fmt::Debug for SimilarityResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimilarityResult")
+            .field("moslqo", &self.moslqo)
+            .field("vnsim", &self.vnsim)
+            .field("fvnsim", &self.fvnsim)
+            .field("fstdnsim", &self.fstdnsim)
+            .field("fvdegenergy", &self.fvdegenergy)
+            .field("center_freq_bands", &self.center_freq_bands)
+            .field("patch_sims", &format_args!("[{} patches]", self.patch_sims.len()))
+            .field("patch_scores", &self.patch_scores)
+            .field("patch_offsets", &self.patch_offsets)
+            .field("alignment_lag_seconds", &self.alignment_lag_seconds)
+            .field("matched_reference_range", &self.matched_reference_range)
+            .field("num_patches", &self.num_patches)
+            .field("num_reference_patches", &self.num_reference_patches)
+            .field(
+                "similarity_matrix",
+                &self.similarity_matrix.as_ref().map(|matrix| matrix.dim()),
+            )
+            .field("timings", &self.timings)
+            .field("warnings", &self.warnings)
+            .field("is_reliable", &self.is_reliable)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a result holding two patches of half a second, starting at 0.5 and 0.0 seconds.
+    fn result_with_unordered_patches() -> SimilarityResult {
+        let patch = |start_time: f64, similarity: f64| {
+            let mut patch =
+                PatchSimilarityResult::new(vec![similarity], vec![0.0], vec![1.0], similarity);
+            patch.ref_patch_start_time = start_time;
+            patch.ref_patch_end_time = start_time + 0.5;
+            patch
+        };
+        SimilarityResult::new(
+            4.0,
+            0.8,
+            vec![0.8],
+            vec![0.0],
+            vec![1.0],
+            vec![50.0],
+            vec![patch(0.5, 0.7), patch(0.0, 0.9)],
+        )
+    }
+
+    #[test]
+    fn vnsim_over_time_is_ordered_by_patch_time() {
+        let result = result_with_unordered_patches();
+
+        assert_eq!(result.vnsim_over_time(), vec![(0.25, 0.9), (0.75, 0.7)]);
+    }
+
+    #[test]
+    fn patch_scores_are_written_as_csv() {
+        let result = result_with_unordered_patches();
+        let mut with_header = Vec::new();
+        let mut without_header = Vec::new();
+
+        result.write_patch_csv(&mut with_header, true).unwrap();
+        result.write_patch_csv(&mut without_header, false).unwrap();
+
+        let rows = "0.25,0.9\n0.75,0.7\n";
+        assert_eq!(String::from_utf8(with_header).unwrap(), format!("time_seconds,nsim\n{}", rows));
+        assert_eq!(String::from_utf8(without_header).unwrap(), rows);
+    }
+
+    #[test]
+    fn result_is_displayed_with_band_summary() {
+        let patch = PatchSimilarityResult::new(vec![0.9, 0.6], vec![0.0; 2], vec![1.0; 2], 0.75);
+        let result = SimilarityResult::new(
+            4.0912,
+            0.75,
+            vec![0.9, 0.6],
+            vec![0.0, 0.0],
+            vec![1.0, 1.0],
+            vec![50.0, 150.0],
+            vec![patch],
+        );
+
+        assert_eq!(
+            result.to_string(),
+            "MOS-LQO 4.091, NSIM 0.750 over 1 patch, band NSIM 0.600 to 0.900 (worst at 150 Hz)"
+        );
+        assert_eq!(
+            format!("{:#}", result),
+            "MOS-LQO 4.091, NSIM 0.750 over 1 patch\n      50 Hz: 0.900\n     150 Hz: 0.600"
+        );
+        assert!(format!("{:?}", result).contains("patch_sims: [1 patches]"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trip_preserves_result() {
+        let patch =
+            PatchSimilarityResult::new(vec![0.5, 0.25], vec![0.1, 0.2], vec![1.0, 2.0], 0.3);
+        let result = SimilarityResult::new(
+            4.123456789012345,
+            0.1 + 0.2,
+            vec![0.5, 0.25],
+            vec![0.1, 0.2],
+            vec![1.0, 2.0],
+            vec![50.0, 150.0],
+            vec![patch],
+        );
+
+        let json = serde_json::to_string(&result).unwrap();
+        let deserialized: SimilarityResult = serde_json::from_str(&json).unwrap();
+
+        assert!(json.contains("\"moslqo\""));
+        assert!(json.contains("\"patch_scores\""));
+        assert_eq!(deserialized.moslqo.to_bits(), result.moslqo.to_bits());
+        assert_eq!(deserialized.vnsim.to_bits(), result.vnsim.to_bits());
+        assert_eq!(deserialized.fvnsim, result.fvnsim);
+        assert_eq!(deserialized.patch_scores, result.patch_scores);
+        assert_eq!(
+            deserialized.patch_sims[0].similarity,
+            result.patch_sims[0].similarity
+        );
     }
 }
@@ -1,5 +1,102 @@
+use crate::visqol_error::VisqolError;
+
 /// Trait to provide a method for predicting a MOS based on features.
 /// Given a feature, the implementations of this trait compute a single score.
-pub trait SimilarityToQualityMapper {
+/// Custom implementations can be passed to `VisqolManager::with_mapper` to replace the mapping selected by the variant.
+/// Implementations must be `Send + Sync`, so that a `VisqolManager` can be shared across threads.
+pub trait SimilarityToQualityMapper: Send + Sync {
+    /// Predicts the MOS from `features`, the mean similarity per frequency band (`SimilarityResult::fvnsim`).
     fn predict_quality(&self, features: &[f64]) -> f64;
+
+    /// Returns the MOS of a perfect similarity in each of the `num_bands` frequency bands, which is used for identical signals.
+    /// Defaults to the prediction for a similarity of 1 in every band.
+    fn max_quality(&self, num_bands: usize) -> f64 { self.predict_quality(&vec![1.0; num_bands]) }
+
+    /// Returns the number of features, i.e. frequency bands, if the mapper only supports a fixed number of them.
+    /// Defaults to `None`, which accepts any number of bands.
+    fn num_features(&self) -> Option<usize> { None }
+}
+
+/// Scales the similarity of each frequency band by a weight before predicting the MOS with another mapper.
+/// The weights are normalized to a mean of 1, so the mean of the scaled similarities, on which the speech mapping is based, is the weighted mean of the band similarities.
+pub struct BandWeightedMapper {
+    mapper: Box<dyn SimilarityToQualityMapper>,
+    band_weights: Vec<f64>,
+}
+
+impl BandWeightedMapper {
+    /// Creates a mapper which applies `band_weights`, one per frequency band, before predicting the MOS with `mapper`.
+    /// Returns `VisqolError::InvalidBandWeights` unless there are `num_bands` finite, non-negative weights which are not all zero.
+    pub fn new(
+        mapper: Box<dyn SimilarityToQualityMapper>,
+        band_weights: Vec<f64>,
+        num_bands: usize,
+    ) -> Result<Self, VisqolError> {
+        let sum = band_weights.iter().sum::<f64>();
+        let valid_weights = band_weights.iter().all(|weight| weight.is_finite() && *weight >= 0.0);
+        if band_weights.len() != num_bands || !valid_weights || sum <= 0.0 {
+            return Err(VisqolError::InvalidBandWeights {
+                num_weights: band_weights.len(),
+                num_bands,
+            });
+        }
+        let scale = num_bands as f64 / sum;
+        Ok(Self {
+            mapper,
+            band_weights: band_weights.iter().map(|weight| weight * scale).collect(),
+        })
+    }
+}
+
+impl SimilarityToQualityMapper for BandWeightedMapper {
+    fn predict_quality(&self, features: &[f64]) -> f64 {
+        let weighted_features = features
+            .iter()
+            .zip(&self.band_weights)
+            .map(|(feature, weight)| feature * weight)
+            .collect::<Vec<f64>>();
+        self.mapper.predict_quality(&weighted_features)
+    }
+
+    /// A perfect similarity in every band is not affected by the weights, so this is the maximum MOS of the wrapped mapper.
+    fn max_quality(&self, num_bands: usize) -> f64 { self.mapper.max_quality(num_bands) }
+
+    fn num_features(&self) -> Option<usize> { self.mapper.num_features() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    /// Predicts the mean of the features, like the speech mapping before fitting it to the MOS scale.
+    struct MeanMapper;
+    impl SimilarityToQualityMapper for MeanMapper {
+        fn predict_quality(&self, features: &[f64]) -> f64 {
+            features.iter().sum::<f64>() / features.len() as f64
+        }
+    }
+
+    #[test]
+    fn band_weights_are_applied_as_weighted_mean() {
+        let weighted = BandWeightedMapper::new(Box::new(MeanMapper), vec![1.0, 3.0], 2).unwrap();
+        let uniform = BandWeightedMapper::new(Box::new(MeanMapper), vec![2.0, 2.0], 2).unwrap();
+
+        assert_abs_diff_eq!(weighted.predict_quality(&[0.2, 0.6]), 0.5, epsilon = 1e-12);
+        assert_abs_diff_eq!(uniform.predict_quality(&[0.2, 0.6]), 0.4, epsilon = 1e-12);
+        assert_eq!(weighted.max_quality(2), 1.0);
+    }
+
+    #[test]
+    fn invalid_band_weights_are_rejected() {
+        for band_weights in [vec![1.0], vec![1.0, -1.0], vec![0.0, 0.0], vec![1.0, f64::NAN]] {
+            assert!(matches!(
+                BandWeightedMapper::new(Box::new(MeanMapper), band_weights, 2),
+                Err(VisqolError::InvalidBandWeights {
+                    num_bands: 2,
+                    ..
+                })
+            ));
+        }
+    }
 }
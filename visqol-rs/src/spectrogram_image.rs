@@ -0,0 +1,126 @@
+use image::{Rgb, RgbImage};
+use ndarray::Array2;
+
+use crate::visqol_error::VisqolError;
+
+/// Control points of the viridis colormap, evenly spaced from the lowest to the highest value.
+const VIRIDIS: [[f64; 3]; 9] = [
+    [68.0, 1.0, 84.0],
+    [71.0, 44.0, 122.0],
+    [59.0, 81.0, 139.0],
+    [44.0, 113.0, 142.0],
+    [33.0, 144.0, 141.0],
+    [39.0, 173.0, 129.0],
+    [92.0, 200.0, 99.0],
+    [170.0, 220.0, 50.0],
+    [253.0, 231.0, 37.0],
+];
+
+/// Determines how the values of a spectrogram are mapped to colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Colormap {
+    /// Maps the lowest value to black and the highest value to white.
+    Grayscale,
+    /// Maps the values to the perceptually uniform viridis colormap, from dark purple to yellow.
+    #[default]
+    Viridis,
+}
+
+impl Colormap {
+    /// Returns the color of `value`, which is expected to lie in [0, 1].
+    fn color(&self, value: f64) -> Rgb<u8> {
+        match self {
+            Colormap::Grayscale => {
+                let intensity = (value * 255.0).round() as u8;
+                Rgb([intensity; 3])
+            }
+            Colormap::Viridis => {
+                let position = value * (VIRIDIS.len() - 1) as f64;
+                let lower = (position.floor() as usize).min(VIRIDIS.len() - 2);
+                let fraction = position - lower as f64;
+                let channel = |i: usize| {
+                    let interpolated =
+                        VIRIDIS[lower][i] + (VIRIDIS[lower + 1][i] - VIRIDIS[lower][i]) * fraction;
+                    interpolated.round() as u8
+                };
+                Rgb([channel(0), channel(1), channel(2)])
+            }
+        }
+    }
+}
+
+/// Renders `spectrogram` to a PNG file at `path` using the default colormap, see `export_spectrogram_png_with`.
+pub fn export_spectrogram_png(spectrogram: &Array2<f64>, path: &str) -> Result<(), VisqolError> {
+    export_spectrogram_png_with(spectrogram, path, Colormap::default())
+}
+
+/// Renders `spectrogram`, whose rows signify frequency bands and whose columns signify frames, to a PNG file at `path`.
+/// Each value becomes one pixel, with the lowest frequency band at the bottom of the image.
+/// The values are normalized from the minimum to the maximum of the spectrogram before `colormap` is applied.
+pub fn export_spectrogram_png_with(
+    spectrogram: &Array2<f64>,
+    path: &str,
+    colormap: Colormap,
+) -> Result<(), VisqolError> {
+    render_spectrogram(spectrogram, colormap).save(path)?;
+    Ok(())
+}
+
+/// Maps each value of `spectrogram` to a pixel of an image with one row per frequency band, the lowest band in the bottom row.
+fn render_spectrogram(spectrogram: &Array2<f64>, colormap: Colormap) -> RgbImage {
+    let finite_values = || spectrogram.iter().copied().filter(|value| value.is_finite());
+    let min = finite_values().fold(f64::INFINITY, f64::min);
+    let max = finite_values().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+
+    let num_rows = spectrogram.nrows();
+    RgbImage::from_fn(spectrogram.ncols() as u32, num_rows as u32, |x, y| {
+        let value = spectrogram[(num_rows - 1 - y as usize, x as usize)];
+        let normalized = if range > 0.0 && value.is_finite() {
+            ((value - min) / range).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        colormap.color(normalized)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::arr2;
+
+    #[test]
+    fn values_are_normalized_to_full_range() {
+        let spectrogram = arr2(&[[-10.0, 0.0, 10.0], [10.0, 10.0, 10.0]]);
+
+        let image = render_spectrogram(&spectrogram, Colormap::Grayscale);
+
+        assert_eq!(image.dimensions(), (3, 2));
+        // The lowest band is drawn at the bottom.
+        assert_eq!(image.get_pixel(0, 1), &Rgb([0, 0, 0]));
+        assert_eq!(image.get_pixel(1, 1), &Rgb([128, 128, 128]));
+        assert_eq!(image.get_pixel(2, 1), &Rgb([255, 255, 255]));
+        assert_eq!(image.get_pixel(0, 0), &Rgb([255, 255, 255]));
+    }
+
+    #[test]
+    fn viridis_spans_from_purple_to_yellow() {
+        assert_eq!(Colormap::Viridis.color(0.0), Rgb([68, 1, 84]));
+        assert_eq!(Colormap::Viridis.color(1.0), Rgb([253, 231, 37]));
+        assert_eq!(Colormap::Viridis.color(0.5), Rgb([33, 144, 141]));
+    }
+
+    #[test]
+    fn spectrogram_is_written_to_png() {
+        let spectrogram = Array2::from_shape_fn((4, 8), |(band, frame)| (band * frame) as f64);
+        let path = crate::test_utility::temp_path("visqol_spectrogram_export_test.png");
+        let path = path.to_str().unwrap();
+
+        export_spectrogram_png(&spectrogram, path).unwrap();
+        let image = image::open(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!((image.width(), image.height()), (8, 4));
+    }
+}
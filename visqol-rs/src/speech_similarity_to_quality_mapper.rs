@@ -1,38 +1,72 @@
 use crate::math_utils;
 use crate::similarity_to_quality_mapper::SimilarityToQualityMapper;
 
+/// Coefficients of the curve `a + exp(b * (nsim - x0))` which maps the mean NSIM to a MOS, see `SpeechSimilarityToQualityMapper`.
+/// The default coefficients are those fitted for ViSQOL.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeechMappingCoefficients {
+    pub a: f64,
+    pub b: f64,
+    pub x0: f64,
+    /// Factor the MOS is multiplied by if the maximum is scaled to the MOS, chosen so that a similarity of 1.0 maps to 5.0.
+    pub scale: f64,
+}
+
+impl Default for SpeechMappingCoefficients {
+    fn default() -> Self {
+        Self {
+            a: 1.155_945_5,
+            b: 4.685_115_3,
+            x0: 0.765_523_2,
+            scale: 1.2031409,
+        }
+    }
+}
+
 /// Maps a similarity score to a MOS using polynomial mapping.
 pub struct SpeechSimilarityToQualityMapper {
     scale_max_to_mos: bool,
+    coefficients: SpeechMappingCoefficients,
 }
 
 impl SpeechSimilarityToQualityMapper {
     /// Creates a new `SpeechSimilarityToQualityMapper`.
     /// If `scale_max_to_mos` is set to true, the a quality score of 1.0 will be mapped to 5.0. If not, will be mapped to 4.x.
     pub fn new(scale_to_max_mos: bool) -> Self {
+        Self::with_coefficients(SpeechMappingCoefficients::default(), scale_to_max_mos)
+    }
+
+    /// Creates a mapper using `coefficients` instead of those fitted for ViSQOL, e.g. fitted to a recalibrated dataset.
+    /// If `scale_max_to_mos` is set to true, the MOS is multiplied by `coefficients.scale`.
+    pub fn with_coefficients(
+        coefficients: SpeechMappingCoefficients,
+        scale_max_to_mos: bool,
+    ) -> Self {
         Self {
-            scale_max_to_mos: scale_to_max_mos,
+            scale_max_to_mos,
+            coefficients,
         }
     }
-}
 
-impl SimilarityToQualityMapper for SpeechSimilarityToQualityMapper {
-    fn predict_quality(&self, similarity_vector: &[f64]) -> f64 {
-        const FIT_PARAMETER_A: f64 = 1.155_945_5;
-        const FIT_PARAMETER_B: f64 = 4.685_115_3;
-        const FIT_PARAMETER_X0: f64 = 0.765_523_2;
-        const FIT_SCALE: f64 = 1.2031409;
+    /// Returns the coefficients of the mapping curve.
+    pub fn coefficients(&self) -> SpeechMappingCoefficients { self.coefficients }
 
-        let nsim_mean = similarity_vector.iter().sum::<f64>() / (similarity_vector.len() as f64);
-        let mos = math_utils::exponential_from_fit(
-            nsim_mean,
-            FIT_PARAMETER_A,
-            FIT_PARAMETER_B,
-            FIT_PARAMETER_X0,
-        );
+    /// Returns the MOS which each of `nsim_values` is mapped to as the mean NSIM over all bands, e.g. to plot the mapping curve.
+    /// The MOS is clamped to the range from 1.0 to 5.0 like `predict_quality`, see `SpeechMappingCoefficients` for the curve.
+    pub fn map_range(&self, nsim_values: &[f64]) -> Vec<f64> {
+        nsim_values
+            .iter()
+            .map(|nsim_mean| self.map_mean(*nsim_mean))
+            .collect()
+    }
+
+    /// Maps the mean NSIM over all bands to a MOS.
+    fn map_mean(&self, nsim_mean: f64) -> f64 {
+        let SpeechMappingCoefficients { a, b, x0, scale } = self.coefficients;
+        let mos = math_utils::exponential_from_fit(nsim_mean, a, b, x0);
 
         let scale = if self.scale_max_to_mos {
-            FIT_SCALE
+            scale
         } else {
             1.0
         };
@@ -40,3 +74,61 @@ impl SimilarityToQualityMapper for SpeechSimilarityToQualityMapper {
         (mos * scale).clamp(1.0, 5.0)
     }
 }
+
+impl SimilarityToQualityMapper for SpeechSimilarityToQualityMapper {
+    fn predict_quality(&self, similarity_vector: &[f64]) -> f64 {
+        let nsim_mean = similarity_vector.iter().sum::<f64>() / (similarity_vector.len() as f64);
+        self.map_mean(nsim_mean)
+    }
+
+    /// Returns exactly 5.0 if the maximum is scaled to the MOS with the default coefficients, as the fitted scale only approximates it.
+    fn max_quality(&self, num_bands: usize) -> f64 {
+        if self.scale_max_to_mos && self.coefficients == SpeechMappingCoefficients::default() {
+            5.0
+        } else {
+            self.predict_quality(&vec![1.0; num_bands])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn range_is_mapped_like_mean_similarity() {
+        let mapper = SpeechSimilarityToQualityMapper::new(true);
+        let nsim_values = [0.0, 0.5, 0.75, 0.9, 1.0];
+
+        let mos = mapper.map_range(&nsim_values);
+
+        assert_eq!(mos.len(), nsim_values.len());
+        for (nsim, mos) in nsim_values.iter().zip(&mos) {
+            assert_abs_diff_eq!(*mos, mapper.predict_quality(&[*nsim; 4]), epsilon = 1e-12);
+        }
+        assert_abs_diff_eq!(mos[0], 1.424, epsilon = 0.001);
+        assert!(mos.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_abs_diff_eq!(mos[4], 5.0, epsilon = 0.01);
+    }
+
+    #[test]
+    fn custom_coefficients_are_applied() {
+        let coefficients = SpeechMappingCoefficients {
+            a: 1.0,
+            b: 2.0,
+            x0: 1.0,
+            scale: 2.0,
+        };
+        let default_mapper = SpeechSimilarityToQualityMapper::new(false);
+        let unscaled = SpeechSimilarityToQualityMapper::with_coefficients(coefficients, false);
+        let scaled = SpeechSimilarityToQualityMapper::with_coefficients(coefficients, true);
+
+        assert_eq!(default_mapper.coefficients(), SpeechMappingCoefficients::default());
+        assert_eq!(scaled.coefficients(), coefficients);
+        assert_abs_diff_eq!(unscaled.predict_quality(&[1.0, 1.0]), 2.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(scaled.predict_quality(&[1.0, 1.0]), 4.0, epsilon = 1e-12);
+        assert_abs_diff_eq!(scaled.max_quality(2), 4.0, epsilon = 1e-12);
+        assert_eq!(SpeechSimilarityToQualityMapper::new(true).max_quality(2), 5.0);
+    }
+}
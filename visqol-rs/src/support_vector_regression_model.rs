@@ -1,22 +1,32 @@
 use ffsvm::{self, DenseFeatures, DenseSVM, Label, Predict};
+use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::fs::read_to_string;
+
+use crate::visqol_error::VisqolError;
 
 /// Thin wrapper around `ffsvm` to compute a prediction from a support vector machine.
 pub struct SupportVectorRegressionModel {
     model: ffsvm::DenseSVM,
+    num_features: usize,
 }
 
 impl SupportVectorRegressionModel {
-    /// Given a path to a `LibSVM` formatted `.txt` file, the model is initialized with its corresponding weights.
-    pub fn new(model_path: &str) -> Self {
-        let model_description = read_to_string(model_path)
-            .unwrap_or_else(|_| panic!("failed to read model path from {}!", model_path));
-        Self {
-            model: DenseSVM::try_from(model_description.as_str())
-                .expect("Failed to load SVM model"),
-        }
+    /// Initializes the model with the weights of the `LibSVM` formatted `model_description`.
+    /// Returns `VisqolError::InvalidModel` describing the problem if the description is not a valid regression model.
+    pub fn from_description(model_description: &str) -> Result<Self, VisqolError> {
+        let invalid_model = |reason: String| VisqolError::InvalidModel { path: None, reason };
+        let num_features = validate_description(model_description).map_err(invalid_model)?;
+        let model = DenseSVM::try_from(model_description)
+            .map_err(|error| invalid_model(format!("{:?}", error)))?;
+        Ok(Self {
+            model,
+            num_features,
+        })
     }
+
+    /// Returns the number of features of each support vector, which `predict` expects as the length of the observation.
+    pub fn num_features(&self) -> usize { self.num_features }
+
     /// Given a slice of features, this function produces a single score.
     pub fn predict(&self, observation: &[f64]) -> f64 {
         let mut problem = DenseFeatures::from(&self.model);
@@ -37,10 +47,121 @@ impl SupportVectorRegressionModel {
     }
 }
 
+/// Checks the header and support vectors of `model_description`, as `ffsvm` either accepts inconsistent models or fails without context.
+/// Returns the number of features of the support vectors or a description of the first problem, e.g. for a file of another format or a truncated file.
+fn validate_description(model_description: &str) -> Result<usize, String> {
+    let mut lines = model_description
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    let mut header = HashMap::new();
+    let mut found_support_vectors = false;
+    for line in lines.by_ref() {
+        if line == "SV" {
+            found_support_vectors = true;
+            break;
+        }
+        let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        header.insert(key, value.trim());
+    }
+
+    let header_value = |key: &str| {
+        header
+            .get(key)
+            .copied()
+            .ok_or_else(|| format!("missing {} header, expected a libSVM model file", key))
+    };
+    let numeric_header = |key: &str| {
+        let value = header_value(key)?;
+        value
+            .parse::<f64>()
+            .map_err(|_| format!("expected a number for {}, found {:?}", key, value))
+    };
+
+    match header_value("svm_type")? {
+        "nu_svr" | "epsilon_svr" => {}
+        svm_type => {
+            return Err(format!(
+                "expected svm_type nu_svr or epsilon_svr, found {:?}",
+                svm_type
+            ))
+        }
+    }
+    let kernel_parameters: &[&str] = match header_value("kernel_type")? {
+        "linear" => &[],
+        "rbf" => &["gamma"],
+        "sigmoid" => &["gamma", "coef0"],
+        "poly" => &["gamma", "coef0", "degree"],
+        kernel_type => return Err(format!("unsupported kernel_type {:?}", kernel_type)),
+    };
+    for parameter in kernel_parameters {
+        numeric_header(parameter)?;
+    }
+    numeric_header("rho")?;
+    let total_sv = header_value("total_sv")?;
+    let total_sv = total_sv
+        .parse::<usize>()
+        .map_err(|_| format!("expected a count for total_sv, found {:?}", total_sv))?;
+    if !found_support_vectors {
+        return Err("missing SV line, the file ends after the header".to_string());
+    }
+
+    let mut num_support_vectors = 0;
+    let mut num_features = None;
+    for (index, line) in lines.enumerate() {
+        let support_vector = index + 1;
+        let mut tokens = line.split_whitespace();
+        let coefficient = tokens.next().unwrap_or_default();
+        if coefficient.parse::<f64>().is_err() {
+            return Err(format!(
+                "expected a coefficient at the start of support vector {}, found {:?}",
+                support_vector, coefficient
+            ));
+        }
+        let mut count = 0;
+        for token in tokens {
+            let attribute = token.split_once(':').and_then(|(attribute, value)| {
+                value.parse::<f64>().ok()?;
+                attribute.parse::<usize>().ok()
+            });
+            if attribute != Some(count + 1) {
+                return Err(format!(
+                    "expected feature {} of support vector {} in the form {}:<value>, found {:?}",
+                    count + 1,
+                    support_vector,
+                    count + 1,
+                    token
+                ));
+            }
+            count += 1;
+        }
+        match num_features {
+            None => num_features = Some(count),
+            Some(expected) if expected != count => {
+                return Err(format!(
+                    "expected {} features for every support vector, found {} in support vector {}",
+                    expected, count, support_vector
+                ))
+            }
+            Some(_) => {}
+        }
+        num_support_vectors += 1;
+    }
+    if num_support_vectors != total_sv {
+        return Err(format!(
+            "total_sv declares {} support vectors, found {}, the file may be truncated",
+            total_sv, num_support_vectors
+        ));
+    }
+    Ok(num_features.unwrap_or_default())
+}
+
 #[cfg(test)]
 mod tests {
     use super::SupportVectorRegressionModel;
+    use crate::visqol_error::VisqolError;
     use approx::assert_abs_diff_eq;
+    use std::fs::read_to_string;
     #[test]
     fn svn_predicts_known_mos() {
         let model_path = concat!(
@@ -50,7 +171,8 @@ mod tests {
             "/",
             "model/libsvm_nu_svr_model.txt"
         );
-        let svm = SupportVectorRegressionModel::new(model_path);
+        let model_description = read_to_string(model_path).unwrap();
+        let svm = SupportVectorRegressionModel::from_description(&model_description).unwrap();
 
         // This is the FVNSIM results for a ViSQOL comparison between
         // contrabassoon48_stereo.wav and contrabassoon48_stereo_24kbps_aac.wav
@@ -66,4 +188,25 @@ mod tests {
         let predicted_score = svm.predict(&observation);
         assert_abs_diff_eq!(predicted_score, expected_score, epsilon = 0.00001);
     }
+
+    #[test]
+    fn invalid_descriptions_are_described() {
+        let reason = |model_description: &str| {
+            match SupportVectorRegressionModel::from_description(model_description) {
+                Err(VisqolError::InvalidModel { reason, .. }) => reason,
+                _ => panic!("expected an invalid model"),
+            }
+        };
+
+        assert!(reason("not a model").contains("svm_type"));
+        assert!(reason("svm_type c_svc\n").contains("nu_svr"));
+        assert!(reason("svm_type nu_svr\nkernel_type rbf\ntotal_sv 1\nrho 0\n").contains("gamma"));
+        assert!(reason("svm_type nu_svr\nkernel_type rbf\ngamma 1\ntotal_sv 1\nrho 0\n")
+            .contains("SV line"));
+        let description = "svm_type nu_svr\nkernel_type linear\ntotal_sv 2\nrho 0\nSV\n";
+        assert!(reason(&format!("{}1 1:0.5 2:0.5\n1 1:0.5\n", description))
+            .contains("expected 2 features"));
+        assert!(reason(&format!("{}1 1:0.5 3:0.5\n", description)).contains("feature 2"));
+        assert!(reason(&format!("{}1 1:0.5 2:0.5\n", description)).contains("truncated"));
+    }
 }
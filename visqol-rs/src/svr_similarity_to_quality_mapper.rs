@@ -1,5 +1,11 @@
+use std::path::Path;
+
 use crate::similarity_to_quality_mapper::SimilarityToQualityMapper;
 use crate::support_vector_regression_model::SupportVectorRegressionModel;
+use crate::visqol_error::VisqolError;
+
+/// The default model of ViSQOL, trained on the TCDAudio14, AACvOpus14 and CoreSV15 datasets.
+const EMBEDDED_MODEL: &[u8] = include_bytes!("../model/libsvm_nu_svr_model.txt");
 
 /// Maps a a similarity score to a MOS using support vector regression.
 pub struct SvrSimilarityToQualityMapper {
@@ -8,10 +14,47 @@ pub struct SvrSimilarityToQualityMapper {
 
 impl SvrSimilarityToQualityMapper {
     /// Initializes the model's weights with a libSVM formatted file located in `model_path`
+    ///
+    /// # Panics
+    /// Panics if the model cannot be loaded, see `SvrSimilarityToQualityMapper::from_path` for a fallible alternative.
     pub fn new(model_path: &str) -> Self {
-        Self {
-            model: SupportVectorRegressionModel::new(model_path),
+        Self::from_path(model_path).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Initializes the model's weights with a libSVM formatted file located in `model_path`.
+    /// Returns `VisqolError::FileNotFound` if the file does not exist and `VisqolError::InvalidModel` if it cannot be parsed.
+    pub fn from_path(model_path: &str) -> Result<Self, VisqolError> {
+        if !Path::new(model_path).exists() {
+            return Err(VisqolError::FileNotFound {
+                path: model_path.to_string(),
+            });
         }
+        Self::from_bytes(&std::fs::read(model_path)?).map_err(|error| match error {
+            VisqolError::InvalidModel { reason, .. } => VisqolError::InvalidModel {
+                path: Some(model_path.to_string()),
+                reason,
+            },
+            error => error,
+        })
+    }
+
+    /// Initializes the model's weights with the contents of a libSVM formatted model file, e.g. fetched from object storage at runtime.
+    /// Returns `VisqolError::InvalidModel` if `model` cannot be parsed.
+    pub fn from_bytes(model: &[u8]) -> Result<Self, VisqolError> {
+        let model_description =
+            std::str::from_utf8(model).map_err(|error| VisqolError::InvalidModel {
+                path: None,
+                reason: error.to_string(),
+            })?;
+        Ok(Self {
+            model: SupportVectorRegressionModel::from_description(model_description)?,
+        })
+    }
+
+    /// Initializes the model's weights with the default model of ViSQOL, which is embedded in the library.
+    /// No file needs to be shipped alongside the binary, which e.g. simplifies deployments in containers.
+    pub fn embedded() -> Self {
+        Self::from_bytes(EMBEDDED_MODEL).expect("The embedded model is valid")
     }
 }
 
@@ -20,4 +63,69 @@ impl SimilarityToQualityMapper for SvrSimilarityToQualityMapper {
         let solution = self.model.predict(similarity_vector);
         solution.clamp(1.0, 5.0)
     }
+
+    /// The model was trained on a fixed number of bands, e.g. 32 for the embedded model.
+    fn num_features(&self) -> Option<usize> { Some(self.model.num_features()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_model_matches_model_file() {
+        let model_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../model/libsvm_nu_svr_model.txt");
+        let similarity_vector = [0.9; 32];
+
+        let embedded = SvrSimilarityToQualityMapper::embedded();
+        let from_file = SvrSimilarityToQualityMapper::new(model_path);
+
+        assert_eq!(
+            embedded.predict_quality(&similarity_vector),
+            from_file.predict_quality(&similarity_vector)
+        );
+    }
+
+    #[test]
+    fn embedded_model_expects_one_feature_per_band() {
+        assert_eq!(SvrSimilarityToQualityMapper::embedded().num_features(), Some(32));
+    }
+
+    #[test]
+    fn model_is_loaded_from_bytes() {
+        let similarity_vector = [0.9; 32];
+
+        let from_bytes = SvrSimilarityToQualityMapper::from_bytes(EMBEDDED_MODEL).unwrap();
+
+        assert_eq!(
+            from_bytes.predict_quality(&similarity_vector),
+            SvrSimilarityToQualityMapper::embedded().predict_quality(&similarity_vector)
+        );
+        assert!(matches!(
+            SvrSimilarityToQualityMapper::from_bytes(b"not a model"),
+            Err(VisqolError::InvalidModel { path: None, .. })
+        ));
+        assert!(matches!(
+            SvrSimilarityToQualityMapper::from_path("does_not_exist.txt"),
+            Err(VisqolError::FileNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn truncated_model_is_rejected() {
+        let path = crate::test_utility::temp_path("visqol_truncated_model.txt");
+        std::fs::write(&path, &EMBEDDED_MODEL[..EMBEDDED_MODEL.len() / 2]).unwrap();
+        let path = path.to_str().unwrap();
+
+        match SvrSimilarityToQualityMapper::from_path(path) {
+            Err(VisqolError::InvalidModel {
+                path: Some(error_path),
+                reason,
+            }) => {
+                assert_eq!(error_path, path);
+                assert!(reason.contains("support vector"));
+            }
+            _ => panic!("expected an invalid model"),
+        }
+    }
 }
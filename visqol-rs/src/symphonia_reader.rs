@@ -0,0 +1,200 @@
+use std::io::{Cursor, Read};
+
+use crate::audio_utils::AudioInfo;
+use crate::visqol_error::VisqolError;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, Track};
+use symphonia::core::io::{MediaSource, MediaSourceStream, ReadOnlySource};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Represents the metadata and decoded contents of a file in any format supported by `symphonia`.
+/// Simple wrapper around the `symphonia` library.
+///
+/// MP3 streams start with the encoder delay and the decoder delay of 529 samples and end with padding up to a full frame.
+/// If the file carries a LAME/Xing header, these are trimmed from the decoded samples (typically 576 + 529 = 1105 samples at the start).
+/// Files without such a header are returned untrimmed, leaving the offset to be compensated by the global alignment.
+pub struct SymphoniaFile {
+    /// The number of channels in the file
+    pub num_channels: u16,
+    /// The sample rate of the file
+    pub sample_rate: u32,
+    /// The interleaved, decoded samples of the first audio track in the range -1.0 to 1.0.
+    pub samples: Vec<f32>,
+}
+
+impl SymphoniaFile {
+    /// given a `reader` providing the contents of an audio file, the container is probed and the first audio track is decoded.
+    /// `extension` is an optional file extension which helps `symphonia` to probe the container.
+    /// Packets which fail to decode are skipped. If no packet can be decoded, `VisqolError::NoAudioTrack` is returned.
+    /// Any other errors are reported by `symphonia`.
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        extension: Option<&str>,
+    ) -> Result<Self, VisqolError> {
+        // The compressed contents are buffered, since symphonia requires a thread-safe source.
+        let mut contents = Vec::<u8>::new();
+        reader.read_to_end(&mut contents)?;
+        let mut format_reader = probe_format(Box::new(Cursor::new(contents)), extension)?;
+
+        let track = first_audio_track(format_reader.as_ref())?;
+        let track_id = track.id;
+        let mut decoder =
+            symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+        let mut num_channels = 0;
+        let mut sample_rate = 0;
+        let mut samples = Vec::<f32>::new();
+
+        loop {
+            let packet = match format_reader.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(error))
+                    if error.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    break;
+                }
+                Err(error) => return Err(error.into()),
+            };
+
+            if packet.track_id() != track_id {
+                continue;
+            }
+
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(error) => return Err(error.into()),
+            };
+            let spec = *decoded.spec();
+            num_channels = spec.channels.count() as u16;
+            sample_rate = spec.rate;
+
+            let mut sample_buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+            sample_buffer.copy_interleaved_ref(decoded);
+            samples.extend_from_slice(sample_buffer.samples());
+        }
+
+        if num_channels == 0 {
+            return Err(VisqolError::NoAudioTrack);
+        }
+
+        Ok(Self {
+            num_channels,
+            sample_rate,
+            samples,
+        })
+    }
+
+    /// Reads the format of the first audio track from the container provided by `reader` without decoding any packets, see `audio_utils::probe`.
+    /// The bit depth is only known for uncompressed codecs, and the duration if the container stores the number of frames.
+    pub fn probe<R: Read + Send + Sync + 'static>(
+        reader: R,
+        extension: Option<&str>,
+    ) -> Result<AudioInfo, VisqolError> {
+        let format_reader = probe_format(Box::new(ReadOnlySource::new(reader)), extension)?;
+        let codec_params = &first_audio_track(format_reader.as_ref())?.codec_params;
+        let sample_rate = codec_params.sample_rate.ok_or(VisqolError::NoAudioTrack)?;
+
+        Ok(AudioInfo {
+            sample_rate,
+            channels: codec_params.channels.map_or(0, |channels| channels.count() as u16),
+            bit_depth: codec_params.bits_per_sample.map(|bits| bits as u16),
+            duration: codec_params
+                .n_frames
+                .map(|num_frames| num_frames as f64 / sample_rate as f64),
+        })
+    }
+}
+
+/// Probes the container provided by `source`, helped by the file `extension` if available.
+fn probe_format(
+    source: Box<dyn MediaSource>,
+    extension: Option<&str>,
+) -> Result<Box<dyn FormatReader>, VisqolError> {
+    let media_source = MediaSourceStream::new(source, Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = extension {
+        hint.with_extension(extension);
+    }
+
+    let format_options = FormatOptions {
+        enable_gapless: true,
+        ..Default::default()
+    };
+
+    let probed = symphonia::default::get_probe().format(
+        &hint,
+        media_source,
+        &format_options,
+        &MetadataOptions::default(),
+    )?;
+    Ok(probed.format)
+}
+
+/// Returns the first track of `format_reader` with a known codec.
+fn first_audio_track(format_reader: &dyn FormatReader) -> Result<&Track, VisqolError> {
+    format_reader
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(VisqolError::NoAudioTrack)
+}
+
+#[cfg(all(test, feature = "symphonia"))]
+mod tests {
+    use super::SymphoniaFile;
+    use crate::visqol_error::VisqolError;
+    use std::fs::File;
+
+    #[test]
+    fn flac_file_is_probed_and_decoded() {
+        let file = File::open("test_data/CA01_01.flac").unwrap();
+        let decoded = SymphoniaFile::from_reader(file, None).unwrap();
+        let reference = hound::WavReader::open("test_data/CA01_01.wav")
+            .unwrap()
+            .samples::<i16>()
+            .map(|sample| sample.unwrap() as f32 / 32768.0)
+            .take(decoded.samples.len())
+            .collect::<Vec<f32>>();
+
+        assert_eq!(decoded.num_channels, 1);
+        assert_eq!(decoded.sample_rate, 48000);
+        assert_eq!(decoded.samples, reference);
+    }
+
+    #[test]
+    fn mp3_file_is_probed_and_decoded() {
+        let file = File::open("test_data/CA01_01.mp3").unwrap();
+        let decoded = SymphoniaFile::from_reader(file, Some("mp3")).unwrap();
+
+        assert_eq!(decoded.num_channels, 1);
+        assert_eq!(decoded.sample_rate, 48000);
+        assert!(!decoded.samples.is_empty());
+    }
+
+    #[test]
+    fn stream_without_decodable_packets_is_rejected() {
+        let garbage = std::iter::repeat_n(0xFFu8, 4096).collect::<Vec<u8>>();
+        let result = SymphoniaFile::from_reader(garbage.as_slice(), Some("mp3"));
+
+        assert!(matches!(
+            result,
+            Err(VisqolError::NoAudioTrack | VisqolError::Symphonia(_))
+        ));
+    }
+
+    #[test]
+    fn mp3_file_is_probed() {
+        let file = File::open("test_data/CA01_01.mp3").unwrap();
+        let info = SymphoniaFile::probe(file, Some("mp3")).unwrap();
+
+        assert_eq!(info.sample_rate, 48000);
+        assert_eq!(info.channels, 1);
+        assert_eq!(info.bit_depth, None);
+    }
+}
@@ -1,6 +1,16 @@
 use approx::assert_abs_diff_eq;
 use ndarray::Array2;
 use num::complex::Complex;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Returns a path for `file_name` in the temporary directory which is unique to the calling test,
+/// so that tests running in parallel or in concurrent test processes do not share files.
+pub fn temp_path(file_name: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("{}_{}_{}", std::process::id(), count, file_name))
+}
 
 pub fn compare_matrix_dimensions<T>(matrix_a: &Array2<T>, matrix_b: &Array2<T>) {
     assert!(
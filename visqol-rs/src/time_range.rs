@@ -0,0 +1,20 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A range of time in seconds, e.g. the part of a signal which a segment was cut from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TimeRange {
+    /// Start of the range in seconds
+    pub start: f64,
+    /// End of the range in seconds, exclusive
+    pub end: f64,
+}
+
+impl TimeRange {
+    /// Creates the range from `start` to `end` seconds.
+    pub fn new(start: f64, end: f64) -> Self { Self { start, end } }
+
+    /// Returns the duration of the range in seconds.
+    pub fn duration(&self) -> f64 { self.end - self.start }
+}
@@ -1,12 +1,15 @@
-use crate::patch_creator::PatchCreator;
+use crate::constants;
+use crate::patch_creator::{Patch, PatchCreator};
 use crate::visqol_error::VisqolError;
 use crate::{analysis_window::AnalysisWindow, audio_signal::AudioSignal, math_utils, rms_vad};
-use itertools::Itertools;
-use ndarray::{s, Array2};
+use ndarray::Array2;
 /// Computes patch indices from a spectrogram by analyzing voice acitivity in the time domain and rejecting patches which are considered silent.
 pub struct VadPatchCreator {
     patch_size: usize,
+    stride: usize,
     frames_with_va_threshold: f64,
+    rms_threshold: f64,
+    vad_enabled: bool,
 }
 
 impl PatchCreator for VadPatchCreator {
@@ -16,75 +19,118 @@ impl PatchCreator for VadPatchCreator {
         ref_signal: &AudioSignal,
         window: &AnalysisWindow,
     ) -> Result<std::vec::Vec<usize>, VisqolError> {
-        let norm_mat = math_utils::normalize_signal(&ref_signal.data_matrix);
-        let norm_sig = AudioSignal::new(
-            norm_mat
-                .as_slice()
-                .expect("Failed to create AudioSignal from slice!"),
-            ref_signal.sample_rate,
-        );
-
-        let frame_size = (window.size as f64 * window.overlap) as usize;
-        let patch_sample_length = self.patch_size * frame_size;
-        let spectrum_length = spectrogram.ncols();
+        let voice_activity = self.voice_activity_mask(spectrogram.ncols(), ref_signal, window)?;
         let first_patch_idx = self.patch_size / 2 - 1;
-        let patch_count = (spectrum_length - first_patch_idx) / self.patch_size;
-        let total_sample_count = patch_count * patch_sample_length;
-
-        let mut ref_patch_indices = Vec::<usize>::with_capacity(patch_count);
-
-        // Pass the reference signal to the VAD to determine which frames have voice
-        // activity.
-        let vad_result = self.get_voice_activity(
-            norm_sig
-                .data_matrix
-                .as_slice()
-                .ok_or(VisqolError::FailedToComputeVad)?,
-            first_patch_idx,
-            total_sample_count,
-            frame_size,
-        );
+        let patch_count = self.patch_count(spectrogram.ncols());
 
-        let mut patch_idx = first_patch_idx;
-
-        for patch in &vad_result.iter().chunks(self.patch_size) {
-            let frames_with_va = patch.sum::<f64>();
-
-            if frames_with_va >= self.frames_with_va_threshold {
-                ref_patch_indices.push(patch_idx);
-            }
-            patch_idx += self.patch_size;
-        }
+        let ref_patch_indices = (0..patch_count)
+            .map(|patch| first_patch_idx + patch * self.stride)
+            .filter(|&patch_idx| {
+                let patch = &voice_activity[patch_idx..patch_idx + self.patch_size];
+                let frames_with_va = patch.iter().filter(|&&voiced| voiced).count();
+                frames_with_va as f64 >= self.frames_with_va_threshold
+            })
+            .collect();
 
         Ok(ref_patch_indices)
     }
 
-    fn create_patches_from_indices(
-        &self,
-        spectrogram: &Array2<f64>,
-        patch_indices: &[usize],
-    ) -> Vec<Array2<f64>> {
-        let mut patches = Vec::<Array2<f64>>::with_capacity(patch_indices.len());
-
-        let mut patch: Array2<f64>;
-
-        let mut end_col: usize;
-        for start_col in patch_indices {
-            end_col = start_col + self.patch_size;
-            patch = spectrogram.slice(s![.., *start_col..end_col]).to_owned();
-            patches.push(patch);
-        }
-        patches
+    fn patches<'a>(
+        &'a self,
+        spectrogram: &'a Array2<f64>,
+        patch_indices: &'a [usize],
+    ) -> Box<dyn Iterator<Item = Patch> + 'a> {
+        Box::new(patch_indices.iter().map(move |&start_frame| {
+            Patch::from_spectrogram(spectrogram, start_frame, self.patch_size)
+        }))
     }
+
+    fn patch_size(&self) -> Option<usize> { Some(self.patch_size) }
 }
 
 impl VadPatchCreator {
-    /// Creates a new `VadPatchCreator` with the desired patch size.
+    /// Creates a new `VadPatchCreator` with the desired patch size, which uses `constants::VAD_RMS_THRESHOLD` to detect voice activity.
     pub fn new(patch_size: usize) -> Self {
+        Self::with_rms_threshold(patch_size, constants::VAD_RMS_THRESHOLD)
+    }
+
+    /// Creates a new `VadPatchCreator` with the desired patch size, which considers frames whose RMS is below `rms_threshold` silent.
+    /// The RMS is computed on 16 bit samples after normalizing the reference to its peak, lowering the threshold keeps quieter speech.
+    pub fn with_rms_threshold(patch_size: usize, rms_threshold: f64) -> Self {
         Self {
             patch_size,
+            stride: patch_size,
             frames_with_va_threshold: 1.0,
+            rms_threshold,
+            vad_enabled: true,
+        }
+    }
+
+    /// Starts a patch every `stride` frames instead of every `patch_size` frames, so that consecutive patches overlap if `stride` is smaller.
+    /// Overlapping patches give a finer time resolution, but each of them is compared with the degraded spectrogram, which takes longer.
+    /// Returns `VisqolError::InvalidPatchStride` unless `stride` is between 1 and the patch size.
+    pub fn with_stride(mut self, stride: usize) -> Result<Self, VisqolError> {
+        if stride == 0 || stride > self.patch_size {
+            return Err(VisqolError::InvalidPatchStride {
+                stride,
+                patch_size: self.patch_size,
+            });
+        }
+        self.stride = stride;
+        Ok(self)
+    }
+
+    /// Returns the number of whole patches of the patch grid in a spectrogram with `num_frames` frames.
+    fn patch_count(&self, num_frames: usize) -> usize {
+        let first_patch_idx = self.patch_size / 2 - 1;
+        match num_frames.checked_sub(first_patch_idx + self.patch_size) {
+            Some(remaining_frames) => remaining_frames / self.stride + 1,
+            None => 0,
+        }
+    }
+
+    /// If `vad_enabled` is `false`, every frame is considered speech, so all patches of the patch grid are selected for comparison.
+    /// Use this if the VAD drops content which should be scored, e.g. sung vowels. Enabled by default.
+    pub fn set_vad_enabled(&mut self, vad_enabled: bool) { self.vad_enabled = vad_enabled; }
+
+    /// Returns for each of the `num_frames` frames of the spectrogram of `ref_signal` whether the VAD classified it as speech.
+    /// Only frames covered by the patch grid are classified, the frames before the first patch and after the last whole patch are `false`.
+    /// A patch is selected for comparison if any of its frames is `true`. If the VAD is disabled, all frames of the patch grid are `true`.
+    pub fn voice_activity_mask(
+        &self,
+        num_frames: usize,
+        ref_signal: &AudioSignal,
+        window: &AnalysisWindow,
+    ) -> Result<Vec<bool>, VisqolError> {
+        let frame_size = window.hop_size();
+        let first_patch_idx = self.patch_size / 2 - 1;
+        let patch_count = self.patch_count(num_frames);
+        let grid_frames = match patch_count {
+            0 => 0,
+            _ => (patch_count - 1) * self.stride + self.patch_size,
+        };
+        let total_sample_count = grid_frames * frame_size;
+
+        let mut voice_activity = vec![false; num_frames];
+        if !self.vad_enabled {
+            voice_activity[first_patch_idx..first_patch_idx + grid_frames].fill(true);
+            return Ok(voice_activity);
+        }
+
+        // Pass the reference signal to the VAD to determine which frames have voice
+        // activity.
+        let norm_mat = math_utils::normalize_signal(&ref_signal.data_matrix);
+        let vad_result = self.get_voice_activity(
+            norm_mat.as_slice().ok_or(VisqolError::FailedToComputeVad)?,
+            first_patch_idx,
+            total_sample_count,
+            frame_size,
+        );
+
+        for (voiced, activity) in voice_activity[first_patch_idx..].iter_mut().zip(vad_result) {
+            *voiced = activity > 0.0;
         }
+        Ok(voice_activity)
     }
 
     /// Given a time domain signal, this function returns a vector with 1s indicating voice acitivity and 0s indicating the absence of acitivity.
@@ -95,7 +141,7 @@ impl VadPatchCreator {
         total_samples: usize,
         frame_length: usize,
     ) -> Vec<f64> {
-        let mut vad = rms_vad::RmsVad::default();
+        let mut vad = rms_vad::RmsVad::new(self.rms_threshold);
 
         let patch = &signal[start_sample..start_sample + total_samples];
 
@@ -154,8 +200,8 @@ mod tests {
         let expected_patches = vec![9, 29, 49, 69, 89];
         let ref_signal = load_as_mono("test_data/clean_speech/CA01_01.wav").unwrap();
 
-        let mut spectrogram_builder: GammatoneSpectrogramBuilder<NUM_BANDS_SPEECH> =
-            GammatoneSpectrogramBuilder::new(GammatoneFilterbank::<NUM_BANDS_SPEECH>::new(50.0));
+        let filter_bank = GammatoneFilterbank::new(NUM_BANDS_SPEECH, 50.0, None);
+        let mut spectrogram_builder = GammatoneSpectrogramBuilder::new(filter_bank);
         let window = AnalysisWindow::new(ref_signal.sample_rate, 0.25, 0.08);
 
         let spectrogram = spectrogram_builder.build(&ref_signal, &window).unwrap();
@@ -170,4 +216,103 @@ mod tests {
             assert_eq!(a, b);
         }
     }
+
+    #[test]
+    fn lower_rms_threshold_keeps_soft_speech() {
+        let mut ref_signal = load_as_mono("test_data/clean_speech/CA01_01.wav").unwrap();
+        // A single loud click makes the speech soft relative to the peak the VAD normalizes to.
+        ref_signal.data_matrix *= 0.2;
+        ref_signal.data_matrix[0] = 1.0;
+
+        let filter_bank = GammatoneFilterbank::new(NUM_BANDS_SPEECH, 50.0, None);
+        let mut spectrogram_builder = GammatoneSpectrogramBuilder::new(filter_bank);
+        let window = AnalysisWindow::new(ref_signal.sample_rate, 0.25, 0.08);
+        let spectrogram = spectrogram_builder.build(&ref_signal, &window).unwrap();
+        let num_patches = |vad: VadPatchCreator| {
+            vad.create_ref_patch_indices(&spectrogram.data, &ref_signal, &window)
+                .unwrap()
+                .len()
+        };
+
+        let default_patches = num_patches(VadPatchCreator::new(20));
+        let lowered_patches = num_patches(VadPatchCreator::with_rms_threshold(20, 200.0));
+        assert!(lowered_patches > default_patches);
+    }
+
+    #[test]
+    fn smaller_stride_creates_overlapping_patches() {
+        const K_PATCH_SIZE: usize = 20;
+        let ref_signal = load_as_mono("test_data/clean_speech/CA01_01.wav").unwrap();
+
+        let filter_bank = GammatoneFilterbank::new(NUM_BANDS_SPEECH, 50.0, None);
+        let mut spectrogram_builder = GammatoneSpectrogramBuilder::new(filter_bank);
+        let window = AnalysisWindow::new(ref_signal.sample_rate, 0.25, 0.08);
+        let spectrogram = spectrogram_builder.build(&ref_signal, &window).unwrap();
+        let patch_indices = |vad: VadPatchCreator| {
+            vad.create_ref_patch_indices(&spectrogram.data, &ref_signal, &window).unwrap()
+        };
+
+        let default_stride = patch_indices(VadPatchCreator::new(K_PATCH_SIZE));
+        let full_stride =
+            patch_indices(VadPatchCreator::new(K_PATCH_SIZE).with_stride(K_PATCH_SIZE).unwrap());
+        let half_stride = patch_indices(
+            VadPatchCreator::new(K_PATCH_SIZE).with_stride(K_PATCH_SIZE / 2).unwrap(),
+        );
+
+        assert_eq!(full_stride, default_stride);
+        assert!(half_stride.len() > default_stride.len());
+        assert!(default_stride.iter().all(|index| half_stride.contains(index)));
+        assert!(VadPatchCreator::new(K_PATCH_SIZE).with_stride(0).is_err());
+    }
+
+    #[test]
+    fn voice_activity_mask_matches_selected_patches() {
+        const K_PATCH_SIZE: usize = 20;
+        let ref_signal = load_as_mono("test_data/clean_speech/CA01_01.wav").unwrap();
+
+        let filter_bank = GammatoneFilterbank::new(NUM_BANDS_SPEECH, 50.0, None);
+        let mut spectrogram_builder = GammatoneSpectrogramBuilder::new(filter_bank);
+        let window = AnalysisWindow::new(ref_signal.sample_rate, 0.25, 0.08);
+        let spectrogram = spectrogram_builder.build(&ref_signal, &window).unwrap();
+
+        let vad = VadPatchCreator::new(K_PATCH_SIZE);
+        let num_frames = spectrogram.data.ncols();
+        let mask = vad.voice_activity_mask(num_frames, &ref_signal, &window).unwrap();
+        let patches = vad
+            .create_ref_patch_indices(&spectrogram.data, &ref_signal, &window)
+            .unwrap();
+
+        assert_eq!(mask.len(), num_frames);
+        assert!(mask[..K_PATCH_SIZE / 2 - 1].iter().all(|&voiced| !voiced));
+        assert!(mask.iter().any(|&voiced| voiced));
+        for patch_idx in patches {
+            assert!(mask[patch_idx..patch_idx + K_PATCH_SIZE].contains(&true));
+        }
+    }
+
+    #[test]
+    fn disabled_vad_selects_whole_patch_grid() {
+        const K_PATCH_SIZE: usize = 20;
+        let mut ref_signal = load_as_mono("test_data/clean_speech/CA01_01.wav").unwrap();
+        ref_signal.data_matrix *= 0.2;
+        ref_signal.data_matrix[0] = 1.0;
+
+        let filter_bank = GammatoneFilterbank::new(NUM_BANDS_SPEECH, 50.0, None);
+        let mut spectrogram_builder = GammatoneSpectrogramBuilder::new(filter_bank);
+        let window = AnalysisWindow::new(ref_signal.sample_rate, 0.25, 0.08);
+        let spectrogram = spectrogram_builder.build(&ref_signal, &window).unwrap();
+
+        let mut vad = VadPatchCreator::new(K_PATCH_SIZE);
+        vad.set_vad_enabled(false);
+        let patches = vad
+            .create_ref_patch_indices(&spectrogram.data, &ref_signal, &window)
+            .unwrap();
+
+        let first_patch_idx = K_PATCH_SIZE / 2 - 1;
+        let patch_count = (spectrogram.data.ncols() - first_patch_idx) / K_PATCH_SIZE;
+        let expected_patches = (0..patch_count)
+            .map(|patch| first_patch_idx + patch * K_PATCH_SIZE)
+            .collect::<Vec<_>>();
+        assert_eq!(patches, expected_patches);
+    }
 }
@@ -1,4 +1,74 @@
+use crate::visqol_error::VisqolError;
+
 pub enum Variant {
-    Fullband { model_path: String },
+    /// Evaluates audio sampled at 48 kHz using an SVR model. If `model_path` is `None`, the default model embedded in the library is used.
+    Fullband { model_path: Option<String> },
     Wideband { use_unscaled_mos_mapping: bool },
+    /// Evaluates telephony speech sampled at 8 kHz like `Variant::Wideband`, with `constants::NUM_BANDS_NARROWBAND` bands up to half the sample rate.
+    Narrowband { use_unscaled_mos_mapping: bool },
+}
+
+impl Variant {
+    /// Selects the variant designed for signals sampled at `sample_rate`: `Variant::Fullband` using the model in `model_path` or the embedded model for 48 kHz audio,
+    /// `Variant::Wideband` with the scaled MOS mapping for 16 kHz speech and `Variant::Narrowband` with the scaled MOS mapping for 8 kHz speech.
+    /// Any other sample rate is rejected with `VisqolError::UnsupportedSampleRate`.
+    pub fn for_sample_rate(
+        sample_rate: u32,
+        model_path: Option<&str>,
+    ) -> Result<Self, VisqolError> {
+        match sample_rate {
+            48000 => Ok(Variant::Fullband {
+                model_path: model_path.map(str::to_string),
+            }),
+            16000 => Ok(Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            }),
+            8000 => Ok(Variant::Narrowband {
+                use_unscaled_mos_mapping: false,
+            }),
+            _ => Err(VisqolError::UnsupportedSampleRate { sample_rate }),
+        }
+    }
+
+    /// Returns the sample rate in Hz of the signals this variant is designed for, the inverse of `Variant::for_sample_rate`.
+    pub fn sample_rate(&self) -> u32 {
+        match self {
+            Variant::Fullband { .. } => 48000,
+            Variant::Wideband { .. } => 16000,
+            Variant::Narrowband { .. } => 8000,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variant_is_selected_by_sample_rate() {
+        assert!(matches!(
+            Variant::for_sample_rate(48000, Some("model.txt")),
+            Ok(Variant::Fullband { model_path: Some(model_path) }) if model_path == "model.txt"
+        ));
+        assert!(matches!(
+            Variant::for_sample_rate(16000, None),
+            Ok(Variant::Wideband {
+                use_unscaled_mos_mapping: false
+            })
+        ));
+        assert!(matches!(
+            Variant::for_sample_rate(8000, None),
+            Ok(Variant::Narrowband {
+                use_unscaled_mos_mapping: false
+            })
+        ));
+        assert!(matches!(
+            Variant::for_sample_rate(44100, None),
+            Err(VisqolError::UnsupportedSampleRate { sample_rate: 44100 })
+        ));
+        for sample_rate in [48000, 16000, 8000] {
+            let variant = Variant::for_sample_rate(sample_rate, None).unwrap();
+            assert_eq!(variant.sample_rate(), sample_rate);
+        }
+    }
 }
@@ -1,74 +1,135 @@
 use crate::{
-    analysis_window::AnalysisWindow, audio_signal::AudioSignal, audio_utils,
+    analysis_window::{AnalysisWindow, WindowFunction},
+    audio_signal::AudioSignal, audio_utils,
     comparison_patches_selector::ComparisonPatchesSelector, constants,
     gammatone_filterbank::GammatoneFilterbank,
     gammatone_spectrogram_builder::GammatoneSpectrogramBuilder, patch_creator::PatchCreator,
-    patch_similarity_comparator::PatchSimilarityResult, similarity_result::SimilarityResult,
+    patch_similarity_comparator::PatchSimilarityResult,
+    similarity_result::{SimilarityResult, Timings},
     similarity_to_quality_mapper::SimilarityToQualityMapper,
-    spectrogram_builder::SpectrogramBuilder,
+    spectrogram::Spectrogram, spectrogram_builder::SpectrogramBuilder,
+    visqol_error::VisqolError,
 };
 use ndarray::Array1;
-use std::error::Error;
+use std::time::Instant;
+
+/// Determines how the gammatone spectrograms of the signals are computed.
+#[derive(Debug, Clone, Copy)]
+pub struct SpectrogramConfig {
+    /// Number of frequency bands
+    pub num_bands: usize,
+    /// Window function applied to each frame
+    pub window_function: WindowFunction,
+    /// Number of samples per frame. If `None`, frames hold `constants::WINDOW_DURATION` seconds of audio.
+    pub fft_size: Option<usize>,
+    /// Number of samples between the starts of two consecutive frames. If `None`, a quarter of the frame size is used.
+    pub hop_size: Option<usize>,
+    /// Lowest center frequency of the gammatone filterbank in Hz
+    pub low_freq_hz: f64,
+    /// Upper bound of the center frequencies of the gammatone filterbank in Hz. If `None`, it is 8 kHz for `constants::NUM_BANDS_SPEECH` bands and half the sample rate otherwise.
+    pub high_freq_hz: Option<f64>,
+}
 
 /// Perform a comparison on two audio signals. Their similarity is calculated
 /// and converted to a quality score using the given similarity to quality
-/// mapper.
-pub fn calculate_similarity<const NUM_BANDS: usize>(
+/// mapper. The spectrograms are computed according to `spectrogram_config`.
+/// The fraction of the work done, from 0.0 to 1.0, is reported to `progress`. Building the spectrograms and
+/// matching the patches each account for a quarter, the fine alignment of the patches for the remaining half.
+/// If `ref_spectrogram` is provided, it must have been built from `ref_signal` with `build_spectrogram` and is used instead of building it again.
+/// If `record_timings` is set, the time spent building the spectrograms and comparing the patches is stored in `SimilarityResult::timings`.
+#[allow(clippy::too_many_arguments)]
+pub fn calculate_similarity(
+    spectrogram_config: &SpectrogramConfig,
     ref_signal: &mut AudioSignal,
     deg_signal: &mut AudioSignal,
     patch_creator: &dyn PatchCreator,
     selector: &ComparisonPatchesSelector,
     sim_to_qual_mapper: &dyn SimilarityToQualityMapper,
     search_window: usize,
-) -> Result<SimilarityResult, Box<dyn Error>> {
+    ref_spectrogram: Option<&Spectrogram>,
+    record_timings: bool,
+    progress: &mut dyn FnMut(f32),
+) -> Result<SimilarityResult, VisqolError> {
+    progress(0.0);
+    let spectrogram_start = record_timings.then(Instant::now);
     /////////////////// Stage 1: Preprocessing ///////////////////
     let deg_signal_scaled =
         audio_utils::scale_to_match_sound_pressure_level(ref_signal, deg_signal);
-    let mut spect_builder = GammatoneSpectrogramBuilder::<NUM_BANDS>::new(
-        GammatoneFilterbank::new(constants::MINIMUM_FREQ),
-    );
+    let window = spectrogram_config.analysis_window(ref_signal.sample_rate);
 
-    let window = AnalysisWindow::new(
-        ref_signal.sample_rate,
-        constants::OVERLAP,
-        constants::WINDOW_DURATION,
-    );
-
-    let mut ref_spectrogram = spect_builder.build(ref_signal, &window)?;
-    let mut deg_spectrogram = spect_builder.build(&deg_signal_scaled, &window)?;
-
-    audio_utils::prepare_spectrograms_for_comparison(&mut ref_spectrogram, &mut deg_spectrogram);
+    let (ref_spectrogram, deg_spectrogram) = build_comparison_spectrograms(
+        spectrogram_config,
+        ref_signal,
+        &deg_signal_scaled,
+        &window,
+        ref_spectrogram,
+    )?;
+    progress(0.25);
+    let nsim_start = record_timings.then(Instant::now);
 
     /////////////// Stage 2: Feature selection and similarity measure ////////////
     let mut ref_patch_indices =
         patch_creator.create_ref_patch_indices(&ref_spectrogram.data, ref_signal, &window)?;
 
     let frame_duration = calculate_frame_duration(
-        window.size as f64 * window.overlap,
+        window.hop_size() as f64,
         ref_signal.sample_rate as usize,
     );
 
+    let num_reference_patches = ref_patch_indices.len();
     let mut ref_patches =
         patch_creator.create_patches_from_indices(&ref_spectrogram.data, &ref_patch_indices);
 
-    let mut sim_match_info = selector.find_most_optimal_deg_patches(
-        &mut ref_patches,
-        &mut ref_patch_indices,
-        &deg_spectrogram.data,
-        frame_duration,
-        search_window as i32,
-    )?;
+    let (mut sim_match_info, patch_offsets, similarity_matrix) =
+        selector.find_most_optimal_deg_patches(
+            &mut ref_patches,
+            &mut ref_patch_indices,
+            &deg_spectrogram.data,
+            frame_duration,
+            search_window as i32,
+        )?;
+    progress(0.5);
     // Realign the patches in time domain subsignals that start at the coarse
     // patch times.
 
-    let realign_result = selector.finely_align_and_recreate_patches::<NUM_BANDS>(
+    let num_patches = sim_match_info.len() as f32;
+    let realign_result = selector.finely_align_and_recreate_patches(
+        spectrogram_config,
         &mut sim_match_info,
         ref_signal,
         &deg_signal_scaled,
         &window,
+        &mut |num_aligned| progress(0.5 + 0.5 * num_aligned as f32 / num_patches),
     )?;
     sim_match_info = realign_result;
 
+    let mut result = similarity_from_patches(
+        sim_match_info,
+        ref_spectrogram.center_freq_bands,
+        frame_duration,
+        sim_to_qual_mapper,
+    );
+    result.patch_offsets = patch_offsets;
+    result.num_reference_patches = num_reference_patches;
+    result.similarity_matrix = similarity_matrix;
+    if let (Some(spectrogram_start), Some(nsim_start)) = (spectrogram_start, nsim_start) {
+        result.timings = Some(Timings {
+            spectrogram: nsim_start - spectrogram_start,
+            nsim: nsim_start.elapsed(),
+            ..Timings::default()
+        });
+    }
+    Ok(result)
+}
+
+/// Aggregates the similarity results of the matched patches `sim_match_info`, which must not be empty, to the similarity per frequency band and predicts the MOS.
+/// `frame_duration` is the duration in seconds of a spectrogram frame, which is the hop size of the analysis window.
+pub fn similarity_from_patches(
+    sim_match_info: Vec<PatchSimilarityResult>,
+    center_freq_bands: Vec<f64>,
+    frame_duration: f64,
+    sim_to_qual_mapper: &dyn SimilarityToQualityMapper,
+) -> SimilarityResult {
     let fvnsim = calc_per_patch_mean_freq_band_means(&sim_match_info);
     let fstdnsim = calc_per_patch_mean_freq_band_std_devs(&sim_match_info, frame_duration);
     let fvdegenergy = calc_per_patch_mean_freq_band_degraded_energy(&sim_match_info);
@@ -83,15 +144,131 @@ pub fn calculate_similarity<const NUM_BANDS: usize>(
     let vnsim = fvnsim.mean().expect("Failed to compute nsim mean");
 
     moslqo = alter_for_similarity_extremes(vnsim, moslqo);
-    Ok(SimilarityResult::new(
+    SimilarityResult::new(
         moslqo,
         vnsim,
         fvnsim.to_vec(),
         fstdnsim.to_vec(),
         fvdegenergy.to_vec(),
-        ref_spectrogram.center_freq_bands,
+        center_freq_bands,
         sim_match_info,
-    ))
+    )
+}
+
+/// Returns the result of comparing a signal sampled at `sample_rate` with an identical copy, without building spectrograms or comparing patches.
+/// Every frequency band has a similarity of 1 and a standard deviation of 0, and the MOS is `SimilarityToQualityMapper::max_quality` of `sim_to_qual_mapper`.
+/// As no patches are compared, `fvdegenergy` and the per-patch fields are empty.
+pub fn identical_signal_result(
+    spectrogram_config: &SpectrogramConfig,
+    sample_rate: u32,
+    sim_to_qual_mapper: &dyn SimilarityToQualityMapper,
+) -> SimilarityResult {
+    let moslqo = sim_to_qual_mapper.max_quality(spectrogram_config.num_bands);
+    let center_freq_bands = GammatoneSpectrogramBuilder::new(spectrogram_config.filterbank())
+        .center_freq_bands(sample_rate);
+
+    SimilarityResult::new(
+        moslqo,
+        1.0,
+        vec![1.0; spectrogram_config.num_bands],
+        vec![0.0; spectrogram_config.num_bands],
+        Vec::new(),
+        center_freq_bands,
+        Vec::new(),
+    )
+}
+
+impl SpectrogramConfig {
+    /// Returns the analysis window for signals sampled at `sample_rate`.
+    pub fn analysis_window(&self, sample_rate: u32) -> AnalysisWindow {
+        let mut window =
+            AnalysisWindow::new(sample_rate, constants::OVERLAP, constants::WINDOW_DURATION)
+                .with_window_function(self.window_function);
+        if let Some(fft_size) = self.fft_size {
+            window.size = fft_size;
+        }
+        if let Some(hop_size) = self.hop_size {
+            window.overlap = hop_size as f64 / window.size as f64;
+        }
+        window
+    }
+
+    /// Returns a gammatone filterbank with the configured bands.
+    pub fn filterbank(&self) -> GammatoneFilterbank {
+        GammatoneFilterbank::new(self.num_bands, self.low_freq_hz, self.high_freq_hz)
+    }
+
+    /// Checks that a configured FFT size is a power of two, that the hop size for signals sampled at `sample_rate` lies between 1 and the frame size
+    /// and that the frequency range of the filterbank is not empty.
+    pub fn validate(&self, sample_rate: u32) -> Result<(), VisqolError> {
+        let window = self.analysis_window(sample_rate);
+        let valid_fft_size = self.fft_size.is_none_or(usize::is_power_of_two);
+        if !valid_fft_size || !(1..=window.size).contains(&window.hop_size()) {
+            return Err(VisqolError::InvalidFrameSizes {
+                fft_size: self.fft_size,
+                hop_size: self.hop_size,
+            });
+        }
+        let valid_low_freq = self.low_freq_hz > 0.0;
+        if !valid_low_freq || self.high_freq_hz.is_some_and(|high| high <= self.low_freq_hz) {
+            return Err(VisqolError::InvalidFrequencyRange {
+                low_freq_hz: self.low_freq_hz,
+                high_freq_hz: self.high_freq_hz,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Computes the gammatone spectrograms of `ref_signal` and `deg_signal` that the patches are taken from according to `spectrogram_config`.
+/// The degraded signal is scaled to the sound pressure level of the reference beforehand.
+pub fn calculate_spectrograms(
+    spectrogram_config: &SpectrogramConfig,
+    ref_signal: &AudioSignal,
+    deg_signal: &AudioSignal,
+) -> Result<(Spectrogram, Spectrogram), VisqolError> {
+    let deg_signal_scaled =
+        audio_utils::scale_to_match_sound_pressure_level(ref_signal, deg_signal);
+    let window = spectrogram_config.analysis_window(ref_signal.sample_rate);
+
+    build_comparison_spectrograms(
+        spectrogram_config,
+        ref_signal,
+        &deg_signal_scaled,
+        &window,
+        None,
+    )
+}
+
+/// Builds the spectrogram of `signal` according to `spectrogram_config` before it is prepared for comparison.
+/// It can be passed to `calculate_similarity` to compare the same reference signal with multiple degraded signals.
+pub fn build_spectrogram(
+    spectrogram_config: &SpectrogramConfig,
+    signal: &AudioSignal,
+) -> Result<Spectrogram, VisqolError> {
+    GammatoneSpectrogramBuilder::new(spectrogram_config.filterbank())
+        .build(signal, &spectrogram_config.analysis_window(signal.sample_rate))
+}
+
+/// Builds the spectrograms of both signals and prepares them for comparison, i.e. converts them to dB and clamps them to a common noise floor.
+/// A copy of `ref_spectrogram` is prepared instead of building the reference spectrogram, if it is provided.
+fn build_comparison_spectrograms(
+    spectrogram_config: &SpectrogramConfig,
+    ref_signal: &AudioSignal,
+    deg_signal: &AudioSignal,
+    window: &AnalysisWindow,
+    ref_spectrogram: Option<&Spectrogram>,
+) -> Result<(Spectrogram, Spectrogram), VisqolError> {
+    let mut spect_builder = GammatoneSpectrogramBuilder::new(spectrogram_config.filterbank());
+
+    let mut ref_spectrogram = match ref_spectrogram {
+        Some(ref_spectrogram) => ref_spectrogram.clone(),
+        None => spect_builder.build(ref_signal, window)?,
+    };
+    let mut deg_spectrogram = spect_builder.build(deg_signal, window)?;
+
+    audio_utils::prepare_spectrograms_for_comparison(&mut ref_spectrogram, &mut deg_spectrogram);
+    Ok((ref_spectrogram, deg_spectrogram))
 }
 
 /// Computes prediction with the given `SimilarityToQualityMapper`
@@ -169,6 +346,6 @@ fn alter_for_similarity_extremes(vnsim: f64, moslqo: f64) -> f64 {
 }
 
 /// Calculates fraeme duration in seonds
-fn calculate_frame_duration(frame_size: f64, sample_rate: usize) -> f64 {
+pub fn calculate_frame_duration(frame_size: f64, sample_rate: usize) -> f64 {
     frame_size / sample_rate as f64
 }
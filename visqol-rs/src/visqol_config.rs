@@ -0,0 +1,180 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants, variant::Variant, visqol_error::VisqolError, visqol_manager::VisqolManagerBuilder,
+};
+
+/// Names the variant selected by a `VisqolConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariantKind {
+    /// `Variant::Wideband`, for speech sampled at 16 kHz.
+    #[default]
+    Wideband,
+    /// `Variant::Fullband`, for audio sampled at 48 kHz.
+    Fullband,
+    /// `Variant::Narrowband`, for telephony speech sampled at 8 kHz.
+    Narrowband,
+}
+
+/// Scoring configuration which can be stored in a TOML or JSON file, so that experiment settings can be versioned alongside the data.
+/// Omitted fields take the defaults of `VisqolManagerBuilder`, e.g. the TOML file
+/// ```toml
+/// variant = "fullband"
+/// model_path = "model/libsvm_nu_svr_model.txt"
+/// search_window = 60
+/// max_alignment_lag_seconds = 0.5
+/// ```
+/// configures the fullband variant with a larger search window and a limited alignment lag.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VisqolConfig {
+    pub variant: VariantKind,
+    /// Path to the SVR model used by `VariantKind::Fullband`. If it is omitted, the embedded model is used.
+    pub model_path: Option<String>,
+    /// See `Variant::Wideband`. Only used by `VariantKind::Wideband` and `VariantKind::Narrowband`.
+    pub use_unscaled_mos_mapping: bool,
+    /// See `VisqolManagerBuilder::search_window`.
+    pub search_window: usize,
+    /// See `VisqolManager::set_align`.
+    pub align: bool,
+    /// See `VisqolManager::set_max_alignment_lag_seconds`.
+    pub max_alignment_lag_seconds: Option<f64>,
+    /// See `VisqolManager::set_subsample_align`.
+    pub subsample_align: bool,
+    /// See `VisqolManager::set_clamp_mos`.
+    pub clamp_mos: bool,
+}
+
+impl Default for VisqolConfig {
+    fn default() -> Self {
+        Self {
+            variant: VariantKind::default(),
+            model_path: None,
+            use_unscaled_mos_mapping: false,
+            search_window: constants::DEFAULT_WINDOW_SIZE,
+            align: true,
+            max_alignment_lag_seconds: None,
+            subsample_align: false,
+            clamp_mos: false,
+        }
+    }
+}
+
+impl VisqolConfig {
+    /// Reads the configuration stored in `path`, which is parsed as TOML or JSON depending on its `.toml` or `.json` extension.
+    /// Returns `VisqolError::InvalidConfig` if the file has another extension or cannot be parsed.
+    pub fn from_file(path: &str) -> Result<Self, VisqolError> {
+        let invalid_config = |reason: String| VisqolError::InvalidConfig {
+            path: path.to_string(),
+            reason,
+        };
+        let extension = Path::new(path).extension().and_then(|extension| extension.to_str());
+        let parse: fn(&str) -> Result<Self, String> = match extension {
+            Some("toml") => |contents| toml::from_str(contents).map_err(|error| error.to_string()),
+            Some("json") => {
+                |contents| serde_json::from_str(contents).map_err(|error| error.to_string())
+            }
+            _ => return Err(invalid_config("expected a .toml or .json file".to_string())),
+        };
+
+        if !Path::new(path).exists() {
+            return Err(VisqolError::FileNotFound {
+                path: path.to_string(),
+            });
+        }
+        parse(&std::fs::read_to_string(path)?).map_err(invalid_config)
+    }
+
+    /// Returns the variant selected by this configuration.
+    pub fn variant(&self) -> Variant {
+        match self.variant {
+            VariantKind::Wideband => Variant::Wideband {
+                use_unscaled_mos_mapping: self.use_unscaled_mos_mapping,
+            },
+            VariantKind::Fullband => Variant::Fullband {
+                model_path: self.model_path.clone(),
+            },
+            VariantKind::Narrowband => Variant::Narrowband {
+                use_unscaled_mos_mapping: self.use_unscaled_mos_mapping,
+            },
+        }
+    }
+
+    /// Returns a builder configured according to this configuration. Settings which are not part of the configuration keep their defaults.
+    pub fn builder<const NUM_BANDS: usize>(&self) -> VisqolManagerBuilder<NUM_BANDS> {
+        let mut builder = VisqolManagerBuilder::default()
+            .variant(self.variant())
+            .search_window(self.search_window)
+            .align(self.align)
+            .subsample_align(self.subsample_align)
+            .clamp_mos(self.clamp_mos);
+        if let Some(max_alignment_lag_seconds) = self.max_alignment_lag_seconds {
+            builder = builder.max_alignment_lag_seconds(max_alignment_lag_seconds);
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visqol_manager::DynamicVisqolManager;
+
+    /// Writes `contents` to a file called `file_name` in a unique path in the temporary directory and returns its path.
+    fn write_config(file_name: &str, contents: &str) -> String {
+        let path = crate::test_utility::temp_path(file_name);
+        std::fs::write(&path, contents).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn toml_and_json_files_are_parsed() {
+        let toml_path = write_config(
+            "visqol_config_test.toml",
+            "variant = \"fullband\"\nmodel_path = \"model.txt\"\nsearch_window = 60\n",
+        );
+        let json_path = write_config(
+            "visqol_config_test.json",
+            r#"{"variant": "fullband", "model_path": "model.txt", "search_window": 60}"#,
+        );
+
+        let expected = VisqolConfig {
+            variant: VariantKind::Fullband,
+            model_path: Some("model.txt".to_string()),
+            search_window: 60,
+            ..VisqolConfig::default()
+        };
+        assert_eq!(VisqolConfig::from_file(&toml_path).unwrap(), expected);
+        assert_eq!(VisqolConfig::from_file(&json_path).unwrap(), expected);
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        let unknown_field_path = write_config("visqol_config_unknown.toml", "window = 60\n");
+        let yaml_path = write_config("visqol_config_test.yaml", "variant: wideband\n");
+
+        assert!(matches!(
+            VisqolConfig::from_file(&unknown_field_path),
+            Err(VisqolError::InvalidConfig { .. })
+        ));
+        assert!(matches!(
+            VisqolConfig::from_file(&yaml_path),
+            Err(VisqolError::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn config_is_applied_to_manager() {
+        let config = VisqolConfig {
+            max_alignment_lag_seconds: Some(0.5),
+            ..VisqolConfig::default()
+        };
+
+        let manager: DynamicVisqolManager = config.builder().build();
+
+        assert_eq!(manager.num_bands(), constants::NUM_BANDS_SPEECH);
+    }
+}
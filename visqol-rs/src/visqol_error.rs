@@ -1,8 +1,68 @@
+use std::fmt;
+
 use thiserror::Error;
 
+/// Identifies which of the compared signals an error refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalRole {
+    Reference,
+    Degraded,
+}
+
+impl fmt::Display for SignalRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignalRole::Reference => write!(f, "reference"),
+            SignalRole::Degraded => write!(f, "degraded"),
+        }
+    }
+}
+
 #[non_exhaustive]
 #[derive(Error, Debug)]
 pub enum VisqolError {
+    #[error("Invalid spectrogram frames! The FFT size {fft_size:?} must be a power of two and the hop size {hop_size:?} must lie between 1 and the frame size")]
+    InvalidFrameSizes {
+        fft_size: Option<usize>,
+        hop_size: Option<usize>,
+    },
+
+    #[error("Invalid frequency range! The lowest center frequency {low_freq_hz:?} Hz must be positive and below the upper bound {high_freq_hz:?} Hz")]
+    InvalidFrequencyRange {
+        low_freq_hz: f64,
+        high_freq_hz: Option<f64>,
+    },
+
+    #[error("Invalid chunk size! The chunk duration {chunk_seconds:?} seconds must be positive and the overlap {overlap_seconds:?} seconds must not be negative")]
+    InvalidChunkSize {
+        chunk_seconds: f64,
+        overlap_seconds: f64,
+    },
+
+    #[error("{option} is not supported when scoring in chunks, as it depends on the whole signals!")]
+    UnsupportedInChunkedMode { option: String },
+
+    #[error("Invalid segment duration! The segment duration {segment_seconds:?} seconds must be positive")]
+    InvalidSegmentDuration { segment_seconds: f64 },
+
+    #[error("Invalid time range! The range from {start:?} to {end:?} seconds does not overlap the signal")]
+    InvalidTimeRange { start: f64, end: f64 },
+
+    #[error("Invalid config file {path:?}: {reason}")]
+    InvalidConfig { path: String, reason: String },
+
+    #[error("Invalid glob pattern {pattern:?}: {reason}")]
+    InvalidGlobPattern { pattern: String, reason: String },
+
+    #[error("Glob patterns match different numbers of files! Reference pattern matches {reference:?} files, degraded pattern matches {degraded:?} files")]
+    GlobCountMismatch { reference: usize, degraded: usize },
+
+    #[error("Invalid SVR model{}: {reason}", .path.as_ref().map_or(String::new(), |path| format!(" {:?}", path)))]
+    InvalidModel {
+        path: Option<String>,
+        reason: String,
+    },
+
     #[error("Sample rates differ! Reference signal is sampled at {reference:?} Hz, degraded signal is sampled at {degraded:?} Hz")]
     DifferentSampleRates { reference: u32, degraded: u32 },
 
@@ -31,6 +91,87 @@ pub enum VisqolError {
     #[error("Failed to compute VAD!")]
     FailedToComputeVad,
 
-    #[error("Visqol input files must be quantized to 16 bit. Found {bits_per_sample:?}!")]
+    #[error("Visqol input files must be quantized to 16 bit, or to 24 bit or 32 bit float for wav files. Found {bits_per_sample:?}!")]
     InvalidBitsPerSample { bits_per_sample: u16 },
+
+    #[error("Invalid raw PCM data! {num_bytes:?} bytes do not hold a whole number of frames of {bytes_per_frame:?} bytes")]
+    InvalidRawPcm {
+        num_bytes: usize,
+        bytes_per_frame: usize,
+    },
+
+    #[error("Expected {num_bands:?} finite, non-negative band weights which are not all zero, one per band. Found {num_weights:?}!")]
+    InvalidBandWeights {
+        num_weights: usize,
+        num_bands: usize,
+    },
+
+    #[error("Invalid number of frequency bands {num_bands:?}: {reason}")]
+    InvalidNumBands { num_bands: usize, reason: String },
+
+    #[error("Expected a patch stride between 1 and the patch size of {patch_size:?} frames. Found {stride:?}!")]
+    InvalidPatchStride { stride: usize, patch_size: usize },
+
+    #[error("Expected {num_channels:?} downmix weights, one per channel. Found {num_weights:?}!")]
+    InvalidDownmixWeights {
+        num_weights: usize,
+        num_channels: usize,
+    },
+
+    #[error("Channel counts differ! Reference signal has {reference:?} channels, degraded signal has {degraded:?} channels")]
+    DifferentChannelCounts { reference: usize, degraded: usize },
+
+    #[error("No variant supports a sample rate of {sample_rate:?} Hz! Use 16000 Hz for speech or 48000 Hz for audio")]
+    UnsupportedSampleRate { sample_rate: u32 },
+
+    #[error("The variant of the manager expects signals sampled at {expected:?} Hz. Found {actual:?} Hz! Use the matching variant, e.g. by VisqolManager::auto")]
+    SampleRateVariantMismatch { expected: u32, actual: u32 },
+
+    #[error("Durations differ! Reference signal is {reference:?} seconds long, degraded signal is {degraded:?} seconds long")]
+    DurationMismatch { reference: f64, degraded: f64 },
+
+    #[error("The {which} signal is empty! It does not hold any samples")]
+    EmptySignal { which: SignalRole },
+
+    #[error("Signal is too short! Found {duration:?} seconds, minimum required is {min_required:?} seconds")]
+    SignalTooShort { duration: f64, min_required: f64 },
+
+    #[error("Signal contains a non-finite sample at index {index:?}!")]
+    NonFiniteSamples { index: usize },
+
+    #[error("File {path:?} does not exist!")]
+    FileNotFound { path: String },
+
+    #[error("Failed to decode {path:?}: {source}")]
+    DecodeFailed {
+        path: String,
+        source: Box<VisqolError>,
+    },
+
+    #[error("File does not contain an audio track!")]
+    NoAudioTrack,
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Wav(#[from] hound::Error),
+
+    #[cfg(feature = "flac")]
+    #[error(transparent)]
+    Flac(#[from] claxon::Error),
+
+    #[cfg(any(feature = "mp3", feature = "symphonia"))]
+    #[error(transparent)]
+    Symphonia(#[from] symphonia::core::errors::Error),
+
+    #[cfg(feature = "image")]
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+
+    #[error(transparent)]
+    ResamplerConstruction(#[from] rubato::ResamplerConstructionError),
+
+    #[error(transparent)]
+    Resampling(#[from] rubato::ResampleError),
 }
@@ -1,137 +1,3381 @@
-use std::error::Error;
+use std::collections::BTreeSet;
+use std::io::{Read, Seek};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use ndarray::s;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use crate::{
-    alignment,
+    alignment::{self, AlignmentResult},
+    analysis_window::WindowFunction,
     audio_signal::AudioSignal,
-    audio_utils,
+    audio_utils::{self, DownmixConfig, FormatHint, NormalizationTarget, WeightingCurve},
     comparison_patches_selector::ComparisonPatchesSelector,
     constants::{self, PATCH_SIZE_AUDIO, PATCH_SIZE_SPEECH},
     image_patch_creator::ImagePatchCreator,
-    neurogram_similiarity_index_measure::NeurogramSimiliarityIndexMeasure,
+    neurogram_similiarity_index_measure::{NeurogramSimiliarityIndexMeasure, NsimConstants},
     patch_creator::PatchCreator,
-    similarity_result::SimilarityResult,
-    similarity_to_quality_mapper::SimilarityToQualityMapper,
+    patch_similarity_comparator::PatchSimilarityResult,
+    similarity_result::{SimilarityResult, Timings},
+    similarity_to_quality_mapper::{BandWeightedMapper, SimilarityToQualityMapper},
+    spectrogram::Spectrogram,
+    time_range::TimeRange,
     speech_similarity_to_quality_mapper::SpeechSimilarityToQualityMapper,
     svr_similarity_to_quality_mapper::SvrSimilarityToQualityMapper,
     vad_patch_creator::VadPatchCreator,
     variant::Variant,
-    visqol,
-    visqol_error::VisqolError,
+    visqol::{self, SpectrogramConfig},
+    visqol_error::{SignalRole, VisqolError},
+    visqol_warning::VisqolWarning,
+    wav_reader::WavChunkReader,
 };
 
 /// Configures and executes audio evaluation using ViSQOL.
+/// Scoring only requires `&self`, so a configured manager can be shared across threads, e.g. in an `Arc`.
+/// Scoring is deterministic: no stage uses randomness, so the same inputs and configuration always give identical results, regardless of threading.
+/// `NUM_BANDS` is the default number of frequency bands, which `VisqolManagerBuilder::num_bands` can override at runtime.
 pub struct VisqolManager<const NUM_BANDS: usize> {
+    num_bands: usize,
+    sample_rate: u32,
     search_window: usize,
     patch_creator: Box<dyn PatchCreator>,
     patch_selector: ComparisonPatchesSelector,
     sim_to_quality_mapper: Box<dyn SimilarityToQualityMapper>,
+    resample_to_match: bool,
+    downmix: DownmixConfig,
+    remove_dc_offset: bool,
+    weighting: Option<WeightingCurve>,
+    loudness_normalization: Option<NormalizationTarget>,
+    silence_threshold_db: Option<f64>,
+    align: bool,
+    duration_tolerance: f64,
+    strict_duration: bool,
+    find_best_subregion: bool,
+    sanitize_non_finite: bool,
+    identical_signal_fast_path: bool,
+    min_reliable_patches: usize,
+    clamp_mos: bool,
+    record_timings: bool,
+    max_alignment_lag_seconds: Option<f64>,
+    subsample_align: bool,
+    alignment_silence_threshold_db: Option<f64>,
+    window_function: WindowFunction,
+    fft_size: Option<usize>,
+    hop_size: Option<usize>,
+    low_freq_hz: f64,
+    high_freq_hz: Option<f64>,
 }
 
-impl<const NUM_BANDS: usize> VisqolManager<NUM_BANDS> {
-    /// Creates a new instance of with the desired configurations.
-    pub fn new(variant: Variant, window_size: usize) -> Self {
-        let patch_creator: Box<dyn PatchCreator>;
-        let sim_to_quality_mapper: Box<dyn SimilarityToQualityMapper>;
-        match variant {
-            Variant::Wideband {
-                use_unscaled_mos_mapping,
-            } => {
-                patch_creator = Box::new(VadPatchCreator::new(PATCH_SIZE_AUDIO));
-                sim_to_quality_mapper = Box::new(SpeechSimilarityToQualityMapper::new(
-                    !use_unscaled_mos_mapping,
-                ));
+/// A `VisqolManager` without a compile-time band count.
+/// Unless `VisqolManagerBuilder::num_bands` is set, the band count is selected by the variant:
+/// `constants::NUM_BANDS_SPEECH` for `Variant::Wideband`, `constants::NUM_BANDS_AUDIO` for `Variant::Fullband` and `constants::NUM_BANDS_NARROWBAND` for `Variant::Narrowband`.
+pub type DynamicVisqolManager = VisqolManager<0>;
+
+/// The result of scoring a pair of files together with their name, see `VisqolManager::run_directory`.
+pub type NamedResult = (String, Result<SimilarityResult, VisqolError>);
+
+/// Statistics of a whole signal which is scored in chunks, see `VisqolManager::run_chunked`.
+struct SignalStats {
+    dc_offset: f64,
+    clipped_fraction: f64,
+    is_silent: bool,
+}
+
+/// Configures a `VisqolManager` with chainable setters.
+/// Options which are not set keep the defaults used by `VisqolManager::new`.
+pub struct VisqolManagerBuilder<const NUM_BANDS: usize> {
+    variant: Variant,
+    num_bands: Option<usize>,
+    search_window: usize,
+    sim_to_quality_mapper: Option<Box<dyn SimilarityToQualityMapper>>,
+    band_weights: Option<Vec<f64>>,
+    patch_creator: Option<Box<dyn PatchCreator>>,
+    patch_stride: Option<usize>,
+    vad_rms_threshold: f64,
+    vad: bool,
+    resample_to_match: bool,
+    downmix: DownmixConfig,
+    remove_dc_offset: bool,
+    weighting: Option<WeightingCurve>,
+    loudness_normalization: Option<NormalizationTarget>,
+    silence_threshold_db: Option<f64>,
+    align: bool,
+    duration_tolerance: f64,
+    strict_duration: bool,
+    find_best_subregion: bool,
+    sanitize_non_finite: bool,
+    identical_signal_fast_path: bool,
+    min_reliable_patches: usize,
+    clamp_mos: bool,
+    record_similarity_matrix: bool,
+    record_timings: bool,
+    max_alignment_lag_seconds: Option<f64>,
+    subsample_align: bool,
+    alignment_silence_threshold_db: Option<f64>,
+    nsim_constants: NsimConstants,
+    window_function: WindowFunction,
+    fft_size: Option<usize>,
+    hop_size: Option<usize>,
+    low_freq_hz: f64,
+    high_freq_hz: Option<f64>,
+}
+
+impl<const NUM_BANDS: usize> Default for VisqolManagerBuilder<NUM_BANDS> {
+    fn default() -> Self {
+        Self {
+            variant: Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            },
+            num_bands: None,
+            search_window: constants::DEFAULT_WINDOW_SIZE,
+            sim_to_quality_mapper: None,
+            band_weights: None,
+            patch_creator: None,
+            patch_stride: None,
+            vad_rms_threshold: constants::VAD_RMS_THRESHOLD,
+            vad: true,
+            resample_to_match: false,
+            downmix: DownmixConfig::default(),
+            remove_dc_offset: false,
+            weighting: None,
+            loudness_normalization: None,
+            silence_threshold_db: None,
+            align: true,
+            duration_tolerance: constants::DURATION_MISMATCH_TOLERANCE,
+            strict_duration: false,
+            find_best_subregion: false,
+            sanitize_non_finite: false,
+            identical_signal_fast_path: true,
+            min_reliable_patches: constants::MIN_RELIABLE_PATCHES,
+            clamp_mos: false,
+            record_similarity_matrix: false,
+            record_timings: false,
+            max_alignment_lag_seconds: None,
+            subsample_align: false,
+            alignment_silence_threshold_db: None,
+            nsim_constants: NsimConstants::default(),
+            window_function: WindowFunction::default(),
+            fft_size: None,
+            hop_size: None,
+            low_freq_hz: constants::MINIMUM_FREQ,
+            high_freq_hz: None,
+        }
+    }
+}
+
+impl<const NUM_BANDS: usize> VisqolManagerBuilder<NUM_BANDS> {
+    /// Sets the variant, which determines the patch creator and the default similarity to quality mapper.
+    /// Defaults to `Variant::Wideband` with the scaled MOS mapping.
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Sets the number of frequency bands of the gammatone spectrograms, e.g. from a value read from a config file.
+    /// Defaults to `NUM_BANDS`, or the band count matching the variant for a `DynamicVisqolManager`.
+    pub fn num_bands(mut self, num_bands: usize) -> Self {
+        self.num_bands = Some(num_bands);
+        self
+    }
+
+    /// Sets the radius of the search window for patch matching. Defaults to `constants::DEFAULT_WINDOW_SIZE`.
+    /// This is unrelated to `max_alignment_lag_seconds`, which bounds the global alignment preceding the patch matching.
+    pub fn search_window(mut self, search_window: usize) -> Self {
+        self.search_window = search_window;
+        self
+    }
+
+    /// Replaces the similarity to quality mapper selected by the variant with `mapper`.
+    pub fn similarity_to_quality_mapper(
+        mut self,
+        mapper: Box<dyn SimilarityToQualityMapper>,
+    ) -> Self {
+        self.sim_to_quality_mapper = Some(mapper);
+        self
+    }
+
+    /// Weights the similarity of each frequency band by `band_weights` before the MOS is predicted, e.g. to emphasize the bands between 1 and 4 kHz
+    /// which matter most for speech intelligibility. As the weights are normalized to a mean of 1, the speech mapping is based on the weighted mean
+    /// of the band similarities, while the SVR of `Variant::Fullband` receives the weighted similarities. See `BandWeightedMapper`.
+    /// There must be one weight per band. By default, all bands are weighted equally. `SimilarityResult::fvnsim` and `vnsim` stay unweighted.
+    pub fn band_weights(mut self, band_weights: Vec<f64>) -> Self {
+        self.band_weights = Some(band_weights);
+        self
+    }
+
+    /// Replaces the patch creator selected by the variant with `patch_creator`.
+    pub fn patch_creator(mut self, patch_creator: Box<dyn PatchCreator>) -> Self {
+        self.patch_creator = Some(patch_creator);
+        self
+    }
+
+    /// Starts a reference patch every `patch_stride` frames instead of every patch size, see `VadPatchCreator::with_stride` and `ImagePatchCreator::with_stride`.
+    /// A stride smaller than the patch size compares overlapping patches, giving a finer time resolution and a smoother `SimilarityResult::vnsim_over_time`
+    /// at the cost of speed. By default, patches do not overlap. Ignored if a custom patch creator is set.
+    pub fn patch_stride(mut self, patch_stride: usize) -> Self {
+        self.patch_stride = Some(patch_stride);
+        self
+    }
+
+    /// Sets the RMS threshold of the voice activity detection used by `Variant::Wideband` and `Variant::Narrowband`, see `VadPatchCreator::with_rms_threshold`.
+    /// Lower it if quiet speech is skipped. Defaults to `constants::VAD_RMS_THRESHOLD` and is ignored if a custom patch creator is set.
+    pub fn vad_rms_threshold(mut self, vad_rms_threshold: f64) -> Self {
+        self.vad_rms_threshold = vad_rms_threshold;
+        self
+    }
+
+    /// If `vad` is `false`, `Variant::Wideband` and `Variant::Narrowband` compare all patches instead of only those with voice activity,
+    /// see `VadPatchCreator::set_vad_enabled`. Enabled by default and ignored if a custom patch creator is set.
+    pub fn vad(mut self, vad: bool) -> Self {
+        self.vad = vad;
+        self
+    }
+
+    /// See `VisqolManager::set_resample_to_match`.
+    pub fn resample_to_match(mut self, resample_to_match: bool) -> Self {
+        self.resample_to_match = resample_to_match;
+        self
+    }
+
+    /// See `VisqolManager::set_downmix`.
+    pub fn downmix(mut self, downmix: DownmixConfig) -> Self {
+        self.downmix = downmix;
+        self
+    }
+
+    /// See `VisqolManager::set_remove_dc_offset`.
+    pub fn remove_dc_offset(mut self, remove_dc_offset: bool) -> Self {
+        self.remove_dc_offset = remove_dc_offset;
+        self
+    }
+
+    /// See `VisqolManager::set_weighting`.
+    pub fn weighting(mut self, curve: WeightingCurve) -> Self {
+        self.weighting = Some(curve);
+        self
+    }
+
+    /// See `VisqolManager::set_loudness_normalization`.
+    pub fn loudness_normalization(mut self, target: NormalizationTarget) -> Self {
+        self.loudness_normalization = Some(target);
+        self
+    }
+
+    /// See `VisqolManager::set_silence_trimming`.
+    pub fn silence_trimming(mut self, threshold_db: f64) -> Self {
+        self.silence_threshold_db = Some(threshold_db);
+        self
+    }
+
+    /// See `VisqolManager::set_align`.
+    pub fn align(mut self, align: bool) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// See `VisqolManager::set_duration_tolerance`.
+    pub fn duration_tolerance(mut self, duration_tolerance: f64) -> Self {
+        self.duration_tolerance = duration_tolerance;
+        self
+    }
+
+    /// See `VisqolManager::set_strict_duration`.
+    pub fn strict_duration(mut self, strict_duration: bool) -> Self {
+        self.strict_duration = strict_duration;
+        self
+    }
+
+    /// See `VisqolManager::set_find_best_subregion`.
+    pub fn find_best_subregion(mut self, find_best_subregion: bool) -> Self {
+        self.find_best_subregion = find_best_subregion;
+        self
+    }
+
+    /// See `VisqolManager::set_sanitize_non_finite`.
+    pub fn sanitize_non_finite(mut self, sanitize_non_finite: bool) -> Self {
+        self.sanitize_non_finite = sanitize_non_finite;
+        self
+    }
+
+    /// See `VisqolManager::set_identical_signal_fast_path`.
+    pub fn identical_signal_fast_path(mut self, identical_signal_fast_path: bool) -> Self {
+        self.identical_signal_fast_path = identical_signal_fast_path;
+        self
+    }
+
+    /// See `VisqolManager::set_min_reliable_patches`.
+    pub fn min_reliable_patches(mut self, min_reliable_patches: usize) -> Self {
+        self.min_reliable_patches = min_reliable_patches;
+        self
+    }
+
+    /// See `VisqolManager::set_clamp_mos`.
+    pub fn clamp_mos(mut self, clamp_mos: bool) -> Self {
+        self.clamp_mos = clamp_mos;
+        self
+    }
+
+    /// See `VisqolManager::set_record_similarity_matrix`.
+    pub fn record_similarity_matrix(mut self, record_similarity_matrix: bool) -> Self {
+        self.record_similarity_matrix = record_similarity_matrix;
+        self
+    }
+
+    /// See `VisqolManager::set_record_timings`.
+    pub fn record_timings(mut self, record_timings: bool) -> Self {
+        self.record_timings = record_timings;
+        self
+    }
+
+    /// See `VisqolManager::set_max_alignment_lag_seconds`.
+    pub fn max_alignment_lag_seconds(mut self, max_alignment_lag_seconds: f64) -> Self {
+        self.max_alignment_lag_seconds = Some(max_alignment_lag_seconds);
+        self
+    }
+
+    /// See `VisqolManager::set_subsample_align`.
+    pub fn subsample_align(mut self, subsample_align: bool) -> Self {
+        self.subsample_align = subsample_align;
+        self
+    }
+
+    /// See `VisqolManager::set_alignment_silence_skipping`.
+    pub fn alignment_silence_skipping(mut self, threshold_db: f64) -> Self {
+        self.alignment_silence_threshold_db = Some(threshold_db);
+        self
+    }
+
+    /// Sets the stabilization constants of the NSIM which compares the patches, e.g. to evaluate alternative NSIM formulations.
+    /// Defaults to the constants of ViSQOL, see `NsimConstants::default`.
+    pub fn nsim_constants(mut self, nsim_constants: NsimConstants) -> Self {
+        self.nsim_constants = nsim_constants;
+        self
+    }
+
+    /// See `VisqolManager::set_window_function`.
+    pub fn window_function(mut self, window_function: WindowFunction) -> Self {
+        self.window_function = window_function;
+        self
+    }
+
+    /// See `VisqolManager::set_fft_size`.
+    pub fn fft_size(mut self, fft_size: usize) -> Self {
+        self.fft_size = Some(fft_size);
+        self
+    }
+
+    /// See `VisqolManager::set_hop_size`.
+    pub fn hop_size(mut self, hop_size: usize) -> Self {
+        self.hop_size = Some(hop_size);
+        self
+    }
+
+    /// See `VisqolManager::set_low_freq_hz`.
+    pub fn low_freq_hz(mut self, low_freq_hz: f64) -> Self {
+        self.low_freq_hz = low_freq_hz;
+        self
+    }
+
+    /// See `VisqolManager::set_high_freq_hz`.
+    pub fn high_freq_hz(mut self, high_freq_hz: f64) -> Self {
+        self.high_freq_hz = Some(high_freq_hz);
+        self
+    }
+
+    /// Creates the configured `VisqolManager`.
+    ///
+    /// # Panics
+    /// Panics if the SVR model of `Variant::Fullband` cannot be loaded, see `VisqolManagerBuilder::try_build` for a fallible alternative.
+    pub fn build(self) -> VisqolManager<NUM_BANDS> {
+        self.try_build().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Creates the configured `VisqolManager`.
+    /// Returns `VisqolError::FileNotFound` or `VisqolError::InvalidModel` if the SVR model of `Variant::Fullband` cannot be loaded
+    /// `VisqolError::InvalidBandWeights` if the band weights do not match the number of bands and `VisqolError::InvalidPatchStride`
+    /// if the patch stride is 0 or exceeds the patch size. Returns `VisqolError::InvalidNumBands` if the number of bands is 0
+    /// or does not match the number of features of the quality mapper, e.g. the 32 features of the SVR model of `Variant::Fullband`.
+    pub fn try_build(self) -> Result<VisqolManager<NUM_BANDS>, VisqolError> {
+        let sample_rate = self.variant.sample_rate();
+        let num_bands = match (self.num_bands, &self.variant) {
+            (Some(num_bands), _) => num_bands,
+            (None, _) if NUM_BANDS != 0 => NUM_BANDS,
+            (None, Variant::Wideband { .. }) => constants::NUM_BANDS_SPEECH,
+            (None, Variant::Fullband { .. }) => constants::NUM_BANDS_AUDIO,
+            (None, Variant::Narrowband { .. }) => constants::NUM_BANDS_NARROWBAND,
+        };
+        if num_bands == 0 {
+            return Err(VisqolError::InvalidNumBands {
+                num_bands,
+                reason: String::from("at least 1 band is required"),
+            });
+        }
+        let patch_creator: Box<dyn PatchCreator> = match (self.patch_creator, &self.variant) {
+            (Some(patch_creator), _) => patch_creator,
+            (None, Variant::Wideband { .. } | Variant::Narrowband { .. }) => {
+                let mut patch_creator =
+                    VadPatchCreator::with_rms_threshold(PATCH_SIZE_AUDIO, self.vad_rms_threshold)
+                        .with_stride(self.patch_stride.unwrap_or(PATCH_SIZE_AUDIO))?;
+                patch_creator.set_vad_enabled(self.vad);
+                Box::new(patch_creator)
             }
-            Variant::Fullband { model_path } => {
-                patch_creator = Box::new(ImagePatchCreator::new(PATCH_SIZE_SPEECH));
-                sim_to_quality_mapper = Box::new(SvrSimilarityToQualityMapper::new(&model_path));
+            (None, Variant::Fullband { .. }) => Box::new(
+                ImagePatchCreator::new(PATCH_SIZE_SPEECH)
+                    .with_stride(self.patch_stride.unwrap_or(PATCH_SIZE_SPEECH))?,
+            ),
+        };
+        // Only load the default mapper if no custom mapper is provided.
+        let sim_to_quality_mapper: Box<dyn SimilarityToQualityMapper> =
+            match (self.sim_to_quality_mapper, self.variant) {
+                (Some(mapper), _) => mapper,
+                (
+                    None,
+                    Variant::Wideband {
+                        use_unscaled_mos_mapping,
+                    }
+                    | Variant::Narrowband {
+                        use_unscaled_mos_mapping,
+                    },
+                ) => Box::new(SpeechSimilarityToQualityMapper::new(
+                    !use_unscaled_mos_mapping,
+                )),
+                (None, Variant::Fullband { model_path }) => match model_path {
+                    Some(model_path) => {
+                        Box::new(SvrSimilarityToQualityMapper::from_path(&model_path)?)
+                    }
+                    None => Box::new(SvrSimilarityToQualityMapper::embedded()),
+                },
+            };
+        let sim_to_quality_mapper = match self.band_weights {
+            Some(band_weights) => Box::new(BandWeightedMapper::new(
+                sim_to_quality_mapper,
+                band_weights,
+                num_bands,
+            )?),
+            None => sim_to_quality_mapper,
+        };
+        if let Some(num_features) = sim_to_quality_mapper.num_features() {
+            if num_features != num_bands {
+                return Err(VisqolError::InvalidNumBands {
+                    num_bands,
+                    reason: format!("the quality mapper expects {} features", num_features),
+                });
             }
         }
 
-        let patch_selector =
-            ComparisonPatchesSelector::new(NeurogramSimiliarityIndexMeasure::default());
+        let mut patch_selector = ComparisonPatchesSelector::new(
+            NeurogramSimiliarityIndexMeasure::new(self.nsim_constants),
+        );
+        patch_selector.set_record_similarity_matrix(self.record_similarity_matrix);
 
-        Self {
-            search_window: window_size,
+        Ok(VisqolManager {
+            num_bands,
+            sample_rate,
+            search_window: self.search_window,
             patch_creator,
             patch_selector,
             sim_to_quality_mapper,
-        }
+            resample_to_match: self.resample_to_match,
+            downmix: self.downmix,
+            remove_dc_offset: self.remove_dc_offset,
+            weighting: self.weighting,
+            loudness_normalization: self.loudness_normalization,
+            silence_threshold_db: self.silence_threshold_db,
+            align: self.align,
+            duration_tolerance: self.duration_tolerance,
+            strict_duration: self.strict_duration,
+            find_best_subregion: self.find_best_subregion,
+            sanitize_non_finite: self.sanitize_non_finite,
+            identical_signal_fast_path: self.identical_signal_fast_path,
+            min_reliable_patches: self.min_reliable_patches,
+            clamp_mos: self.clamp_mos,
+            record_timings: self.record_timings,
+            max_alignment_lag_seconds: self.max_alignment_lag_seconds,
+            subsample_align: self.subsample_align,
+            alignment_silence_threshold_db: self.alignment_silence_threshold_db,
+            window_function: self.window_function,
+            fft_size: self.fft_size,
+            hop_size: self.hop_size,
+            low_freq_hz: self.low_freq_hz,
+            high_freq_hz: self.high_freq_hz,
+        })
+    }
+}
+
+impl<const NUM_BANDS: usize> VisqolManager<NUM_BANDS> {
+    /// Creates a new instance of with the desired configurations.
+    /// All other options are set to their defaults, use `VisqolManager::builder` to configure them.
+    pub fn new(variant: Variant, window_size: usize) -> Self {
+        Self::builder()
+            .variant(variant)
+            .search_window(window_size)
+            .build()
+    }
+
+    /// Creates a new instance which uses `mapper` instead of the similarity to quality mapper selected by `variant`.
+    /// The rest of the pipeline is identical to `VisqolManager::new`.
+    pub fn with_mapper(
+        variant: Variant,
+        window_size: usize,
+        mapper: Box<dyn SimilarityToQualityMapper>,
+    ) -> Self {
+        Self::builder()
+            .variant(variant)
+            .search_window(window_size)
+            .similarity_to_quality_mapper(mapper)
+            .build()
+    }
+
+    /// Returns a `VisqolManagerBuilder` to configure a new instance.
+    pub fn builder() -> VisqolManagerBuilder<NUM_BANDS> { VisqolManagerBuilder::default() }
+
+    /// Returns the number of frequency bands used for the spectrograms.
+    pub fn num_bands(&self) -> usize { self.num_bands }
+
+    /// If `resample_to_match` is `true`, a degraded signal with a different sample rate than the reference is resampled to the reference's sample rate before alignment.
+    /// By default, such inputs are rejected with `VisqolError::DifferentSampleRates`.
+    pub fn set_resample_to_match(&mut self, resample_to_match: bool) {
+        self.resample_to_match = resample_to_match;
+    }
+
+    /// Sets how multichannel files and readers are combined to mono before scoring. By default, all channels are averaged.
+    pub fn set_downmix(&mut self, downmix: DownmixConfig) { self.downmix = downmix; }
+
+    /// If `remove_dc_offset` is `true`, the mean of each signal is subtracted from it before any other processing, see `audio_utils::remove_dc_offset`.
+    /// Otherwise, a `VisqolWarning::DcOffset` is returned if the offset of either signal exceeds `constants::DC_OFFSET_WARNING_LEVEL`. Disabled by default.
+    pub fn set_remove_dc_offset(&mut self, remove_dc_offset: bool) {
+        self.remove_dc_offset = remove_dc_offset;
+    }
+
+    /// If set, both signals are filtered with the `weighting` curve before loudness normalization and spectrogram analysis,
+    /// see `audio_utils::apply_weighting`. This changes which frequencies dominate the comparison, so the MOS differs
+    /// from unweighted scores. Disabled by default.
+    pub fn set_weighting(&mut self, weighting: Option<WeightingCurve>) {
+        self.weighting = weighting;
+    }
+
+    /// If set, both signals are normalized to `loudness_normalization` before alignment. Disabled by default.
+    pub fn set_loudness_normalization(
+        &mut self,
+        loudness_normalization: Option<NormalizationTarget>,
+    ) {
+        self.loudness_normalization = loudness_normalization;
+    }
+
+    /// If set, leading and trailing samples below `silence_threshold_db` dB relative to full scale are trimmed before alignment. Disabled by default.
+    /// Only the leading silence common to both signals is removed, so the offset between reference and degraded signal is preserved.
+    pub fn set_silence_trimming(&mut self, silence_threshold_db: Option<f64>) {
+        self.silence_threshold_db = silence_threshold_db;
+    }
+
+    /// If `align` is `false`, the degraded signal is scored as-is instead of being globally aligned to the reference first.
+    /// Use this for signals which are already sample-accurately aligned. Enabled by default.
+    pub fn set_align(&mut self, align: bool) { self.align = align; }
+
+    /// Sets the difference in seconds between the durations of reference and degraded signal above which a warning is logged.
+    /// Defaults to `constants::DURATION_MISMATCH_TOLERANCE`.
+    pub fn set_duration_tolerance(&mut self, duration_tolerance: f64) {
+        self.duration_tolerance = duration_tolerance;
+    }
+
+    /// If set, the global alignment only searches for lags of at most `max_alignment_lag_seconds` in either direction.
+    /// By default, lags of up to half the reference's duration are accepted.
+    /// This only affects the global alignment of the whole signals. `search_window` independently bounds how far each patch may move, in frames, when matching patches after the global alignment.
+    pub fn set_max_alignment_lag_seconds(&mut self, max_alignment_lag_seconds: Option<f64>) {
+        self.max_alignment_lag_seconds = max_alignment_lag_seconds;
+    }
+
+    /// If `subsample_align` is `true`, the global alignment estimates the lag to a fraction of a sample and delays the degraded signal accordingly.
+    /// This compensates fractional delays, e.g. introduced by codecs. Disabled by default.
+    pub fn set_subsample_align(&mut self, subsample_align: bool) {
+        self.subsample_align = subsample_align;
+    }
+
+    /// If set, the global alignment skips the leading samples of each signal below `threshold_db` dB relative to full scale while searching the lag,
+    /// see `alignment::globally_align_skipping_silence`. This aligns signals whose silent lead-ins differ in length, e.g. recordings started at different times.
+    /// The reported lag still refers to the complete signals, while `max_alignment_lag_seconds` bounds the lag after the lead-ins. Disabled by default.
+    pub fn set_alignment_silence_skipping(&mut self, threshold_db: Option<f64>) {
+        self.alignment_silence_threshold_db = threshold_db;
+    }
+
+    /// Sets the window function applied to each frame of the signals before computing their spectrograms.
+    /// Defaults to `WindowFunction::Rectangular`, the window used by ViSQOL, which leaves the frames unchanged.
+    pub fn set_window_function(&mut self, window_function: WindowFunction) {
+        self.window_function = window_function;
+    }
+
+    /// Sets the number of samples per spectrogram frame, which must be a power of two.
+    /// If `None`, which is the default, each frame holds 80 ms of audio. Larger frames trade temporal resolution for more stable band energies.
+    /// An invalid size is reported as `VisqolError::InvalidFrameSizes` when scoring.
+    pub fn set_fft_size(&mut self, fft_size: Option<usize>) { self.fft_size = fft_size; }
+
+    /// Sets the number of samples between the starts of two consecutive spectrogram frames, which must lie between 1 and the frame size.
+    /// If `None`, which is the default, consecutive frames are a quarter of the frame size apart.
+    /// An invalid size is reported as `VisqolError::InvalidFrameSizes` when scoring.
+    pub fn set_hop_size(&mut self, hop_size: Option<usize>) { self.hop_size = hop_size; }
+
+    /// Sets the lowest center frequency of the gammatone filterbank in Hz. Defaults to `constants::MINIMUM_FREQ`.
+    /// The number of bands stays the same, so narrowing the frequency range places the bands closer together.
+    pub fn set_low_freq_hz(&mut self, low_freq_hz: f64) { self.low_freq_hz = low_freq_hz; }
+
+    /// Sets the upper bound of the center frequencies of the gammatone filterbank in Hz, which is limited to half the sample rate.
+    /// If `None`, which is the default, it is 8 kHz for `constants::NUM_BANDS_SPEECH` bands and half the sample rate otherwise.
+    /// A range that is empty is reported as `VisqolError::InvalidFrequencyRange` when scoring.
+    pub fn set_high_freq_hz(&mut self, high_freq_hz: Option<f64>) {
+        self.high_freq_hz = high_freq_hz;
+    }
+
+    /// If `strict_duration` is `true`, signals whose durations differ by more than the duration tolerance are rejected with `VisqolError::DurationMismatch` instead of logging a warning.
+    /// Disabled by default.
+    pub fn set_strict_duration(&mut self, strict_duration: bool) {
+        self.strict_duration = strict_duration;
+    }
+
+    /// If `find_best_subregion` is `true` and the reference is longer than the degraded signal, e.g. because the degraded signal is a clip of it,
+    /// the region of the reference matching the degraded signal is located by `alignment::find_subregion_start` and only that region is scored.
+    /// The durations are then not checked against each other and the region is reported in `SimilarityResult::matched_reference_range`. Disabled by default.
+    pub fn set_find_best_subregion(&mut self, find_best_subregion: bool) {
+        self.find_best_subregion = find_best_subregion;
+    }
+
+    /// Signals containing NaN or infinite samples are rejected with `VisqolError::NonFiniteSamples` by default.
+    /// If `sanitize_non_finite` is `true`, such samples are replaced with zeros instead.
+    pub fn set_sanitize_non_finite(&mut self, sanitize_non_finite: bool) {
+        self.sanitize_non_finite = sanitize_non_finite;
+    }
+
+    /// If the aligned degraded signal is sample-identical to the reference, the spectrograms and patch comparison are skipped
+    /// and the maximum MOS of the similarity to quality mapper is returned, see `visqol::identical_signal_result`.
+    /// Signals differing in a single sample are scored as usual. Enabled by default.
+    pub fn set_identical_signal_fast_path(&mut self, identical_signal_fast_path: bool) {
+        self.identical_signal_fast_path = identical_signal_fast_path;
+    }
+
+    /// Results scored from fewer than `min_reliable_patches` patches have `SimilarityResult::is_reliable` set to `false`,
+    /// since a MOS averaged over a few patches depends strongly on their content. Too few patches are compared if the signals
+    /// are short, or if the VAD of the speech variants finds little activity, e.g. in quiet recordings.
+    /// Defaults to `constants::MIN_RELIABLE_PATCHES`. Results of the identical signal fast path are always reliable.
+    pub fn set_min_reliable_patches(&mut self, min_reliable_patches: usize) {
+        self.min_reliable_patches = min_reliable_patches;
+    }
+
+    /// If enabled, the MOS of every result is clamped to [`constants::MIN_MOS`, `constants::MAX_MOS`] after it has been predicted,
+    /// for consumers relying on the 1 to 5 scale. The built-in mappers already predict MOS within it, but custom mappers,
+    /// e.g. an SVR extrapolating beyond its training data, may not. Disabled by default, so that the predicted MOS is returned unchanged.
+    pub fn set_clamp_mos(&mut self, clamp_mos: bool) {
+        self.clamp_mos = clamp_mos;
+    }
+
+    /// If enabled, results hold the NSIM of every reference patch to every degraded patch within its search window in
+    /// `SimilarityResult::similarity_matrix`, for diagnosing the patch alignment. Disabled by default, as the matrix holds a value
+    /// per reference patch and degraded frame. It is not recorded by `run_chunked` and for identical signals skipping the comparison.
+    pub fn set_record_similarity_matrix(&mut self, record_similarity_matrix: bool) {
+        self.patch_selector.set_record_similarity_matrix(record_similarity_matrix);
+    }
+
+    /// If enabled, results hold the time spent decoding, aligning, building the spectrograms and comparing the patches in `SimilarityResult::timings`,
+    /// e.g. to find the bottleneck of a workload. The decoding time is only measured by `run`, `run_range`, `run_with_progress` and `run_chunked`,
+    /// which sums the time of all chunks. Disabled by default. Measuring requires a system clock, which is not available for `wasm32-unknown-unknown`.
+    pub fn set_record_timings(&mut self, record_timings: bool) {
+        self.record_timings = record_timings;
     }
 
     /// Loads the audio store in `ref_signal_path` and `deg_signal_path` and computes its MOS.
     pub fn run(
-        &mut self,
+        &self,
+        ref_signal_path: &str,
+        deg_signal_path: &str,
+    ) -> Result<SimilarityResult, VisqolError> {
+        let (ref_signal, deg_signal, decode_time) =
+            self.decode_pair(ref_signal_path, deg_signal_path)?;
+
+        let result = self.validate_and_compute(ref_signal, deg_signal)?;
+        Ok(Self::with_decode_time(result, decode_time))
+    }
+
+    /// Loads the audio like `run`, crops both signals to `range` and scores the cropped signals, e.g. to only score the chorus of a song.
+    /// The signals are cropped before they are prepared and aligned. A range exceeding a signal is clamped to it with a warning,
+    /// and `VisqolError::InvalidTimeRange` is returned if nothing of a signal remains. The patch times are relative to the start of `range`.
+    pub fn run_range(
+        &self,
         ref_signal_path: &str,
         deg_signal_path: &str,
-    ) -> Result<SimilarityResult, Box<dyn Error>> {
-        let mut ref_signal = audio_utils::load_as_mono(ref_signal_path)?;
-        let mut deg_signal = audio_utils::load_as_mono(deg_signal_path)?;
+        range: TimeRange,
+    ) -> Result<SimilarityResult, VisqolError> {
+        let (ref_signal, deg_signal, decode_time) =
+            self.decode_pair(ref_signal_path, deg_signal_path)?;
 
-        Self::validate_input_audio(&ref_signal, &deg_signal)?;
+        let (ref_signal, ref_warning) = Self::crop(ref_signal, range, ref_signal_path)?;
+        let (deg_signal, deg_warning) = Self::crop(deg_signal, range, deg_signal_path)?;
 
-        self.compute_results(&mut ref_signal, &mut deg_signal)
+        let result = self.validate_and_compute(ref_signal, deg_signal)?;
+        let result = Self::with_warnings(result, ref_warning.into_iter().chain(deg_warning));
+        Ok(Self::with_decode_time(result, decode_time))
     }
 
-    pub fn compute_results(
-        &mut self,
-        ref_signal: &mut AudioSignal,
-        deg_signal: &mut AudioSignal,
-    ) -> Result<SimilarityResult, Box<dyn Error>> {
-        let (mut deg_signal, _) = alignment::globally_align(ref_signal, deg_signal)
-            .ok_or(VisqolError::FailedToAlignSignals)?;
+    /// Loads and scores the audio like `run` and reports the fraction of the work done, from 0.0 to 1.0, to `progress` while the patches are processed.
+    /// The callback does not affect the result. It is called on the calling thread, so it can e.g. update a progress bar.
+    pub fn run_with_progress(
+        &self,
+        ref_signal_path: &str,
+        deg_signal_path: &str,
+        mut progress: impl FnMut(f32),
+    ) -> Result<SimilarityResult, VisqolError> {
+        let (ref_signal, deg_signal, decode_time) =
+            self.decode_pair(ref_signal_path, deg_signal_path)?;
+        let (mut ref_signal, mut deg_signal, warnings) =
+            self.prepare_signals(ref_signal, deg_signal)?;
 
-        visqol::calculate_similarity::<NUM_BANDS>(
-            ref_signal,
+        let result = self.compute_results_with_progress(
+            &mut ref_signal,
             &mut deg_signal,
-            self.patch_creator.as_mut(),
-            &self.patch_selector,
-            self.sim_to_quality_mapper.as_mut(),
-            self.search_window,
-        )
+            None,
+            &mut progress,
+        )?;
+        Ok(Self::with_decode_time(Self::with_warnings(result, warnings), decode_time))
     }
 
-    /// Performs sanity checks on the configuration to prevent incorrect use of the algorithm.
-    fn validate_input_audio(
-        ref_signal: &AudioSignal,
-        deg_signal: &AudioSignal,
-    ) -> Result<(), VisqolError> {
-        if ref_signal.sample_rate != deg_signal.sample_rate {
-            return Err(VisqolError::DifferentSampleRates {
-                reference: ref_signal.sample_rate,
-                degraded: deg_signal.sample_rate,
+    /// Loads and prepares the audio like `run`, aligns the signals once globally and scores each segment of `segment_seconds` of the aligned pair separately,
+    /// e.g. to find the worst 30 seconds of a long recording. The segments are returned in order together with their time range in the reference signal.
+    /// A remainder which is shorter than half a segment or too short to be scored is added to the last segment.
+    /// The patch times of each result are relative to the start of the reference signal and `alignment_lag_seconds` holds the global lag.
+    pub fn run_segmented(
+        &self,
+        ref_signal_path: &str,
+        deg_signal_path: &str,
+        segment_seconds: f64,
+    ) -> Result<Vec<(TimeRange, SimilarityResult)>, VisqolError> {
+        if segment_seconds.is_nan() || segment_seconds <= 0.0 {
+            return Err(VisqolError::InvalidSegmentDuration { segment_seconds });
+        }
+        let ref_signal = audio_utils::load_as_mono_with(ref_signal_path, &self.downmix)?;
+        let deg_signal = audio_utils::load_as_mono_with(deg_signal_path, &self.downmix)?;
+        let (ref_signal, deg_signal, warnings) = self.prepare_signals(ref_signal, deg_signal)?;
+        let sample_rate = ref_signal.sample_rate;
+
+        let min_required = self.patch_creator.patch_size().map_or(0.0, |patch_size| {
+            self.min_signal_duration(patch_size, sample_rate)
+        });
+        if segment_seconds < min_required {
+            return Err(VisqolError::SignalTooShort {
+                duration: segment_seconds,
+                min_required,
             });
         }
 
-        if (ref_signal.get_duration() - deg_signal.get_duration()).abs()
-            > constants::DURATION_MISMATCH_TOLERANCE
-        {
-            log::warn!("Mismatch in duration between reference and degraded signal. Reference is {} seconds. Degraded is {} seconds.", ref_signal.get_duration(), deg_signal.get_duration());
+        let alignment = self.align_degraded(&ref_signal, &deg_signal)?;
+        let lag = alignment.as_ref().map_or(0.0, |alignment| alignment.lag_seconds);
+        let deg_signal = alignment.map_or(deg_signal, |alignment| alignment.aligned_degraded);
+
+        let to_samples = |seconds: f64| (seconds * sample_rate as f64).ceil() as usize;
+        let segment_len = to_samples(segment_seconds).max(1);
+        let min_remainder = (segment_len / 2).max(to_samples(min_required));
+        let num_samples = ref_signal.len();
+        let mut segments = Vec::new();
+        let mut start = 0;
+        while start < num_samples {
+            let mut end = (start + segment_len).min(num_samples);
+            if num_samples - end < min_remainder {
+                end = num_samples;
+            }
+            // The last segment holds the whole remainder of the degraded signal, which may be longer.
+            let deg_end = if end == num_samples {
+                deg_signal.len()
+            } else {
+                end.min(deg_signal.len())
+            };
+            let segment = |signal: &AudioSignal, start: usize, end: usize| AudioSignal {
+                data_matrix: signal.data_matrix.slice(s![start..end]).to_owned(),
+                sample_rate,
+            };
+            let mut ref_segment = segment(&ref_signal, start, end);
+            let mut deg_segment = segment(&deg_signal, start.min(deg_end), deg_end);
+
+            let result =
+                self.score_aligned(&mut ref_segment, &mut deg_segment, None, &mut |_| {})?;
+            let mut result = Self::with_warnings(result, warnings.iter().cloned());
+            let offset = start as f64 / sample_rate as f64;
+            Self::shift_patch_times(&mut result.patch_sims, offset);
+            result.alignment_lag_seconds = lag;
+            segments.push((TimeRange::new(offset, end as f64 / sample_rate as f64), result));
+            start = end;
         }
-        Ok(())
+        Ok(segments)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use approx::assert_abs_diff_eq;
+    /// Scores the wav files in `ref_signal_path` and `deg_signal_path` chunk by chunk, so that peak memory is bounded by the chunk size instead of the file length.
+    /// Each chunk covers `chunk_seconds` of both signals plus `overlap_seconds` on each side. Its signals are prepared and aligned like in `run`,
+    /// and only the patches starting within the chunk itself are kept. The similarity of all kept patches is aggregated to the MOS.
+    /// The durations are validated and the warnings and DC offsets are computed once for the whole files, which are read an additional time for this.
+    /// Loudness normalization, silence trimming, resampling to match the reference and finding the best reference subregion depend on the whole signals
+    /// and are rejected with `VisqolError::UnsupportedInChunkedMode`.
+    ///
+    /// Patches starting close to a chunk edge are matched against degraded audio from the overlap. To avoid artifacts at the chunk edges,
+    /// the overlap should span the search window of a patch, see `VisqolManager::chunk_overlap_seconds`. Smaller overlaps work well
+    /// if the degraded signal has little local timing drift, as each chunk is globally aligned before its patches are matched.
+    /// The remainder of the signals shorter than half a chunk is added to the last chunk, and chunks without any matching patch are skipped,
+    /// as well as chunks for which the degraded signal ends too early to hold a patch. `patch_offsets` is empty
+    /// and `alignment_lag_seconds` holds the lag of the first chunk. `num_reference_patches` counts the kept patches and the reference patches
+    /// of scored chunks which could not be compared, but not the patches of skipped chunks. Both files must have the same sample rate,
+    /// which must be the one the variant is designed for, see `compute_results`.
+    pub fn run_chunked(
+        &self,
+        ref_signal_path: &str,
+        deg_signal_path: &str,
+        chunk_seconds: f64,
+        overlap_seconds: f64,
+    ) -> Result<SimilarityResult, VisqolError> {
+        if !(chunk_seconds > 0.0 && overlap_seconds >= 0.0) {
+            return Err(VisqolError::InvalidChunkSize {
+                chunk_seconds,
+                overlap_seconds,
+            });
+        }
+        let unsupported = [
+            (self.loudness_normalization.is_some(), "Loudness normalization"),
+            (self.silence_threshold_db.is_some(), "Silence trimming"),
+            (self.resample_to_match, "Resampling to match the reference"),
+            (self.find_best_subregion, "Finding the best reference subregion"),
+        ];
+        if let Some((_, option)) = unsupported.iter().find(|(enabled, _)| *enabled) {
+            return Err(VisqolError::UnsupportedInChunkedMode {
+                option: option.to_string(),
+            });
+        }
+        let mut ref_reader = audio_utils::open_wav_chunks(ref_signal_path)?;
+        let mut deg_reader = audio_utils::open_wav_chunks(deg_signal_path)?;
+        let readers = [(&ref_reader, SignalRole::Reference), (&deg_reader, SignalRole::Degraded)];
+        for (reader, which) in readers {
+            if reader.num_frames() == 0 {
+                return Err(VisqolError::EmptySignal { which });
+            }
+        }
+        let sample_rate = ref_reader.sample_rate();
+        self.check_variant_sample_rate(sample_rate)?;
+        if sample_rate != deg_reader.sample_rate() {
+            return Err(VisqolError::DifferentSampleRates {
+                reference: sample_rate,
+                degraded: deg_reader.sample_rate(),
+            });
+        }
 
-    #[test]
-    fn visqol_returns_expected_mos() {
-        use super::*;
-        use crate::constants;
-        let mut vm = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::new(
-            Variant::Wideband {
-                use_unscaled_mos_mapping: false,
-            },
-            60,
+        let num_frames = ref_reader.num_frames();
+        let to_seconds = |frame: u32| frame as f64 / sample_rate as f64;
+        let chunk_frames = ((chunk_seconds * sample_rate as f64) as u32).max(1);
+        let overlap_frames = (overlap_seconds * sample_rate as f64) as u32;
+        let config = self.spectrogram_config();
+        let frame_duration = visqol::calculate_frame_duration(
+            config.analysis_window(sample_rate).hop_size() as f64,
+            sample_rate as usize,
         );
+        let min_duration = self
+            .patch_creator
+            .patch_size()
+            .map(|patch_size| self.min_signal_duration(patch_size, sample_rate));
 
-        let res = vm
-            .run(
-                "test_data/clean_speech/reference_signal_16k.wav",
-                "test_data/clean_speech/degraded_signal_16k.wav",
-            )
-            .unwrap();
-        assert_abs_diff_eq!(res.moslqo, 2.35, epsilon = 0.01);
+        let mut timings = Timings::default();
+        let (stats, stats_time) = self.timed(|| -> Result<_, VisqolError> {
+            Ok((
+                self.signal_stats(&mut ref_reader, chunk_frames)?,
+                self.signal_stats(&mut deg_reader, chunk_frames)?,
+            ))
+        });
+        let (ref_stats, deg_stats) = stats?;
+        timings.decode += stats_time.unwrap_or_default();
+        let mut warnings =
+            Self::check_clipping(deg_stats.clipped_fraction).into_iter().collect::<Vec<_>>();
+        warnings.extend(Self::check_silence(ref_stats.is_silent, deg_stats.is_silent));
+        if !self.remove_dc_offset {
+            warnings.extend(Self::check_dc_offset(ref_stats.dc_offset, deg_stats.dc_offset));
+        }
+        warnings.extend(self.validate_durations(
+            to_seconds(num_frames),
+            to_seconds(deg_reader.num_frames()),
+            sample_rate,
+            false,
+        )?);
+
+        let mut patches = Vec::new();
+        let mut num_unmatched_patches = 0;
+        let mut center_freq_bands = Vec::new();
+        let mut alignment_lag_seconds = None;
+        let mut chunk_start = 0;
+        while chunk_start < num_frames {
+            let mut chunk_end = chunk_start.saturating_add(chunk_frames).min(num_frames);
+            if num_frames - chunk_end < chunk_frames / 2 {
+                chunk_end = num_frames;
+            }
+            let read_start = chunk_start.saturating_sub(overlap_frames);
+            let read_end = chunk_end.saturating_add(overlap_frames).min(num_frames);
+            // The last chunk holds the whole remainder of the degraded signal, which may be longer.
+            let deg_read_end = if chunk_end == num_frames {
+                deg_reader.num_frames().max(read_end)
+            } else {
+                read_end
+            };
+            let (chunks, decode_time) = self.timed(|| -> Result<_, VisqolError> {
+                Ok((
+                    audio_utils::read_mono_chunk(
+                        &mut ref_reader,
+                        read_start,
+                        read_end - read_start,
+                        &self.downmix,
+                    )?,
+                    audio_utils::read_mono_chunk(
+                        &mut deg_reader,
+                        read_start,
+                        deg_read_end - read_start,
+                        &self.downmix,
+                    )?,
+                ))
+            });
+            let (mut ref_signal, mut deg_signal) = chunks?;
+            timings.decode += decode_time.unwrap_or_default();
+
+            if let Some(min_required) = min_duration {
+                if ref_signal.get_duration() < min_required {
+                    return Err(VisqolError::SignalTooShort {
+                        duration: ref_signal.get_duration(),
+                        min_required,
+                    });
+                }
+            }
+            // The degraded signal may end before this chunk, or too early to hold a patch.
+            if deg_signal.is_empty()
+                || min_duration.is_some_and(|min_duration| deg_signal.get_duration() < min_duration)
+            {
+                chunk_start = chunk_end;
+                continue;
+            }
+            for (signal, stats) in [(&mut ref_signal, &ref_stats), (&mut deg_signal, &deg_stats)] {
+                self.handle_non_finite_samples(signal)?;
+                if self.remove_dc_offset {
+                    signal.data_matrix -= stats.dc_offset;
+                }
+                if let Some(curve) = self.weighting {
+                    audio_utils::apply_weighting(signal, curve);
+                }
+            }
+            let mut aligned_deg_signal;
+            let (alignment, alignment_time) =
+                self.timed(|| self.align_degraded(&ref_signal, &deg_signal));
+            timings.alignment += alignment_time.unwrap_or_default();
+            let deg_signal = match alignment? {
+                Some(alignment) => {
+                    alignment_lag_seconds.get_or_insert(alignment.lag_seconds);
+                    aligned_deg_signal = alignment.aligned_degraded;
+                    &mut aligned_deg_signal
+                }
+                None => &mut deg_signal,
+            };
+            let result = match visqol::calculate_similarity(
+                &config,
+                &mut ref_signal,
+                deg_signal,
+                self.patch_creator.as_ref(),
+                &self.patch_selector,
+                self.sim_to_quality_mapper.as_ref(),
+                self.search_window,
+                None,
+                self.record_timings,
+                &mut |_| {},
+            ) {
+                Ok(result) => result,
+                // A chunk without any matching patch does not contribute to the result.
+                Err(VisqolError::SignalsTooDifferent) => {
+                    chunk_start = chunk_end;
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+
+            num_unmatched_patches += result.num_reference_patches - result.num_patches;
+            if let Some(chunk_timings) = result.timings {
+                timings.spectrogram += chunk_timings.spectrogram;
+                timings.nsim += chunk_timings.nsim;
+            }
+            let mut chunk_patches = result.patch_sims;
+            Self::shift_patch_times(&mut chunk_patches, to_seconds(read_start));
+            let owned = to_seconds(chunk_start)..to_seconds(chunk_end);
+            patches.extend(
+                chunk_patches
+                    .into_iter()
+                    .filter(|patch| owned.contains(&patch.ref_patch_start_time)),
+            );
+            center_freq_bands = result.center_freq_bands;
+            chunk_start = chunk_end;
+        }
+
+        if patches.is_empty() {
+            return Err(VisqolError::SignalsTooDifferent);
+        }
+        let mut result = visqol::similarity_from_patches(
+            patches,
+            center_freq_bands,
+            frame_duration,
+            self.sim_to_quality_mapper.as_ref(),
+        );
+        result.alignment_lag_seconds = alignment_lag_seconds.unwrap_or(0.0);
+        result.num_reference_patches = result.num_patches + num_unmatched_patches;
+        result.timings = self.record_timings.then_some(timings);
+        result.warnings = warnings;
+        result.is_reliable = result.num_patches >= self.min_reliable_patches;
+        Ok(self.finish_result(result))
+    }
+
+    /// Returns the overlap in seconds which `run_chunked` needs for signals sampled at `sample_rate`, so that every patch near a chunk edge
+    /// can be compared with all degraded patches in its search window. This is the duration of `search_window + 1` patches and e.g.
+    /// about 20 seconds for speech with the default search window. Returns `None` if the patch creator does not have a fixed patch size.
+    pub fn chunk_overlap_seconds(&self, sample_rate: u32) -> Option<f64> {
+        let hop_size = self.spectrogram_config().analysis_window(sample_rate).hop_size();
+        self.patch_creator.patch_size().map(|patch_size| {
+            ((self.search_window + 1) * patch_size * hop_size) as f64 / sample_rate as f64
+        })
+    }
+
+    /// Decodes the audio data provided by `reference` and `degraded` according to their format hints and computes its MOS.
+    /// No data is written to the filesystem, so e.g. a `std::io::Cursor<Vec<u8>>` can be used to score in-memory files.
+    pub fn run_from_readers<R: Read + Seek, D: Read + Seek>(
+        &self,
+        reference: R,
+        reference_hint: FormatHint,
+        degraded: D,
+        degraded_hint: FormatHint,
+    ) -> Result<SimilarityResult, VisqolError> {
+        let ref_signal =
+            audio_utils::load_as_mono_from_reader_with(reference, reference_hint, &self.downmix)?;
+        let deg_signal =
+            audio_utils::load_as_mono_from_reader_with(degraded, degraded_hint, &self.downmix)?;
+
+        self.validate_and_compute(ref_signal, deg_signal)
+    }
+
+    /// Computes the MOS of the in-memory mono signals `reference` and `degraded`, both sampled at `sample_rate`.
+    pub fn run_from_samples(
+        &self,
+        reference: &[f64],
+        degraded: &[f64],
+        sample_rate: u32,
+    ) -> Result<SimilarityResult, VisqolError> {
+        let ref_signal = AudioSignal::new(reference, sample_rate);
+        let deg_signal = AudioSignal::new(degraded, sample_rate);
+
+        self.validate_and_compute(ref_signal, deg_signal)
+    }
+
+    /// Computes the MOS of the owned mono signals `reference` and `degraded` like `run_from_samples`, offloading the work to the blocking thread pool of `tokio`,
+    /// so that the calling task does not block the executor. The manager is shared with the blocking task, so it is passed in an `Arc`.
+    ///
+    /// # Panics
+    /// Panics if not awaited within a `tokio` runtime, and resumes the panic if scoring panicked.
+    #[cfg(feature = "async")]
+    pub async fn run_async(
+        self: std::sync::Arc<Self>,
+        reference: Vec<f64>,
+        degraded: Vec<f64>,
+        sample_rate: u32,
+    ) -> Result<SimilarityResult, VisqolError> {
+        let scoring = move || self.run_from_samples(&reference, &degraded, sample_rate);
+        match tokio::task::spawn_blocking(scoring).await {
+            Ok(result) => result,
+            Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Loads the audio stored in `ref_signal_path` and `deg_signal_path` and computes the MOS of each pair of channels with the same index.
+    /// Both files must have the same number of channels.
+    pub fn run_multichannel(
+        &self,
+        ref_signal_path: &str,
+        deg_signal_path: &str,
+    ) -> Result<Vec<SimilarityResult>, VisqolError> {
+        let ref_channels = audio_utils::load_channels(ref_signal_path)?;
+        let deg_channels = audio_utils::load_channels(deg_signal_path)?;
+
+        if ref_channels.len() != deg_channels.len() {
+            return Err(VisqolError::DifferentChannelCounts {
+                reference: ref_channels.len(),
+                degraded: deg_channels.len(),
+            });
+        }
+
+        ref_channels
+            .into_iter()
+            .zip(deg_channels)
+            .map(|(ref_signal, deg_signal)| self.validate_and_compute(ref_signal, deg_signal))
+            .collect()
+    }
+
+    /// Computes the MOS of each `(reference, degraded)` pair of file paths in `pairs` in parallel, using all available cores.
+    /// Without the `parallel` feature, the pairs are scored one after another.
+    /// The results are returned in the order of `pairs`. A pair which fails to be scored does not affect the other pairs.
+    /// The results can be aggregated into a report with `BatchSummary::from_results`.
+    pub fn run_batch(
+        &self,
+        pairs: &[(String, String)],
+    ) -> Vec<Result<SimilarityResult, VisqolError>> {
+        self.run_batch_with_progress(pairs, |_, _| {})
+    }
+
+    /// Scores the `pairs` like `run_batch` and calls `on_pair_scored` with the index of each pair in `pairs` and its result as soon as it is scored.
+    /// With the `parallel` feature, the callback is called from multiple threads and pairs complete in any order.
+    pub fn run_batch_with_progress(
+        &self,
+        pairs: &[(String, String)],
+        on_pair_scored: impl Fn(usize, &Result<SimilarityResult, VisqolError>) + Sync,
+    ) -> Vec<Result<SimilarityResult, VisqolError>> {
+        #[cfg(feature = "parallel")]
+        let pairs = pairs.par_iter().enumerate();
+        #[cfg(not(feature = "parallel"))]
+        let pairs = pairs.iter().enumerate();
+        pairs
+            .map(|(index, (ref_signal_path, deg_signal_path))| {
+                let result = self.run(ref_signal_path, deg_signal_path);
+                on_pair_scored(index, &result);
+                result
+            })
+            .collect()
+    }
+
+    /// Scores each file in the directory `deg_dir` against the file with the same name in the directory `ref_dir` like `run_batch`, e.g. for an evaluation set
+    /// with `reference/` and `degraded/` directories. Returns the results together with the file names, sorted by name.
+    /// A file without a counterpart in the other directory is reported with `VisqolError::FileNotFound` for the missing file instead of being skipped.
+    /// Subdirectories are ignored. Returns an error if either directory cannot be read.
+    pub fn run_directory(
+        &self,
+        ref_dir: &str,
+        deg_dir: &str,
+    ) -> Result<Vec<NamedResult>, VisqolError> {
+        let mut names = Self::file_names(ref_dir)?;
+        names.extend(Self::file_names(deg_dir)?);
+        let path = |dir: &str, name: &str| Path::new(dir).join(name).to_string_lossy().into_owned();
+        let pairs = names
+            .iter()
+            .map(|name| (path(ref_dir, name), path(deg_dir, name)))
+            .collect::<Vec<(String, String)>>();
+
+        // A missing counterpart fails to load with `VisqolError::FileNotFound`.
+        Ok(names.into_iter().zip(self.run_batch(&pairs)).collect())
+    }
+
+    /// Scores the files matching the glob pattern `deg_pattern` against those matching `ref_pattern` like `run_batch`, e.g. `deg/*.wav` against `ref/*.wav`.
+    /// The matches of both patterns are paired in sorted order, see `glob_pairs`, which also returns the pairs in the order of the results.
+    #[cfg(feature = "glob")]
+    pub fn run_glob(
+        &self,
+        ref_pattern: &str,
+        deg_pattern: &str,
+    ) -> Result<Vec<Result<SimilarityResult, VisqolError>>, VisqolError> {
+        Ok(self.run_batch(&glob_pairs(ref_pattern, deg_pattern)?))
+    }
+
+    /// Returns the names of the files in the directory `dir`, sorted by name.
+    fn file_names(dir: &str) -> Result<BTreeSet<String>, VisqolError> {
+        let mut names = BTreeSet::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                names.insert(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        Ok(names)
+    }
+
+    /// Scores each of the degraded files in `deg_signal_paths` against the reference file in `ref_signal_path`, e.g. to compare several codecs.
+    /// The reference is loaded once and its spectrogram is only built once, while each degraded file is still aligned separately.
+    /// If silence trimming is enabled, the reference differs per degraded file and its spectrogram is built for each of them.
+    /// If the reference spectrogram cannot be built, a warning is logged and building it is retried for each degraded file, so each result holds the error.
+    /// Returns an error if the reference cannot be loaded, otherwise the results in the order of `deg_signal_paths`.
+    /// With the `parallel` feature, the degraded files are scored in parallel.
+    pub fn run_against_many(
+        &self,
+        ref_signal_path: &str,
+        deg_signal_paths: &[String],
+    ) -> Result<Vec<Result<SimilarityResult, VisqolError>>, VisqolError> {
+        let ref_signal = audio_utils::load_as_mono_with(ref_signal_path, &self.downmix)?;
+        let ref_spectrogram = std::sync::OnceLock::new();
+
+        let score = |deg_signal_path: &String| {
+            let deg_signal = audio_utils::load_as_mono_with(deg_signal_path, &self.downmix)?;
+            let (mut ref_signal, mut deg_signal, warnings) =
+                self.prepare_signals(ref_signal.clone(), deg_signal)?;
+            let ref_spectrogram = if self.silence_threshold_db.is_none() {
+                let cached = ref_spectrogram.get_or_init(|| {
+                    visqol::build_spectrogram(&self.spectrogram_config(), &ref_signal).inspect_err(
+                        |error| {
+                            log_warn!("Failed to build the reference spectrogram: {}", error);
+                        },
+                    )
+                });
+                cached.as_ref().ok()
+            } else {
+                None
+            };
+            let result = self.compute_results_with_progress(
+                &mut ref_signal,
+                &mut deg_signal,
+                ref_spectrogram,
+                &mut |_| {},
+            )?;
+            Ok(Self::with_warnings(result, warnings))
+        };
+
+        #[cfg(feature = "parallel")]
+        let deg_signal_paths = deg_signal_paths.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let deg_signal_paths = deg_signal_paths.iter();
+        Ok(deg_signal_paths.map(score).collect())
+    }
+
+    /// Loads the audio stored in `ref_signal_path` and `deg_signal_path`, preprocesses it like `run` does and returns the gammatone spectrograms of the reference and the degraded signal.
+    /// Each spectrogram holds one row per frequency band and one column per frame, in dB above the common noise floor. These are the spectrograms the patches are compared on.
+    pub fn compute_spectrograms(
+        &self,
+        ref_signal_path: &str,
+        deg_signal_path: &str,
+    ) -> Result<(Spectrogram, Spectrogram), VisqolError> {
+        let (ref_signal, deg_signal) = self.scored_signals(ref_signal_path, deg_signal_path)?;
+        visqol::calculate_spectrograms(&self.spectrogram_config(), &ref_signal, &deg_signal)
+    }
+
+    /// Loads the audio stored in `ref_signal_path` and `deg_signal_path`, preprocesses it like `run` does and returns the reference and the globally aligned degraded signal.
+    /// These are the signals which are scored, e.g. to save them with `audio_utils::save_wav` when investigating an alignment.
+    pub fn scored_signals(
+        &self,
+        ref_signal_path: &str,
+        deg_signal_path: &str,
+    ) -> Result<(AudioSignal, AudioSignal), VisqolError> {
+        let ref_signal = audio_utils::load_as_mono_with(ref_signal_path, &self.downmix)?;
+        let deg_signal = audio_utils::load_as_mono_with(deg_signal_path, &self.downmix)?;
+        let (ref_signal, deg_signal, _) = self.prepare_signals(ref_signal, deg_signal)?;
+
+        match self.align_degraded(&ref_signal, &deg_signal)? {
+            Some(alignment) => Ok((ref_signal, alignment.aligned_degraded)),
+            None => Ok((ref_signal, deg_signal)),
+        }
+    }
+
+    /// Aligns `deg_signal` to `ref_signal` if alignment is enabled and computes their MOS.
+    /// Returns `VisqolError::SampleRateVariantMismatch` if the signals are not sampled at the rate the variant is designed for,
+    /// e.g. 48 kHz audio scored by `Variant::Wideband`, as the bands and the MOS mapping of the variant would not fit the signals.
+    pub fn compute_results(
+        &self,
+        ref_signal: &mut AudioSignal,
+        deg_signal: &mut AudioSignal,
+    ) -> Result<SimilarityResult, VisqolError> {
+        self.compute_results_with_progress(ref_signal, deg_signal, None, &mut |_| {})
+    }
+
+    /// Computes the results like `compute_results` and reports the fraction of the work done to `progress`, see `visqol::calculate_similarity`.
+    /// `ref_spectrogram` is passed on to `score_aligned`.
+    fn compute_results_with_progress(
+        &self,
+        ref_signal: &mut AudioSignal,
+        deg_signal: &mut AudioSignal,
+        ref_spectrogram: Option<&Spectrogram>,
+        progress: &mut dyn FnMut(f32),
+    ) -> Result<SimilarityResult, VisqolError> {
+        let mut ref_subregion;
+        let (ref_signal, ref_spectrogram, matched_range) =
+            match self.find_subregion(ref_signal, deg_signal)? {
+                Some((subregion, range)) => {
+                    ref_subregion = subregion;
+                    (&mut ref_subregion, None, Some(range))
+                }
+                None => (ref_signal, ref_spectrogram, None),
+            };
+        let mut aligned_deg_signal;
+        let (alignment, alignment_time) =
+            self.timed(|| self.align_degraded(ref_signal, deg_signal));
+        let (deg_signal, lag) = match alignment? {
+            Some(alignment) => {
+                aligned_deg_signal = alignment.aligned_degraded;
+                (&mut aligned_deg_signal, alignment.lag_seconds)
+            }
+            None => (deg_signal, 0.0),
+        };
+
+        let mut result = self.score_aligned(ref_signal, deg_signal, ref_spectrogram, progress)?;
+        result.alignment_lag_seconds = lag;
+        result.matched_reference_range = matched_range;
+        if let (Some(timings), Some(alignment_time)) = (result.timings.as_mut(), alignment_time) {
+            timings.alignment = alignment_time;
+        }
+        Ok(result)
+    }
+
+    /// Scores signals which are already aligned, skipping the comparison for identical signals if the fast path is enabled.
+    /// The comparison is also skipped if both signals are silent, as the NSIM of silent patches is not meaningful. The result then has the
+    /// maximum MOS of the quality mapper like identical signals, but is marked as unreliable.
+    /// `ref_spectrogram` is the spectrogram of `ref_signal` if it has already been built, see `visqol::calculate_similarity`.
+    fn score_aligned(
+        &self,
+        ref_signal: &mut AudioSignal,
+        deg_signal: &mut AudioSignal,
+        ref_spectrogram: Option<&Spectrogram>,
+        progress: &mut dyn FnMut(f32),
+    ) -> Result<SimilarityResult, VisqolError> {
+        self.check_variant_sample_rate(ref_signal.sample_rate)?;
+        let both_silent = audio_utils::is_silent(ref_signal) && audio_utils::is_silent(deg_signal);
+        let identical =
+            self.identical_signal_fast_path && ref_signal.data_matrix == deg_signal.data_matrix;
+        if both_silent || identical {
+            progress(1.0);
+            let mut result = visqol::identical_signal_result(
+                &self.spectrogram_config(),
+                ref_signal.sample_rate,
+                self.sim_to_quality_mapper.as_ref(),
+            );
+            result.is_reliable = !both_silent;
+            return Ok(self.finish_result(result));
+        }
+
+        let mut result = visqol::calculate_similarity(
+            &self.spectrogram_config(),
+            ref_signal,
+            deg_signal,
+            self.patch_creator.as_ref(),
+            &self.patch_selector,
+            self.sim_to_quality_mapper.as_ref(),
+            self.search_window,
+            ref_spectrogram,
+            self.record_timings,
+            progress,
+        )?;
+        result.is_reliable = result.num_patches >= self.min_reliable_patches;
+        Ok(self.finish_result(result))
+    }
+
+    /// Applies the MOS clamp to a scored `result`, see `set_clamp_mos`, and warns about reference patches which could not be compared.
+    /// If timings are recorded, results which skipped the comparison get zero timings.
+    fn finish_result(&self, mut result: SimilarityResult) -> SimilarityResult {
+        if result.num_patches < result.num_reference_patches {
+            result.warnings.push(VisqolWarning::DroppedReferencePatches {
+                num_dropped: result.num_reference_patches - result.num_patches,
+                num_reference_patches: result.num_reference_patches,
+            });
+        }
+        if self.clamp_mos {
+            result.moslqo = result.moslqo.clamp(constants::MIN_MOS, constants::MAX_MOS);
+        }
+        if self.record_timings {
+            result.timings.get_or_insert_with(Timings::default);
+        }
+        result
+    }
+
+    /// Runs `stage` and returns its output together with the time it took if timings are recorded, see `set_record_timings`.
+    fn timed<T>(&self, stage: impl FnOnce() -> T) -> (T, Option<Duration>) {
+        let start = self.record_timings.then(Instant::now);
+        let output = stage();
+        (output, start.map(|start| start.elapsed()))
+    }
+
+    /// Loads both files as mono and returns them together with the time it took if timings are recorded.
+    fn decode_pair(
+        &self,
+        ref_signal_path: &str,
+        deg_signal_path: &str,
+    ) -> Result<(AudioSignal, AudioSignal, Option<Duration>), VisqolError> {
+        let (signals, decode_time) = self.timed(|| -> Result<_, VisqolError> {
+            Ok((
+                audio_utils::load_as_mono_with(ref_signal_path, &self.downmix)?,
+                audio_utils::load_as_mono_with(deg_signal_path, &self.downmix)?,
+            ))
+        });
+        let (ref_signal, deg_signal) = signals?;
+        Ok((ref_signal, deg_signal, decode_time))
+    }
+
+    /// Inserts the `warnings` raised while loading and preparing the signals before those raised while scoring them.
+    fn with_warnings(
+        mut result: SimilarityResult,
+        warnings: impl IntoIterator<Item = VisqolWarning>,
+    ) -> SimilarityResult {
+        result.warnings.splice(0..0, warnings);
+        result
+    }
+
+    /// Stores the `decode_time` of the files scored in `result`, if timings are recorded.
+    fn with_decode_time(
+        mut result: SimilarityResult,
+        decode_time: Option<Duration>,
+    ) -> SimilarityResult {
+        if let (Some(timings), Some(decode_time)) = (result.timings.as_mut(), decode_time) {
+            timings.decode = decode_time;
+        }
+        result
+    }
+
+    /// Returns the part of `signal`, loaded from `path`, within `range`. A range exceeding the signal is clamped and a warning is returned.
+    fn crop(
+        signal: AudioSignal,
+        range: TimeRange,
+        path: &str,
+    ) -> Result<(AudioSignal, Option<VisqolWarning>), VisqolError> {
+        let duration = signal.get_duration();
+        let start = range.start.max(0.0);
+        let end = range.end.min(duration);
+        if start >= end {
+            return Err(VisqolError::InvalidTimeRange {
+                start: range.start,
+                end: range.end,
+            });
+        }
+        let warning = (start != range.start || end != range.end).then(|| {
+            let warning = VisqolWarning::TimeRangeClamped {
+                path: path.to_string(),
+                start: range.start,
+                end: range.end,
+                duration,
+            };
+            log_warn!("{}", warning);
+            warning
+        });
+
+        let to_sample = |seconds: f64| (seconds * signal.sample_rate as f64) as usize;
+        let cropped = AudioSignal {
+            data_matrix: signal
+                .data_matrix
+                .slice(s![to_sample(start)..to_sample(end).min(signal.len())])
+                .to_owned(),
+            sample_rate: signal.sample_rate,
+        };
+        Ok((cropped, warning))
+    }
+
+    /// Moves the reference and degraded times of `patches` by `offset` seconds, e.g. from the start of a segment to the start of the signal.
+    fn shift_patch_times(patches: &mut [PatchSimilarityResult], offset: f64) {
+        for patch in patches {
+            patch.ref_patch_start_time += offset;
+            patch.ref_patch_end_time += offset;
+            patch.deg_patch_start_time += offset;
+            patch.deg_patch_end_time += offset;
+        }
+    }
+
+    /// Returns the configuration of the spectrograms the patches are compared on.
+    fn spectrogram_config(&self) -> SpectrogramConfig {
+        SpectrogramConfig {
+            num_bands: self.num_bands,
+            window_function: self.window_function,
+            fft_size: self.fft_size,
+            hop_size: self.hop_size,
+            low_freq_hz: self.low_freq_hz,
+            high_freq_hz: self.high_freq_hz,
+        }
+    }
+
+    /// Returns the region of `ref_signal` matching `deg_signal` together with its time range if enabled and the reference is longer, otherwise `None`.
+    fn find_subregion(
+        &self,
+        ref_signal: &AudioSignal,
+        deg_signal: &AudioSignal,
+    ) -> Result<Option<(AudioSignal, TimeRange)>, VisqolError> {
+        if !self.find_best_subregion || ref_signal.len() <= deg_signal.len() {
+            return Ok(None);
+        }
+        let start = alignment::find_subregion_start(ref_signal, deg_signal)
+            .ok_or(VisqolError::FailedToAlignSignals)?;
+        let end = start + deg_signal.len();
+        let subregion = AudioSignal {
+            data_matrix: ref_signal.data_matrix.slice(s![start..end]).to_owned(),
+            sample_rate: ref_signal.sample_rate,
+        };
+        let to_seconds = |sample: usize| sample as f64 / ref_signal.sample_rate as f64;
+        Ok(Some((subregion, TimeRange::new(to_seconds(start), to_seconds(end)))))
+    }
+
+    /// Globally aligns `deg_signal` to `ref_signal` if alignment is enabled, otherwise returns `None`.
+    fn align_degraded(
+        &self,
+        ref_signal: &AudioSignal,
+        deg_signal: &AudioSignal,
+    ) -> Result<Option<AlignmentResult>, VisqolError> {
+        if !self.align {
+            return Ok(None);
+        }
+        let max_lag_seconds = self.max_alignment_lag_seconds;
+        match self.alignment_silence_threshold_db {
+            Some(threshold_db) => alignment::globally_align_skipping_silence(
+                ref_signal,
+                deg_signal,
+                max_lag_seconds,
+                self.subsample_align,
+                threshold_db,
+            ),
+            None if self.subsample_align => {
+                alignment::globally_align_subsample(ref_signal, deg_signal, max_lag_seconds)
+            }
+            None => alignment::globally_align_within(ref_signal, deg_signal, max_lag_seconds),
+        }
+        .map(Some)
+        .ok_or(VisqolError::FailedToAlignSignals)
+    }
+
+    /// Resamples and normalizes the signals if configured to, validates both signals and computes their MOS.
+    fn validate_and_compute(
+        &self,
+        ref_signal: AudioSignal,
+        deg_signal: AudioSignal,
+    ) -> Result<SimilarityResult, VisqolError> {
+        let (mut ref_signal, mut deg_signal, warnings) =
+            self.prepare_signals(ref_signal, deg_signal)?;
+        let result = self.compute_results(&mut ref_signal, &mut deg_signal)?;
+        Ok(Self::with_warnings(result, warnings))
+    }
+
+    /// Resamples, normalizes and trims the signals if configured to and validates both signals.
+    /// Returns the prepared signals together with the warnings raised while checking them.
+    fn prepare_signals(
+        &self,
+        mut ref_signal: AudioSignal,
+        mut deg_signal: AudioSignal,
+    ) -> Result<(AudioSignal, AudioSignal, Vec<VisqolWarning>), VisqolError> {
+        self.check_variant_sample_rate(ref_signal.sample_rate)?;
+        self.handle_non_finite_samples(&mut ref_signal)?;
+        self.handle_non_finite_samples(&mut deg_signal)?;
+        let mut warnings = Self::check_clipping(audio_utils::clipped_fraction(&deg_signal))
+            .into_iter()
+            .collect::<Vec<_>>();
+        warnings.extend(Self::check_silence(
+            audio_utils::is_silent(&ref_signal),
+            audio_utils::is_silent(&deg_signal),
+        ));
+        if self.remove_dc_offset {
+            audio_utils::remove_dc_offset(&mut ref_signal);
+            audio_utils::remove_dc_offset(&mut deg_signal);
+        } else {
+            warnings.extend(Self::check_dc_offset(
+                audio_utils::dc_offset(&ref_signal),
+                audio_utils::dc_offset(&deg_signal),
+            ));
+        }
+
+        let mut deg_signal =
+            if self.resample_to_match && ref_signal.sample_rate != deg_signal.sample_rate {
+                audio_utils::resample(&deg_signal, ref_signal.sample_rate)?
+            } else {
+                deg_signal
+            };
+
+        if let Some(curve) = self.weighting {
+            audio_utils::apply_weighting(&mut ref_signal, curve);
+            audio_utils::apply_weighting(&mut deg_signal, curve);
+        }
+
+        if let Some(target) = self.loudness_normalization {
+            audio_utils::normalize_loudness(&mut ref_signal, target);
+            audio_utils::normalize_loudness(&mut deg_signal, target);
+        }
+
+        warnings.extend(self.validate_input_audio(&ref_signal, &deg_signal)?);
+
+        if let Some(threshold_db) = self.silence_threshold_db {
+            Self::trim_common_silence(&mut ref_signal, &mut deg_signal, threshold_db);
+        }
+
+        Ok((ref_signal, deg_signal, warnings))
+    }
+
+    /// Returns `VisqolError::SampleRateVariantMismatch` if `sample_rate` is not the rate the variant is designed for.
+    fn check_variant_sample_rate(&self, sample_rate: u32) -> Result<(), VisqolError> {
+        if sample_rate != self.sample_rate {
+            return Err(VisqolError::SampleRateVariantMismatch {
+                expected: self.sample_rate,
+                actual: sample_rate,
+            });
+        }
+        Ok(())
+    }
+
+    /// Replaces non-finite samples in `signal` with zeros if sanitizing is enabled, otherwise returns an error holding the index of the first one.
+    fn handle_non_finite_samples(&self, signal: &mut AudioSignal) -> Result<(), VisqolError> {
+        if self.sanitize_non_finite {
+            signal
+                .data_matrix
+                .map_inplace(|sample| *sample = if sample.is_finite() { *sample } else { 0.0 });
+            return Ok(());
+        }
+
+        match signal.data_matrix.iter().position(|sample| !sample.is_finite()) {
+            Some(index) => Err(VisqolError::NonFiniteSamples { index }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns a warning if the `reference` or `degraded` signal is silent, see `audio_utils::is_silent`.
+    fn check_silence(reference: bool, degraded: bool) -> Option<VisqolWarning> {
+        (reference || degraded).then(|| {
+            let warning = VisqolWarning::Silence { reference, degraded };
+            log_warn!("{}", warning);
+            warning
+        })
+    }
+
+    /// Returns a warning if more than `constants::CLIPPING_WARNING_FRACTION` of the samples of the degraded signal are clipped, see `audio_utils::clipped_fraction`.
+    fn check_clipping(fraction: f64) -> Option<VisqolWarning> {
+        (fraction > constants::CLIPPING_WARNING_FRACTION).then(|| {
+            let warning = VisqolWarning::Clipping { fraction };
+            log_warn!("{}", warning);
+            warning
+        })
+    }
+
+    /// Returns a warning if the DC offset of the `reference` or `degraded` signal exceeds `constants::DC_OFFSET_WARNING_LEVEL`.
+    fn check_dc_offset(reference: f64, degraded: f64) -> Option<VisqolWarning> {
+        let level = constants::DC_OFFSET_WARNING_LEVEL;
+        (reference.abs() > level || degraded.abs() > level).then(|| {
+            let warning = VisqolWarning::DcOffset { reference, degraded };
+            log_warn!("{}", warning);
+            warning
+        })
+    }
+
+    /// Removes the leading silence shared by `ref_signal` and `deg_signal` and the trailing silence of each signal.
+    fn trim_common_silence(
+        ref_signal: &mut AudioSignal,
+        deg_signal: &mut AudioSignal,
+        threshold_db: f64,
+    ) {
+        let (ref_leading, ref_trailing) = audio_utils::find_silence(ref_signal, threshold_db);
+        let (deg_leading, deg_trailing) = audio_utils::find_silence(deg_signal, threshold_db);
+        let common_leading = ref_leading.min(deg_leading);
+
+        for (signal, trailing) in [(ref_signal, ref_trailing), (deg_signal, deg_trailing)] {
+            let end = (signal.len() - trailing).max(common_leading);
+            signal.data_matrix = signal.data_matrix.slice(s![common_leading..end]).to_owned();
+        }
+    }
+
+    /// Returns the duration in seconds of the shortest signal whose spectrogram holds a single patch of `patch_size` frames after the offset of the first patch.
+    fn min_signal_duration(&self, patch_size: usize, sample_rate: u32) -> f64 {
+        let window = self.spectrogram_config().analysis_window(sample_rate);
+        let num_frames = patch_size + patch_size / 2;
+
+        (window.size + (num_frames - 1) * window.hop_size()) as f64 / sample_rate as f64
+    }
+
+    /// Performs sanity checks on the configuration to prevent incorrect use of the algorithm.
+    /// Returns warnings about conditions which do not prevent scoring, e.g. a duration mismatch within the strict tolerance.
+    fn validate_input_audio(
+        &self,
+        ref_signal: &AudioSignal,
+        deg_signal: &AudioSignal,
+    ) -> Result<Vec<VisqolWarning>, VisqolError> {
+        let signals = [(ref_signal, SignalRole::Reference), (deg_signal, SignalRole::Degraded)];
+        for (signal, which) in signals {
+            if signal.is_empty() {
+                return Err(VisqolError::EmptySignal { which });
+            }
+        }
+
+        if ref_signal.sample_rate != deg_signal.sample_rate {
+            return Err(VisqolError::DifferentSampleRates {
+                reference: ref_signal.sample_rate,
+                degraded: deg_signal.sample_rate,
+            });
+        }
+
+        let is_clip = self.find_best_subregion && ref_signal.len() > deg_signal.len();
+        self.validate_durations(
+            ref_signal.get_duration(),
+            deg_signal.get_duration(),
+            ref_signal.sample_rate,
+            is_clip,
+        )
+    }
+
+    /// Checks the spectrogram configuration for `sample_rate` and the durations of the reference and degraded signal in seconds,
+    /// which are also known from the headers of files scored in chunks. A duration mismatch is accepted if the degraded signal `is_clip`.
+    fn validate_durations(
+        &self,
+        reference: f64,
+        degraded: f64,
+        sample_rate: u32,
+        is_clip: bool,
+    ) -> Result<Vec<VisqolWarning>, VisqolError> {
+        self.spectrogram_config().validate(sample_rate)?;
+
+        if let Some(patch_size) = self.patch_creator.patch_size() {
+            let min_required = self.min_signal_duration(patch_size, sample_rate);
+            for duration in [reference, degraded] {
+                if duration < min_required {
+                    return Err(VisqolError::SignalTooShort {
+                        duration,
+                        min_required,
+                    });
+                }
+            }
+        }
+
+        let mut warnings = Vec::new();
+        if !is_clip && (reference - degraded).abs() > self.duration_tolerance {
+            if self.strict_duration {
+                return Err(VisqolError::DurationMismatch {
+                    reference,
+                    degraded,
+                });
+            }
+            let warning = VisqolWarning::DurationMismatch {
+                reference,
+                degraded,
+            };
+            log_warn!("{}", warning);
+            warnings.push(warning);
+        }
+        Ok(warnings)
+    }
+
+    /// Reads the signal of `reader` in chunks of `chunk_frames` to compute the statistics of the whole signal which `run_chunked` needs before scoring the first chunk.
+    /// Returns `VisqolError::NonFiniteSamples` holding the index within the whole signal unless non-finite samples are sanitized.
+    fn signal_stats<R: Read + Seek>(
+        &self,
+        reader: &mut WavChunkReader<R>,
+        chunk_frames: u32,
+    ) -> Result<SignalStats, VisqolError> {
+        let mut sum = 0.0;
+        let mut num_clipped = 0.0;
+        let mut is_silent = true;
+        let mut start = 0;
+        while start < reader.num_frames() {
+            let num_frames = chunk_frames.min(reader.num_frames() - start);
+            let mut chunk = audio_utils::read_mono_chunk(reader, start, num_frames, &self.downmix)?;
+            self.handle_non_finite_samples(&mut chunk).map_err(|error| match error {
+                VisqolError::NonFiniteSamples { index } => VisqolError::NonFiniteSamples {
+                    index: start as usize + index,
+                },
+                error => error,
+            })?;
+            sum += audio_utils::dc_offset(&chunk) * chunk.len() as f64;
+            num_clipped += audio_utils::clipped_fraction(&chunk) * chunk.len() as f64;
+            is_silent &= audio_utils::is_silent(&chunk);
+            start += num_frames;
+        }
+
+        let num_frames = reader.num_frames().max(1) as f64;
+        Ok(SignalStats {
+            dc_offset: sum / num_frames,
+            clipped_fraction: num_clipped / num_frames,
+            is_silent,
+        })
+    }
+}
+
+impl DynamicVisqolManager {
+    /// Creates a new instance whose variant and band count match the sample rate of the reference signal in `ref_signal_path`, see `Variant::for_sample_rate`.
+    /// Only the header of the reference is read, see `audio_utils::probe`.
+    /// `model_path` is only used if the reference is sampled at 48 kHz, if it is `None` the embedded model is used. Unsupported sample rates are rejected with `VisqolError::UnsupportedSampleRate`.
+    pub fn auto(
+        ref_signal_path: &str,
+        model_path: Option<&str>,
+        window_size: usize,
+    ) -> Result<Self, VisqolError> {
+        let sample_rate = audio_utils::probe(ref_signal_path)?.sample_rate;
+        let variant = Variant::for_sample_rate(sample_rate, model_path)?;
+
+        Self::builder()
+            .variant(variant)
+            .search_window(window_size)
+            .try_build()
+    }
+}
+
+/// Expands the glob patterns `ref_pattern` and `deg_pattern` and pairs their matches in sorted order, see `VisqolManager::run_glob`.
+/// Returns `VisqolError::InvalidGlobPattern` if a pattern is malformed and `VisqolError::GlobCountMismatch` if the patterns match different numbers of paths.
+#[cfg(feature = "glob")]
+pub fn glob_pairs(
+    ref_pattern: &str,
+    deg_pattern: &str,
+) -> Result<Vec<(String, String)>, VisqolError> {
+    let expand = |pattern: &str| -> Result<Vec<String>, VisqolError> {
+        let matches = glob::glob(pattern).map_err(|error| VisqolError::InvalidGlobPattern {
+            pattern: pattern.to_string(),
+            reason: error.to_string(),
+        })?;
+        let mut paths = Vec::new();
+        for path in matches {
+            let path = path.map_err(std::io::Error::from)?;
+            paths.push(path.to_string_lossy().into_owned());
+        }
+        paths.sort();
+        Ok(paths)
+    };
+    let (ref_paths, deg_paths) = (expand(ref_pattern)?, expand(deg_pattern)?);
+    if ref_paths.len() != deg_paths.len() {
+        return Err(VisqolError::GlobCountMismatch {
+            reference: ref_paths.len(),
+            degraded: deg_paths.len(),
+        });
+    }
+    Ok(ref_paths.into_iter().zip(deg_paths).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_abs_diff_eq;
+
+    fn wideband() -> super::Variant {
+        super::Variant::Wideband {
+            use_unscaled_mos_mapping: false,
+        }
+    }
+
+    /// Returns the builder of the wideband speech manager shared by most tests.
+    fn speech_builder() -> super::VisqolManagerBuilder<{ crate::constants::NUM_BANDS_SPEECH }> {
+        super::VisqolManager::builder().variant(wideband()).search_window(60)
+    }
+
+    /// Returns `seconds` of a harmonic tone at 16 kHz, with a syllable-like envelope and uniform noise
+    /// of amplitude `noise`. It is much shorter than the speech test files, for tests which check
+    /// plumbing rather than the MOS value.
+    fn synthetic_signal(seconds: f64, gain: f64, noise: f64) -> Vec<f64> {
+        use std::f64::consts::PI;
+        let mut state = 0x853c_49e6_748f_ea9b_u64;
+        (0..(seconds * 16000.0) as usize)
+            .map(|n| {
+                let time = n as f64 / 16000.0;
+                let tone = (1..=8)
+                    .map(|harmonic| {
+                        let harmonic = harmonic as f64;
+                        (2.0 * PI * 140.0 * harmonic * time).sin() / harmonic
+                    })
+                    .sum::<f64>();
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let uniform = (state >> 11) as f64 / (1u64 << 53) as f64;
+                gain * 0.3 * (3.0 * PI * time).sin().abs() * tone + noise * (2.0 * uniform - 1.0)
+            })
+            .collect()
+    }
+
+    /// Returns a synthetic reference and an attenuated, noisy copy as degraded signal.
+    fn synthetic_pair(seconds: f64) -> (Vec<f64>, Vec<f64>) {
+        (synthetic_signal(seconds, 1.0, 0.0), synthetic_signal(seconds, 0.9, 0.05))
+    }
+
+    /// The signals of `synthetic_pair` saved as wav files, which are removed on drop.
+    struct SyntheticFiles {
+        ref_path: String,
+        deg_path: String,
+    }
+
+    impl SyntheticFiles {
+        fn new(seconds: f64) -> SyntheticFiles {
+            let (reference, degraded) = synthetic_pair(seconds);
+            let save = |samples: Vec<f64>, file_name: &str| {
+                let path = crate::test_utility::temp_path(file_name);
+                let path = path.to_str().unwrap().to_string();
+                crate::audio_utils::save_wav(&super::AudioSignal::new(samples, 16000), &path)
+                    .unwrap();
+                path
+            };
+            SyntheticFiles {
+                ref_path: save(reference, "visqol_synthetic_reference.wav"),
+                deg_path: save(degraded, "visqol_synthetic_degraded.wav"),
+            }
+        }
+    }
+
+    impl Drop for SyntheticFiles {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.ref_path);
+            let _ = std::fs::remove_file(&self.deg_path);
+        }
+    }
+
+    #[test]
+    fn visqol_returns_expected_mos() {
+        use super::*;
+        use crate::constants;
+        let vm = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::new(
+            Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            },
+            60,
+        );
+
+        let res = vm
+            .run(
+                "test_data/clean_speech/reference_signal_16k.wav",
+                "test_data/clean_speech/degraded_signal_16k.wav",
+            )
+            .unwrap();
+        assert_abs_diff_eq!(res.moslqo, 2.35, epsilon = 0.01);
+    }
+
+    #[test]
+    fn segments_are_scored_separately() {
+        use super::*;
+        let ref_path = "test_data/clean_speech/reference_signal_16k.wav";
+        let deg_path = "test_data/clean_speech/degraded_signal_16k.wav";
+        let vm = DynamicVisqolManager::new(
+            Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            },
+            60,
+        );
+
+        let segments = vm.run_segmented(ref_path, deg_path, 1.0).unwrap();
+        let ranges = segments.iter().map(|(range, _)| *range).collect::<Vec<TimeRange>>();
+        assert_eq!(ranges, vec![TimeRange::new(0.0, 1.0), TimeRange::new(1.0, 2.7384375)]);
+        assert_ne!(segments[0].1.moslqo, segments[1].1.moslqo);
+        let (_, last_segment) = &segments[1];
+        assert!(last_segment
+            .vnsim_over_time()
+            .iter()
+            .all(|&(time, _)| (1.0..2.7384375).contains(&time)));
+        assert!(matches!(
+            vm.run_segmented(ref_path, deg_path, 0.5),
+            Err(VisqolError::SignalTooShort { .. })
+        ));
+        assert!(matches!(
+            vm.run_segmented(ref_path, deg_path, 0.0),
+            Err(VisqolError::InvalidSegmentDuration { .. })
+        ));
+    }
+
+    #[test]
+    fn time_range_is_cropped_and_clamped() {
+        use super::*;
+        let files = SyntheticFiles::new(2.0);
+        let (ref_path, deg_path) = (files.ref_path.as_str(), files.deg_path.as_str());
+        let vm = speech_builder().build();
+
+        let whole_file = vm.run(ref_path, deg_path).unwrap();
+        let clamped = vm.run_range(ref_path, deg_path, TimeRange::new(-1.0, 10.0)).unwrap();
+        let cropped = vm.run_range(ref_path, deg_path, TimeRange::new(1.0, 2.0)).unwrap();
+        assert_eq!(clamped.moslqo, whole_file.moslqo);
+        assert_ne!(cropped.moslqo, whole_file.moslqo);
+        assert_abs_diff_eq!(cropped.moslqo, whole_file.moslqo, epsilon = 0.1);
+        assert!(cropped.num_reference_patches < whole_file.num_reference_patches);
+        assert!(matches!(
+            vm.run_range(ref_path, deg_path, TimeRange::new(5.0, 6.0)),
+            Err(VisqolError::InvalidTimeRange { .. })
+        ));
+    }
+
+    #[test]
+    fn reference_is_reused_across_degraded_files() {
+        use super::*;
+        let files = SyntheticFiles::new(1.0);
+        let (ref_path, deg_path) = (files.ref_path.as_str(), files.deg_path.as_str());
+        let missing_path = "test_data/does_not_exist.wav";
+        let vm = speech_builder().build();
+        let deg_paths = [deg_path, missing_path, ref_path].map(String::from);
+
+        let results = vm.run_against_many(ref_path, &deg_paths).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].as_ref().unwrap().moslqo,
+            vm.run(ref_path, deg_path).unwrap().moslqo
+        );
+        assert!(matches!(results[1], Err(VisqolError::FileNotFound { .. })));
+        assert_eq!(
+            results[2].as_ref().unwrap().moslqo,
+            vm.run(ref_path, ref_path).unwrap().moslqo
+        );
+        assert!(matches!(
+            vm.run_against_many(missing_path, &deg_paths),
+            Err(VisqolError::FileNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn narrowband_scores_8khz_speech() {
+        use super::*;
+        let load_8khz = |path: &str| {
+            let signal = audio_utils::load_as_mono(path).unwrap();
+            audio_utils::resample(&signal, 8000).unwrap()
+        };
+        let ref_signal = load_8khz("test_data/clean_speech/reference_signal_16k.wav");
+        let deg_signal = load_8khz("test_data/clean_speech/degraded_signal_16k.wav");
+        let vm = DynamicVisqolManager::new(
+            Variant::Narrowband {
+                use_unscaled_mos_mapping: false,
+            },
+            60,
+        );
+
+        let result = vm
+            .run_from_samples(
+                ref_signal.data_matrix.as_slice().unwrap(),
+                deg_signal.data_matrix.as_slice().unwrap(),
+                8000,
+            )
+            .unwrap();
+        assert_eq!(vm.num_bands(), constants::NUM_BANDS_NARROWBAND);
+        assert_eq!(result.center_freq_bands.len(), constants::NUM_BANDS_NARROWBAND);
+        assert!(result.center_freq_bands.iter().all(|&freq| freq <= 4000.0));
+        assert!(result.moslqo > 1.0 && result.moslqo < 5.0);
+    }
+
+    #[test]
+    fn chunked_scoring_matches_whole_file_scoring() {
+        use super::*;
+        let ref_path = "test_data/conformance_testdata_subset/contrabassoon48_stereo.wav";
+        let deg_path =
+            "test_data/conformance_testdata_subset/contrabassoon48_stereo_24kbps_aac.wav";
+        let vm = DynamicVisqolManager::new(Variant::Fullband { model_path: None }, 60);
+
+        let whole_file = vm.run(ref_path, deg_path).unwrap();
+        let chunked = vm.run_chunked(ref_path, deg_path, 6.0, 2.0).unwrap();
+        assert_abs_diff_eq!(chunked.moslqo, whole_file.moslqo, epsilon = 0.01);
+        assert_eq!(chunked.patch_sims.len(), whole_file.patch_sims.len());
+        assert!(matches!(
+            vm.run_chunked(ref_path, deg_path, 0.0, 2.0),
+            Err(VisqolError::InvalidChunkSize { .. })
+        ));
+    }
+
+    #[test]
+    fn progress_is_reported_without_affecting_score() {
+        use std::sync::Mutex;
+        let files = SyntheticFiles::new(1.0);
+        let (ref_path, deg_path) = (files.ref_path.as_str(), files.deg_path.as_str());
+        let vm = speech_builder().build();
+
+        let mut reported = Vec::new();
+        let result = vm
+            .run_with_progress(ref_path, deg_path, |fraction| reported.push(fraction))
+            .unwrap();
+        assert_eq!(result.moslqo, vm.run(ref_path, deg_path).unwrap().moslqo);
+        assert_eq!(reported.first(), Some(&0.0));
+        assert_eq!(reported.last(), Some(&1.0));
+        assert!(reported.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        let pairs = vec![
+            (ref_path.to_string(), deg_path.to_string()),
+            (ref_path.to_string(), "does_not_exist.wav".to_string()),
+        ];
+        let scored = Mutex::new(Vec::new());
+        let results = vm.run_batch_with_progress(&pairs, |index, result| {
+            scored.lock().unwrap().push((index, result.is_ok()))
+        });
+        let mut scored = scored.into_inner().unwrap();
+        scored.sort();
+        assert_eq!(results.len(), 2);
+        assert_eq!(scored, vec![(0, true), (1, false)]);
+    }
+
+    #[test]
+    fn identical_signals_skip_comparison() {
+        let samples = synthetic_signal(1.0, 1.0, 0.0);
+        let mut near_identical = samples.clone();
+        near_identical[1000] += 1e-6;
+        let vm = speech_builder().build();
+        let full_pipeline = speech_builder().identical_signal_fast_path(false).build();
+
+        let identical = vm.run_from_samples(&samples, &samples, 16000).unwrap();
+        assert_eq!(identical.moslqo, 5.0);
+        assert_eq!(identical.vnsim, 1.0);
+        assert!(identical.patch_sims.is_empty());
+        assert_eq!(identical.center_freq_bands.len(), vm.num_bands());
+
+        let near_identical = vm.run_from_samples(&samples, &near_identical, 16000).unwrap();
+        let compared = full_pipeline.run_from_samples(&samples, &samples, 16000).unwrap();
+        assert!(!near_identical.patch_sims.is_empty());
+        assert!(!compared.patch_sims.is_empty());
+        assert_eq!(compared.center_freq_bands, identical.center_freq_bands);
+    }
+
+    #[test]
+    fn samples_and_files_produce_identical_mos() {
+        use super::*;
+        let files = SyntheticFiles::new(1.0);
+        let (ref_path, deg_path) = (files.ref_path.as_str(), files.deg_path.as_str());
+        let vm = speech_builder().build();
+
+        let ref_signal = audio_utils::load_as_mono(ref_path).unwrap();
+        let deg_signal = audio_utils::load_as_mono(deg_path).unwrap();
+
+        let from_files = vm.run(ref_path, deg_path).unwrap();
+        let from_samples = vm
+            .run_from_samples(
+                ref_signal.data_matrix.as_slice().unwrap(),
+                deg_signal.data_matrix.as_slice().unwrap(),
+                ref_signal.sample_rate,
+            )
+            .unwrap();
+        assert_eq!(from_files.moslqo, from_samples.moslqo);
+    }
+
+    #[test]
+    fn buffers_and_files_produce_identical_mos() {
+        use super::*;
+        use std::io::Cursor;
+        let files = SyntheticFiles::new(1.0);
+        let (ref_path, deg_path) = (files.ref_path.as_str(), files.deg_path.as_str());
+        let vm = speech_builder().build();
+
+        let from_files = vm.run(ref_path, deg_path).unwrap();
+        let from_buffers = vm
+            .run_from_readers(
+                Cursor::new(std::fs::read(ref_path).unwrap()),
+                FormatHint::Wav,
+                Cursor::new(std::fs::read(deg_path).unwrap()),
+                FormatHint::Detect,
+            )
+            .unwrap();
+        assert_eq!(from_files.moslqo, from_buffers.moslqo);
+    }
+
+    #[test]
+    #[cfg(any(feature = "mp3", feature = "symphonia"))]
+    fn mp3_with_different_sample_rate_is_rejected() {
+        use super::*;
+        use crate::constants;
+        let vm = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::new(
+            Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            },
+            60,
+        );
+
+        let error = vm
+            .run(
+                "test_data/clean_speech/reference_signal_16k.wav",
+                "test_data/CA01_01.mp3",
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VisqolError::DifferentSampleRates {
+                reference: 16000,
+                degraded: 48000
+            }
+        ));
+    }
+
+    #[test]
+    fn degraded_signal_is_resampled_to_match_reference() {
+        use super::*;
+        use crate::constants;
+        let mut vm = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::new(
+            Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            },
+            60,
+        );
+        let ref_path = "test_data/clean_speech/reference_signal_16k.wav";
+
+        let error = vm
+            .run(ref_path, "test_data/clean_speech/degraded_signal.wav")
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VisqolError::DifferentSampleRates {
+                reference: 16000,
+                degraded: 48000
+            }
+        ));
+
+        vm.set_resample_to_match(true);
+        let res = vm
+            .run(ref_path, "test_data/clean_speech/degraded_signal.wav")
+            .unwrap();
+        assert_abs_diff_eq!(res.moslqo, 2.35, epsilon = 0.1);
+    }
+
+    #[test]
+    fn each_channel_is_scored_separately() {
+        use super::*;
+        use crate::constants;
+        let vm = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::new(
+            Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            },
+            60,
+        );
+
+        // The left channel holds the degraded signal, the right channel a copy of the reference.
+        let results = vm
+            .run_multichannel(
+                "test_data/clean_speech/reference_signal_16k_stereo.wav",
+                "test_data/clean_speech/degraded_signal_16k_stereo.wav",
+            )
+            .unwrap();
+        let mono_result = vm
+            .run(
+                "test_data/clean_speech/reference_signal_16k.wav",
+                "test_data/clean_speech/degraded_signal_16k.wav",
+            )
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].moslqo, mono_result.moslqo);
+        assert!(results[1].moslqo > results[0].moslqo);
+    }
+
+    #[test]
+    fn different_channel_counts_are_rejected() {
+        use super::*;
+        use crate::constants;
+        let vm = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::new(
+            Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            },
+            60,
+        );
+
+        let error = vm
+            .run_multichannel(
+                "test_data/clean_speech/reference_signal_16k_stereo.wav",
+                "test_data/clean_speech/degraded_signal_16k.wav",
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            VisqolError::DifferentChannelCounts {
+                reference: 2,
+                degraded: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn loudness_normalization_compensates_level_difference() {
+        use super::*;
+        let mut vm = speech_builder().build();
+        let (reference, degraded) = synthetic_pair(1.0);
+        let quiet_degraded = degraded.iter().map(|x| x * 0.1).collect::<Vec<f64>>();
+
+        vm.set_loudness_normalization(Some(NormalizationTarget::EbuR128));
+        let res = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+        let quiet_res = vm.run_from_samples(&reference, &quiet_degraded, 16000).unwrap();
+
+        assert_abs_diff_eq!(res.moslqo, quiet_res.moslqo, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn trimming_preserves_offset_between_signals() {
+        use super::*;
+        let mut ref_samples = vec![0.0; 100];
+        ref_samples.extend(vec![0.5; 1000]);
+        let mut deg_samples = vec![0.0; 300];
+        deg_samples.extend(vec![0.5; 1000]);
+        deg_samples.extend(vec![0.0; 200]);
+        let mut ref_signal = AudioSignal::new(ref_samples, 16000);
+        let mut deg_signal = AudioSignal::new(deg_samples, 16000);
+
+        VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::trim_common_silence(
+            &mut ref_signal,
+            &mut deg_signal,
+            -60.0,
+        );
+
+        assert_eq!(ref_signal.len(), 1000);
+        assert_eq!(deg_signal.len(), 1200);
+        assert_eq!(deg_signal[199], 0.0);
+        assert_eq!(deg_signal[200], 0.5);
+    }
+
+    #[test]
+    fn common_silence_does_not_change_mos() {
+        let mut vm = speech_builder().build();
+        vm.set_silence_trimming(Some(-90.0));
+        let (reference, degraded) = synthetic_pair(1.0);
+        let pad = |signal: &[f64]| -> Vec<f64> {
+            let mut samples = vec![0.0; 16000];
+            samples.extend(signal);
+            samples
+        };
+
+        let res = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+        let padded_res = vm
+            .run_from_samples(&pad(&reference), &pad(&degraded), 16000)
+            .unwrap();
+
+        assert_eq!(res.moslqo, padded_res.moslqo);
+    }
+
+    #[test]
+    fn patch_scores_are_reported_per_patch() {
+        let vm = speech_builder().build();
+        let (reference, degraded) = synthetic_pair(2.0);
+
+        let res = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+
+        assert!(!res.patch_scores.is_empty());
+        assert_eq!(res.patch_scores.len(), res.patch_sims.len());
+        assert_eq!(res.patch_offsets.len(), res.patch_sims.len());
+        for (score, patch) in res.patch_scores.iter().zip(&res.patch_sims) {
+            assert_eq!(*score, patch.similarity);
+        }
+    }
+
+    #[test]
+    fn spectrograms_hold_one_row_per_band() {
+        let files = SyntheticFiles::new(1.0);
+        let vm = speech_builder().build();
+
+        let (ref_spectrogram, deg_spectrogram) =
+            vm.compute_spectrograms(&files.ref_path, &files.deg_path).unwrap();
+
+        for spectrogram in [&ref_spectrogram, &deg_spectrogram] {
+            assert_eq!(spectrogram.data.nrows(), vm.num_bands());
+            assert_eq!(spectrogram.center_freq_bands.len(), vm.num_bands());
+            assert!(spectrogram.data.iter().all(|&value| value >= 0.0));
+        }
+        assert!(ref_spectrogram.data.ncols() > 0);
+    }
+
+    #[test]
+    fn fvnsim_holds_mean_nsim_per_band() {
+        use crate::constants;
+        let vm = speech_builder().build();
+        let (reference, degraded) = synthetic_pair(2.0);
+
+        let res = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+
+        assert_eq!(res.fvnsim.len(), constants::NUM_BANDS_SPEECH);
+        assert_eq!(res.center_freq_bands.len(), constants::NUM_BANDS_SPEECH);
+        for (band, fvnsim) in res.fvnsim.iter().enumerate() {
+            let mean = res
+                .patch_sims
+                .iter()
+                .map(|patch| patch.freq_band_means[band])
+                .sum::<f64>()
+                / res.patch_sims.len() as f64;
+            assert_abs_diff_eq!(*fvnsim, mean, epsilon = 1e-12);
+        }
+    }
+
+    #[test]
+    fn batch_results_are_returned_in_input_order() {
+        let files = SyntheticFiles::new(1.0);
+        let (ref_path, deg_path) = (files.ref_path.as_str(), files.deg_path.as_str());
+        let vm = speech_builder().build();
+        let pairs = [
+            (ref_path.to_string(), deg_path.to_string()),
+            (ref_path.to_string(), "test_data/does_not_exist.wav".to_string()),
+            (ref_path.to_string(), ref_path.to_string()),
+        ];
+
+        let results = vm.run_batch(&pairs);
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].as_ref().unwrap().moslqo,
+            vm.run(ref_path, deg_path).unwrap().moslqo
+        );
+        assert!(results[1].is_err());
+        assert_eq!(
+            results[2].as_ref().unwrap().moslqo,
+            vm.run(ref_path, ref_path).unwrap().moslqo
+        );
+    }
+
+    #[test]
+    fn manager_is_shareable_across_threads() {
+        use super::*;
+        use crate::constants;
+        use std::sync::Arc;
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<VisqolManager<{ constants::NUM_BANDS_SPEECH }>>();
+        assert_send_sync::<VisqolManager<{ constants::NUM_BANDS_AUDIO }>>();
+
+        let files = Arc::new(SyntheticFiles::new(1.0));
+        let vm = Arc::new(speech_builder().build());
+        let handles = (0..2)
+            .map(|_| {
+                let vm = Arc::clone(&vm);
+                let files = Arc::clone(&files);
+                std::thread::spawn(move || {
+                    vm.run(&files.ref_path, &files.deg_path).unwrap().moslqo
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let scores = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<Vec<f64>>();
+        assert_eq!(scores[0], scores[1]);
+    }
+
+    #[test]
+    fn alignment_lag_is_reported() {
+        use super::*;
+        use crate::constants;
+        let vm = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::new(
+            Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            },
+            60,
+        );
+        let signal =
+            audio_utils::load_as_mono("test_data/clean_speech/reference_signal_16k.wav").unwrap();
+        let mut delayed_samples = vec![0.0; 1600];
+        delayed_samples.extend(signal.data_matrix.iter());
+        let reference = signal.data_matrix.as_slice().unwrap();
+
+        let aligned_res = vm.run_from_samples(reference, reference, 16000).unwrap();
+        let delayed_res = vm
+            .run_from_samples(reference, &delayed_samples, 16000)
+            .unwrap();
+        let ahead_res = vm
+            .run_from_samples(&delayed_samples, reference, 16000)
+            .unwrap();
+
+        assert_eq!(aligned_res.alignment_lag_seconds, 0.0);
+        assert_abs_diff_eq!(delayed_res.alignment_lag_seconds, -0.1, epsilon = 0.01);
+        assert_abs_diff_eq!(ahead_res.alignment_lag_seconds, 0.1, epsilon = 0.01);
+    }
+
+    #[test]
+    fn alignment_can_be_disabled() {
+        let mut vm = speech_builder().build();
+        let reference = synthetic_signal(1.0, 1.0, 0.0);
+        let mut delayed_samples = vec![0.0; 1600];
+        delayed_samples.extend(&reference);
+
+        let aligned_res = vm
+            .run_from_samples(&reference, &delayed_samples, 16000)
+            .unwrap();
+        vm.set_align(false);
+        let unaligned_res = vm
+            .run_from_samples(&reference, &delayed_samples, 16000)
+            .unwrap();
+
+        assert_eq!(unaligned_res.alignment_lag_seconds, 0.0);
+        assert!(unaligned_res.moslqo < aligned_res.moslqo);
+    }
+
+    #[test]
+    fn builder_matches_constructor() {
+        use super::*;
+        use crate::constants;
+        let (reference, degraded) = synthetic_pair(1.0);
+
+        let from_new = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::new(wideband(), 60)
+            .run_from_samples(&reference, &degraded, 16000)
+            .unwrap();
+        let from_builder = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::builder()
+            .variant(wideband())
+            .search_window(60)
+            .duration_tolerance(0.5)
+            .build()
+            .run_from_samples(&reference, &degraded, 16000)
+            .unwrap();
+        let unaligned = speech_builder()
+            .align(false)
+            .build()
+            .run_from_samples(&reference, &degraded, 16000)
+            .unwrap();
+
+        assert_eq!(from_new.moslqo, from_builder.moslqo);
+        assert_eq!(unaligned.alignment_lag_seconds, 0.0);
+    }
+
+    #[test]
+    fn band_count_can_be_chosen_at_runtime() {
+        use super::*;
+        use crate::constants;
+        let (reference, degraded) = synthetic_pair(1.0);
+
+        let generic = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::new(wideband(), 60);
+        let dynamic = DynamicVisqolManager::new(wideband(), 60);
+        let num_bands = "32".parse::<usize>().unwrap();
+        let configured = DynamicVisqolManager::builder()
+            .variant(wideband())
+            .search_window(60)
+            .num_bands(num_bands)
+            .build();
+        assert_eq!(dynamic.num_bands(), constants::NUM_BANDS_SPEECH);
+        assert_eq!(configured.num_bands(), num_bands);
+
+        let generic_res = generic.run_from_samples(&reference, &degraded, 16000).unwrap();
+        let dynamic_res = dynamic.run_from_samples(&reference, &degraded, 16000).unwrap();
+        let configured_res = configured.run_from_samples(&reference, &degraded, 16000).unwrap();
+
+        assert_eq!(generic_res.moslqo, dynamic_res.moslqo);
+        assert_eq!(configured_res.fvnsim.len(), num_bands);
+        assert_eq!(configured_res.center_freq_bands.len(), num_bands);
+    }
+
+    #[test]
+    fn custom_mapper_replaces_variant_mapper() {
+        use super::*;
+        use crate::constants;
+        struct ConstantMapper;
+        impl SimilarityToQualityMapper for ConstantMapper {
+            fn predict_quality(&self, _features: &[f64]) -> f64 { 3.0 }
+        }
+        let (reference, degraded) = synthetic_pair(1.0);
+
+        let from_builder = speech_builder()
+            .similarity_to_quality_mapper(Box::new(ConstantMapper))
+            .build()
+            .run_from_samples(&reference, &degraded, 16000)
+            .unwrap();
+        let from_constructor = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::with_mapper(
+            wideband(),
+            60,
+            Box::new(ConstantMapper),
+        )
+        .run_from_samples(&reference, &degraded, 16000)
+        .unwrap();
+
+        assert_eq!(from_builder.moslqo, 3.0);
+        assert_eq!(from_constructor.moslqo, 3.0);
+    }
+
+    #[test]
+    fn custom_mapper_skips_loading_svr_model() {
+        use super::*;
+        use crate::constants;
+        struct ConstantMapper;
+        impl SimilarityToQualityMapper for ConstantMapper {
+            fn predict_quality(&self, _features: &[f64]) -> f64 { 3.0 }
+        }
+
+        // Would panic if the model was loaded.
+        VisqolManager::<{ constants::NUM_BANDS_AUDIO }>::with_mapper(
+            Variant::Fullband {
+                model_path: Some(String::from("does_not_exist.txt")),
+            },
+            60,
+            Box::new(ConstantMapper),
+        );
+    }
+
+    #[test]
+    fn try_build_reports_invalid_model() {
+        use super::*;
+
+        let invalid_model_path = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let build = |model_path: &str| {
+            DynamicVisqolManager::builder()
+                .variant(Variant::Fullband {
+                    model_path: Some(model_path.to_string()),
+                })
+                .try_build()
+        };
+
+        assert!(matches!(
+            build("does_not_exist.txt"),
+            Err(VisqolError::FileNotFound { .. })
+        ));
+        assert!(matches!(
+            build(invalid_model_path),
+            Err(VisqolError::InvalidModel { path: Some(_), .. })
+        ));
+    }
+
+    #[test]
+    fn custom_patch_creator_replaces_variant_patch_creator() {
+        use super::*;
+        use crate::analysis_window::AnalysisWindow;
+        use crate::patch_creator::Patch;
+        use ndarray::Array2;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        /// Emits patches of `PATCH_SIZE` frames on a regular grid.
+        struct GridPatchCreator {
+            num_patches: Arc<AtomicUsize>,
+        }
+        const PATCH_SIZE: usize = 20;
+        impl PatchCreator for GridPatchCreator {
+            fn create_ref_patch_indices(
+                &self,
+                spectrogram: &Array2<f64>,
+                _ref_signal: &AudioSignal,
+                _window: &AnalysisWindow,
+            ) -> Result<Vec<usize>, VisqolError> {
+                let indices = (0..spectrogram.ncols().saturating_sub(PATCH_SIZE))
+                    .step_by(PATCH_SIZE)
+                    .collect::<Vec<usize>>();
+                self.num_patches.store(indices.len(), Ordering::SeqCst);
+                Ok(indices)
+            }
+
+            fn patches<'a>(
+                &'a self,
+                spectrogram: &'a Array2<f64>,
+                patch_indices: &'a [usize],
+            ) -> Box<dyn Iterator<Item = Patch> + 'a> {
+                Box::new(patch_indices.iter().map(move |&index| {
+                    Patch::from_spectrogram(spectrogram, index, PATCH_SIZE)
+                }))
+            }
+        }
+
+        let num_patches = Arc::new(AtomicUsize::new(0));
+        let vm = speech_builder()
+            .patch_creator(Box::new(GridPatchCreator {
+                num_patches: Arc::clone(&num_patches),
+            }))
+            .build();
+        let (reference, degraded) = synthetic_pair(2.0);
+        let res = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+
+        assert!(num_patches.load(Ordering::SeqCst) > 0);
+        assert_eq!(res.patch_sims.len(), num_patches.load(Ordering::SeqCst));
+        assert!((1.0..=5.0).contains(&res.moslqo));
+    }
+
+    #[test]
+    fn variant_is_selected_from_reference_sample_rate() {
+        use super::*;
+        use crate::constants;
+        let model_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../model/libsvm_nu_svr_model.txt");
+
+        let speech = DynamicVisqolManager::auto(
+            "test_data/clean_speech/reference_signal_16k.wav",
+            Some(model_path),
+            60,
+        )
+        .unwrap();
+        let audio = DynamicVisqolManager::auto(
+            "test_data/conformance_testdata_subset/contrabassoon48_stereo.wav",
+            Some(model_path),
+            60,
+        )
+        .unwrap();
+        assert_eq!(speech.num_bands(), constants::NUM_BANDS_SPEECH);
+        assert_eq!(audio.num_bands(), constants::NUM_BANDS_AUDIO);
+    }
+
+    #[test]
+    fn strict_duration_rejects_duration_mismatch() {
+        use super::*;
+        let reference = synthetic_signal(2.5, 1.0, 0.0);
+        let shortened = &reference[..reference.len() - 24000];
+        let mut vm = speech_builder().build();
+
+        assert!(vm.run_from_samples(&reference, shortened, 16000).is_ok());
+
+        vm.set_strict_duration(true);
+        let error = vm
+            .run_from_samples(&reference, shortened, 16000)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            VisqolError::DurationMismatch { reference, degraded }
+                if (reference - degraded - 1.5).abs() < 1e-9
+        ));
+    }
+
+    #[test]
+    fn duration_tolerance_can_be_overridden() {
+        let reference = synthetic_signal(2.5, 1.0, 0.0);
+        let shortened = &reference[..reference.len() - 24000];
+        let mut vm = speech_builder().strict_duration(true).build();
+
+        assert!(vm.run_from_samples(&reference, shortened, 16000).is_err());
+
+        vm.set_duration_tolerance(2.0);
+        assert!(vm.run_from_samples(&reference, shortened, 16000).is_ok());
+    }
+
+    #[test]
+    fn signal_shorter_than_a_patch_is_rejected() {
+        use super::*;
+        use crate::constants;
+        let ref_signal =
+            audio_utils::load_as_mono("test_data/clean_speech/reference_signal_16k.wav").unwrap();
+        let short_signal = &ref_signal.data_matrix.as_slice().unwrap()[..8000];
+        let vm = VisqolManager::<{ constants::NUM_BANDS_SPEECH }>::new(
+            Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            },
+            60,
+        );
+
+        let error = vm
+            .run_from_samples(short_signal, short_signal, 16000)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            VisqolError::SignalTooShort { duration, min_required }
+                if duration == 0.5 && min_required > duration
+        ));
+    }
+
+    #[test]
+    fn non_finite_samples_are_rejected_or_sanitized() {
+        use super::*;
+        let (reference, degraded) = synthetic_pair(1.0);
+        let mut corrupted = degraded;
+        corrupted[100] = f64::NAN;
+        corrupted[200] = f64::INFINITY;
+        let mut vm = speech_builder().build();
+
+        let error = vm
+            .run_from_samples(&reference, &corrupted, 16000)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            VisqolError::NonFiniteSamples { index: 100 }
+        ));
+
+        vm.set_sanitize_non_finite(true);
+        let res = vm.run_from_samples(&reference, &corrupted, 16000).unwrap();
+        assert!(res.moslqo.is_finite());
+    }
+
+    #[test]
+    fn subsample_alignment_reports_fractional_lag() {
+        let reference = synthetic_signal(1.0, 1.0, 0.05);
+        // Delays the reference by 5.5 samples by averaging two integer delays.
+        let delayed = (0..reference.len())
+            .map(|n| {
+                let sample = |delay: usize| n.checked_sub(delay).map_or(0.0, |i| reference[i]);
+                0.5 * (sample(5) + sample(6))
+            })
+            .collect::<Vec<f64>>();
+        let vm = speech_builder().subsample_align(true).build();
+
+        let res = vm.run_from_samples(&reference, &delayed, 16000).unwrap();
+
+        let lag_samples = res.alignment_lag_seconds * 16000.0;
+        assert_ne!(lag_samples.fract(), 0.0);
+        assert_abs_diff_eq!(lag_samples, -5.5, epsilon = 0.5);
+    }
+
+    #[test]
+    fn window_function_is_opt_in() {
+        use super::*;
+        let (reference, degraded) = synthetic_pair(1.0);
+        let mut vm = speech_builder().build();
+        let default_res = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+
+        vm.set_window_function(WindowFunction::Rectangular);
+        let rectangular_res = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+        vm.set_window_function(WindowFunction::Hann);
+        let hann_res = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+
+        assert_eq!(default_res.moslqo, rectangular_res.moslqo);
+        assert_ne!(default_res.moslqo, hann_res.moslqo);
+        assert_abs_diff_eq!(hann_res.moslqo, default_res.moslqo, epsilon = 0.1);
+    }
+
+    #[test]
+    fn fft_and_hop_size_change_spectrogram_shape() {
+        use super::*;
+        let files = SyntheticFiles::new(1.0);
+        let (ref_path, deg_path) = (files.ref_path.as_str(), files.deg_path.as_str());
+        let num_samples = audio_utils::load_as_mono(ref_path).unwrap().len();
+        let default_vm = speech_builder().align(false).build();
+        let mut vm = speech_builder().align(false).fft_size(2048).hop_size(256).build();
+
+        let (default_spectrogram, _) = default_vm.compute_spectrograms(ref_path, deg_path).unwrap();
+        let (spectrogram, _) = vm.compute_spectrograms(ref_path, deg_path).unwrap();
+        let res = vm.run(ref_path, deg_path).unwrap();
+
+        // 80 ms frames which are 20 ms apart at 16 kHz.
+        assert_eq!(default_spectrogram.data.ncols(), 1 + (num_samples - 1280) / 320);
+        assert_eq!(spectrogram.data.ncols(), 1 + (num_samples - 2048) / 256);
+        assert_eq!(spectrogram.data.nrows(), default_spectrogram.data.nrows());
+        assert!((1.0..=5.0).contains(&res.moslqo));
+
+        vm.set_fft_size(Some(1000));
+        let error = vm.run(ref_path, deg_path).err().unwrap();
+        assert!(matches!(
+            error,
+            VisqolError::InvalidFrameSizes {
+                fft_size: Some(1000),
+                hop_size: Some(256)
+            }
+        ));
+        vm.set_fft_size(Some(128));
+        assert!(vm.run(ref_path, deg_path).is_err());
+    }
+
+    #[test]
+    fn frequency_range_is_validated() {
+        use super::*;
+        let (reference, degraded) = synthetic_pair(1.0);
+        let mut vm = speech_builder().low_freq_hz(100.0).high_freq_hz(4000.0).build();
+
+        let res = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+        assert_abs_diff_eq!(res.center_freq_bands[0], 100.0, epsilon = 1e-6);
+        assert!(res.center_freq_bands[res.center_freq_bands.len() - 1] <= 4000.0);
+
+        vm.set_high_freq_hz(Some(50.0));
+        let error = vm.run_from_samples(&reference, &degraded, 16000).err().unwrap();
+        assert!(matches!(
+            error,
+            VisqolError::InvalidFrequencyRange {
+                low_freq_hz: 100.0,
+                high_freq_hz: Some(50.0)
+            }
+        ));
+    }
+
+    #[test]
+    fn results_from_too_few_patches_are_unreliable() {
+        let (reference, degraded) = synthetic_pair(1.0);
+        let mut vm = speech_builder().min_reliable_patches(1).build();
+
+        let res = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+        assert_eq!(res.num_patches, res.patch_sims.len());
+        assert!(res.is_reliable);
+
+        vm.set_min_reliable_patches(res.num_patches + 1);
+        let unreliable = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+        assert!(!unreliable.is_reliable);
+        assert_eq!(unreliable.moslqo, res.moslqo);
+        assert!(unreliable.to_string().contains("(unreliable)"));
+    }
+
+    #[test]
+    fn band_weights_are_validated_and_applied() {
+        use super::*;
+        use crate::constants;
+        let (reference, degraded) = synthetic_pair(1.0);
+        let low_band_weights = (0..constants::NUM_BANDS_SPEECH)
+            .map(|band| if band < 10 { 1.0 } else { 0.0 })
+            .collect::<Vec<f64>>();
+        let run = |builder: VisqolManagerBuilder<{ constants::NUM_BANDS_SPEECH }>| {
+            builder.build().run_from_samples(&reference, &degraded, 16000).unwrap()
+        };
+
+        let unweighted = run(speech_builder());
+        let uniform = run(speech_builder().band_weights(vec![2.0; constants::NUM_BANDS_SPEECH]));
+        let low_bands = run(speech_builder().band_weights(low_band_weights));
+
+        assert_abs_diff_eq!(uniform.moslqo, unweighted.moslqo, epsilon = 1e-9);
+        assert!((low_bands.moslqo - unweighted.moslqo).abs() > 1e-3);
+        assert_eq!(low_bands.fvnsim, unweighted.fvnsim);
+        assert!(matches!(
+            speech_builder().band_weights(vec![1.0; 3]).try_build(),
+            Err(VisqolError::InvalidBandWeights { num_weights: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn mos_is_clamped_if_enabled() {
+        use super::*;
+        use crate::constants;
+        struct ExtrapolatingMapper;
+        impl SimilarityToQualityMapper for ExtrapolatingMapper {
+            fn predict_quality(&self, _features: &[f64]) -> f64 { 5.7 }
+        }
+
+        let (reference, degraded) = synthetic_pair(1.0);
+        let run = |clamp_mos: bool| {
+            speech_builder()
+                .similarity_to_quality_mapper(Box::new(ExtrapolatingMapper))
+                .clamp_mos(clamp_mos)
+                .build()
+                .run_from_samples(&reference, &degraded, 16000)
+                .unwrap()
+        };
+
+        assert_eq!(run(false).moslqo, 5.7);
+        assert_eq!(run(true).moslqo, constants::MAX_MOS);
+    }
+
+    #[test]
+    fn weighting_is_applied_to_both_signals() {
+        use super::*;
+        let (reference, degraded) = synthetic_pair(1.0);
+        let mut vm = speech_builder().build();
+
+        let unweighted = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+        vm.set_weighting(Some(WeightingCurve::A));
+        let a_weighted = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+        vm.set_weighting(Some(WeightingCurve::C));
+        let c_weighted = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+
+        // A-weighting attenuates the harmonics of the tone relative to the broadband noise.
+        assert!(a_weighted.moslqo < unweighted.moslqo);
+        assert_ne!(c_weighted.moslqo, a_weighted.moslqo);
+        assert_abs_diff_eq!(c_weighted.moslqo, unweighted.moslqo, epsilon = 0.1);
+        assert_eq!(vm.run_from_samples(&reference, &reference, 16000).unwrap().moslqo, 5.0);
+    }
+
+    #[test]
+    fn smaller_patch_stride_compares_more_patches() {
+        use super::*;
+        use crate::constants;
+        let (reference, degraded) = synthetic_pair(1.5);
+
+        let default_stride = speech_builder()
+            .build()
+            .run_from_samples(&reference, &degraded, 16000)
+            .unwrap();
+        let half_stride = speech_builder()
+            .patch_stride(constants::PATCH_SIZE_AUDIO / 2)
+            .build()
+            .run_from_samples(&reference, &degraded, 16000)
+            .unwrap();
+
+        assert!(half_stride.num_patches > default_stride.num_patches);
+        assert!(half_stride.vnsim_over_time().len() > default_stride.vnsim_over_time().len());
+        assert!(half_stride.moslqo >= 1.0 && half_stride.moslqo <= 5.0);
+        assert!(matches!(
+            speech_builder().patch_stride(constants::PATCH_SIZE_AUDIO + 1).try_build(),
+            Err(VisqolError::InvalidPatchStride { .. })
+        ));
+        assert!(speech_builder().patch_stride(0).try_build().is_err());
+    }
+
+    #[test]
+    fn reference_patches_beyond_truncated_degraded_signal_are_counted() {
+        let reference = synthetic_signal(3.0, 1.0, 0.0);
+        let mut vm = speech_builder()
+            .vad(false)
+            .identical_signal_fast_path(false)
+            .build();
+        vm.set_align(false);
+
+        let complete = vm.run_from_samples(&reference, &reference, 16000).unwrap();
+        let truncated = vm
+            .run_from_samples(&reference, &reference[..reference.len() / 3], 16000)
+            .unwrap();
+
+        assert_eq!(complete.num_reference_patches, complete.num_patches);
+        assert_eq!(truncated.num_reference_patches, complete.num_reference_patches);
+        assert!(truncated.num_patches < truncated.num_reference_patches);
+    }
+
+    #[test]
+    fn similarity_matrix_is_only_recorded_if_enabled() {
+        let (reference, degraded) = synthetic_pair(2.0);
+
+        let default_result = speech_builder()
+            .build()
+            .run_from_samples(&reference, &degraded, 16000)
+            .unwrap();
+        let recorded = speech_builder()
+            .record_similarity_matrix(true)
+            .build()
+            .run_from_samples(&reference, &degraded, 16000)
+            .unwrap();
+
+        assert!(default_result.similarity_matrix.is_none());
+        let matrix = recorded.similarity_matrix.unwrap();
+        assert_eq!(matrix.nrows(), recorded.num_reference_patches);
+        assert_eq!(recorded.moslqo, default_result.moslqo);
+        for (row, &offset) in recorded.patch_offsets.iter().enumerate() {
+            assert!(!matrix[(row, offset)].is_nan());
+        }
+    }
+
+    #[test]
+    fn repeated_runs_give_identical_results() {
+        let files = SyntheticFiles::new(1.0);
+        let (ref_path, deg_path) = (files.ref_path.as_str(), files.deg_path.as_str());
+        let vm = speech_builder().build();
+
+        let first = vm.run(ref_path, deg_path).unwrap();
+        let second = vm.run(ref_path, deg_path).unwrap();
+        let from_new_manager = speech_builder().build().run(ref_path, deg_path).unwrap();
+        let pair = (ref_path.to_string(), deg_path.to_string());
+        let batch = vm.run_batch(&[pair.clone(), pair]);
+
+        for result in [&second, &from_new_manager]
+            .iter()
+            .copied()
+            .chain(batch.iter().map(|result| result.as_ref().unwrap()))
+        {
+            assert_eq!(result.moslqo.to_bits(), first.moslqo.to_bits());
+            assert_eq!(result.fvnsim, first.fvnsim);
+            assert_eq!(result.patch_offsets, first.patch_offsets);
+        }
+    }
+
+    #[test]
+    fn timings_are_only_recorded_if_enabled() {
+        use super::*;
+        let files = SyntheticFiles::new(1.0);
+        let (ref_path, deg_path) = (files.ref_path.as_str(), files.deg_path.as_str());
+        let mut vm = speech_builder().build();
+
+        let without_timings = vm.run(ref_path, deg_path).unwrap();
+        vm.set_record_timings(true);
+        let with_timings = vm.run(ref_path, deg_path).unwrap();
+        let chunked = vm.run_chunked(ref_path, deg_path, 1.0, 1.0).unwrap();
+
+        assert!(without_timings.timings.is_none());
+        assert_eq!(with_timings.moslqo, without_timings.moslqo);
+        for timings in [with_timings.timings.unwrap(), chunked.timings.unwrap()] {
+            assert!(timings.decode > Duration::ZERO);
+            assert!(timings.alignment > Duration::ZERO);
+            assert!(timings.spectrogram > Duration::ZERO);
+            assert!(timings.nsim > Duration::ZERO);
+        }
+        let identical = vm.run(ref_path, ref_path).unwrap();
+        assert_eq!(identical.timings.unwrap().nsim, Duration::ZERO);
+    }
+
+    #[test]
+    fn warnings_are_returned_with_result() {
+        use super::*;
+        let reference = synthetic_signal(2.5, 1.0, 0.0);
+        let shortened = &reference[..reference.len() - 24000];
+        let files = SyntheticFiles::new(1.0);
+        let ref_path = files.ref_path.as_str();
+        let vm = speech_builder().build();
+
+        let identical = vm.run_from_samples(&reference, &reference, 16000).unwrap();
+        let mismatch = vm.run_from_samples(&reference, shortened, 16000).unwrap();
+        let clamped = vm.run_range(ref_path, ref_path, TimeRange::new(0.0, 10.0)).unwrap();
+
+        assert!(identical.warnings.is_empty());
+        assert!(matches!(
+            mismatch.warnings[0],
+            VisqolWarning::DurationMismatch { reference, degraded }
+                if (reference - degraded - 1.5).abs() < 1e-9
+        ));
+        assert_eq!(clamped.warnings.len(), 2);
+        assert!(clamped.warnings.iter().all(|warning| matches!(
+            warning,
+            VisqolWarning::TimeRangeClamped { path, end, .. } if path == ref_path && *end == 10.0
+        )));
+    }
+
+    #[test]
+    fn clipping_of_degraded_signal_is_reported() {
+        use super::*;
+        use crate::constants;
+        let reference = synthetic_signal(1.0, 1.0, 0.0);
+        let clipped = reference
+            .iter()
+            .map(|sample| (sample * 20.0).clamp(-1.0, 1.0))
+            .collect::<Vec<f64>>();
+        let vm = speech_builder().build();
+
+        let result = vm.run_from_samples(&reference, &clipped, 16000).unwrap();
+
+        let expected = audio_utils::clipped_fraction(&AudioSignal::new(clipped.as_slice(), 16000));
+        assert!(expected > constants::CLIPPING_WARNING_FRACTION);
+        assert_eq!(result.warnings, vec![VisqolWarning::Clipping { fraction: expected }]);
+    }
+
+    #[test]
+    fn dc_offset_is_reported_or_removed() {
+        use super::*;
+        let (reference, degraded) = synthetic_pair(1.0);
+        let biased = degraded.iter().map(|sample| sample + 0.05).collect::<Vec<f64>>();
+        let mut vm = speech_builder().build();
+
+        let unbiased = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+        let warned = vm.run_from_samples(&reference, &biased, 16000).unwrap();
+        vm.set_remove_dc_offset(true);
+        let removed = vm.run_from_samples(&reference, &biased, 16000).unwrap();
+
+        let offset = audio_utils::dc_offset(&AudioSignal::new(degraded, 16000)) + 0.05;
+        assert!(matches!(
+            warned.warnings[..],
+            [VisqolWarning::DcOffset { degraded, .. }] if (degraded - offset).abs() < 1e-9
+        ));
+        assert!(unbiased.warnings.is_empty());
+        assert!(removed.warnings.is_empty());
+        assert_abs_diff_eq!(removed.moslqo, unbiased.moslqo, epsilon = 0.01);
+        assert!((warned.moslqo - unbiased.moslqo).abs() > (removed.moslqo - unbiased.moslqo).abs());
+    }
+
+    #[test]
+    fn empty_signals_are_rejected() {
+        use super::*;
+        let ref_path = "test_data/clean_speech/reference_signal_16k.wav";
+        let empty_path = crate::test_utility::temp_path("visqol_empty_signal.wav");
+        let empty_path = empty_path.to_str().unwrap();
+        audio_utils::save_wav(&AudioSignal::new([0.0; 0], 16000), empty_path).unwrap();
+        let vm = DynamicVisqolManager::builder().search_window(60).build();
+
+        let empty_signal = |result: Result<SimilarityResult, VisqolError>| match result {
+            Err(VisqolError::EmptySignal { which }) => Some(which),
+            _ => None,
+        };
+        assert_eq!(empty_signal(vm.run(ref_path, empty_path)), Some(SignalRole::Degraded));
+        assert_eq!(empty_signal(vm.run(empty_path, ref_path)), Some(SignalRole::Reference));
+        assert_eq!(
+            empty_signal(vm.run_chunked(ref_path, empty_path, 2.0, 0.5)),
+            Some(SignalRole::Degraded)
+        );
+        assert_eq!(
+            empty_signal(vm.run_from_samples(&[], &[], 16000)),
+            Some(SignalRole::Reference)
+        );
+    }
+
+    #[test]
+    fn silent_signals_give_finite_scores() {
+        use super::*;
+        let reference = synthetic_signal(1.0, 1.0, 0.0);
+        let silence = vec![0.0; reference.len()];
+        let vm = speech_builder().identical_signal_fast_path(false).build();
+
+        let both_silent = vm.run_from_samples(&silence, &silence, 16000).unwrap();
+        let deg_silent = vm.run_from_samples(&reference, &silence, 16000).unwrap();
+
+        assert_eq!(both_silent.moslqo, 5.0);
+        assert!(!both_silent.is_reliable);
+        assert_eq!(
+            both_silent.warnings,
+            vec![VisqolWarning::Silence {
+                reference: true,
+                degraded: true
+            }]
+        );
+        assert!(deg_silent.moslqo.is_finite() && deg_silent.vnsim.is_finite());
+        assert_eq!(
+            deg_silent.warnings,
+            vec![VisqolWarning::Silence {
+                reference: false,
+                degraded: true
+            }]
+        );
+    }
+
+    #[test]
+    fn leading_silence_is_skipped_for_alignment() {
+        let (reference, degraded) = synthetic_pair(1.0);
+        let mut delayed = vec![0.0; 8000];
+        delayed.extend(&degraded);
+        let limited_builder = || speech_builder().max_alignment_lag_seconds(0.1);
+
+        let expected = speech_builder()
+            .build()
+            .run_from_samples(&reference, &degraded, 16000)
+            .unwrap();
+        let limited = limited_builder()
+            .build()
+            .run_from_samples(&reference, &delayed, 16000)
+            .unwrap();
+        let skipped = limited_builder()
+            .alignment_silence_skipping(-70.0)
+            .build()
+            .run_from_samples(&reference, &delayed, 16000)
+            .unwrap();
+
+        assert!(limited.alignment_lag_seconds.abs() <= 0.1);
+        assert_abs_diff_eq!(
+            skipped.alignment_lag_seconds,
+            expected.alignment_lag_seconds - 0.5,
+            epsilon = 1e-3
+        );
+        assert_abs_diff_eq!(skipped.moslqo, expected.moslqo, epsilon = 0.05);
+    }
+
+    #[test]
+    fn sample_rate_must_match_variant() {
+        use super::*;
+        let ref_path = "test_data/conformance_testdata_subset/contrabassoon48_stereo.wav";
+        let deg_path = "test_data/conformance_testdata_subset/contrabassoon48_stereo_24kbps_aac.wav";
+        let vm = DynamicVisqolManager::builder()
+            .variant(Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            })
+            .search_window(60)
+            .build();
+
+        fn is_mismatch<T>(result: Result<T, VisqolError>) -> bool {
+            matches!(
+                result,
+                Err(VisqolError::SampleRateVariantMismatch {
+                    expected: 16000,
+                    actual: 48000
+                })
+            )
+        }
+        let mut silence = AudioSignal::new(vec![0.0; 48000], 48000);
+
+        assert!(is_mismatch(vm.run(ref_path, deg_path)));
+        assert!(is_mismatch(vm.run_segmented(ref_path, deg_path, 5.0)));
+        assert!(is_mismatch(vm.run_chunked(ref_path, deg_path, 5.0, 1.0)));
+        assert!(is_mismatch(vm.compute_results(&mut silence.clone(), &mut silence)));
+    }
+
+    #[test]
+    fn clip_is_scored_against_matching_subregion() {
+        let (clip_reference, degraded) = synthetic_pair(1.0);
+        // Precedes the clip with a second of noise, which does not match the degraded signal.
+        let mut reference = synthetic_signal(1.0, 0.0, 0.3);
+        reference.extend(&clip_reference);
+        let vm = speech_builder().find_best_subregion(true).build();
+
+        let expected = vm.run_from_samples(&clip_reference, &degraded, 16000).unwrap();
+        let clip = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+
+        let range = clip.matched_reference_range.unwrap();
+        assert_abs_diff_eq!(range.start, 1.0, epsilon = 0.01);
+        assert_abs_diff_eq!(range.duration(), 1.0, epsilon = 1e-9);
+        assert_eq!(expected.matched_reference_range, None);
+        assert!(clip.warnings.is_empty());
+        assert_abs_diff_eq!(clip.moslqo, expected.moslqo, epsilon = 0.05);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn signals_are_scored_asynchronously() {
+        use super::*;
+        use std::sync::Arc;
+        let vm = Arc::new(speech_builder().build());
+        let (reference, degraded) = synthetic_pair(1.0);
+
+        let expected = vm.run_from_samples(&reference, &degraded, 16000).unwrap();
+        let result = vm.clone().run_async(reference, degraded, 16000).await.unwrap();
+
+        assert_eq!(result.moslqo, expected.moslqo);
+        assert!(matches!(
+            vm.run_async(Vec::new(), Vec::new(), 16000).await,
+            Err(VisqolError::EmptySignal { .. })
+        ));
+    }
+
+    #[test]
+    fn directories_are_paired_by_file_name() {
+        use super::*;
+        let root = crate::test_utility::temp_path("visqol_run_directory");
+        let (ref_dir, deg_dir) = (root.join("reference"), root.join("degraded"));
+        for dir in [&ref_dir, &deg_dir] {
+            std::fs::create_dir_all(dir.join("subdirectory")).unwrap();
+        }
+        let copy = |source: &str, dir: &Path, name: &str| {
+            std::fs::copy(format!("test_data/clean_speech/{}", source), dir.join(name)).unwrap();
+        };
+        copy("reference_signal_16k.wav", &ref_dir, "paired.wav");
+        copy("degraded_signal_16k.wav", &deg_dir, "paired.wav");
+        copy("reference_signal_16k.wav", &ref_dir, "reference_only.wav");
+        copy("degraded_signal_16k.wav", &deg_dir, "degraded_only.wav");
+        let vm = DynamicVisqolManager::builder().search_window(60).build();
+
+        let results = vm
+            .run_directory(ref_dir.to_str().unwrap(), deg_dir.to_str().unwrap())
+            .unwrap();
+
+        let names = results.iter().map(|(name, _)| name.as_str()).collect::<Vec<&str>>();
+        assert_eq!(names, ["degraded_only.wav", "paired.wav", "reference_only.wav"]);
+        let missing_path = |result: &Result<SimilarityResult, VisqolError>| match result {
+            Err(VisqolError::FileNotFound { path }) => Some(path.clone()),
+            _ => None,
+        };
+        let expected_path = |dir: &Path, name: &str| dir.join(name).to_str().map(str::to_string);
+        assert_eq!(missing_path(&results[0].1), expected_path(&ref_dir, "degraded_only.wav"));
+        assert_abs_diff_eq!(results[1].1.as_ref().unwrap().moslqo, 2.35, epsilon = 0.01);
+        assert_eq!(missing_path(&results[2].1), expected_path(&deg_dir, "reference_only.wav"));
+        assert!(matches!(
+            vm.run_directory("test_data/does_not_exist", deg_dir.to_str().unwrap()),
+            Err(VisqolError::Io(_))
+        ));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(feature = "glob")]
+    #[test]
+    fn glob_matches_are_paired_in_sorted_order() {
+        use super::*;
+        let vm = DynamicVisqolManager::builder().search_window(60).build();
+
+        let pairs = glob_pairs(
+            "test_data/clean_speech/reference_signal_16k*.wav",
+            "test_data/clean_speech/degraded_signal_16k*.wav",
+        )
+        .unwrap();
+        let results = vm
+            .run_glob(
+                "test_data/clean_speech/reference_signal_16k.wav",
+                "test_data/clean_speech/degraded_signal_16k.wav",
+            )
+            .unwrap();
+
+        assert_eq!(
+            pairs,
+            [
+                (
+                    "test_data/clean_speech/reference_signal_16k.wav".to_string(),
+                    "test_data/clean_speech/degraded_signal_16k.wav".to_string()
+                ),
+                (
+                    "test_data/clean_speech/reference_signal_16k_stereo.wav".to_string(),
+                    "test_data/clean_speech/degraded_signal_16k_stereo.wav".to_string()
+                ),
+            ]
+        );
+        assert_eq!(results.len(), 1);
+        assert_abs_diff_eq!(results[0].as_ref().unwrap().moslqo, 2.35, epsilon = 0.01);
+        assert!(matches!(
+            vm.run_glob("test_data/clean_speech/*.wav", "test_data/clean_speech/degraded*.wav"),
+            Err(VisqolError::GlobCountMismatch { degraded: 3, .. })
+        ));
+        assert!(matches!(
+            vm.run_glob("test_data/[", "test_data/*.wav"),
+            Err(VisqolError::InvalidGlobPattern { .. })
+        ));
+    }
+
+    #[test]
+    fn try_build_rejects_invalid_band_counts() {
+        use super::*;
+
+        let build = |variant: Variant, num_bands: usize| {
+            DynamicVisqolManager::builder()
+                .variant(variant)
+                .num_bands(num_bands)
+                .try_build()
+        };
+        let fullband = || Variant::Fullband { model_path: None };
+
+        assert!(matches!(
+            build(
+                Variant::Wideband {
+                    use_unscaled_mos_mapping: false
+                },
+                0
+            ),
+            Err(VisqolError::InvalidNumBands { num_bands: 0, .. })
+        ));
+        assert!(matches!(
+            build(fullband(), 21),
+            Err(VisqolError::InvalidNumBands { num_bands: 21, .. })
+        ));
+        assert!(build(fullband(), 32).is_ok());
+    }
+
+    #[test]
+    fn chunked_scoring_validates_whole_files_once() {
+        use super::*;
+        let ref_path = "test_data/clean_speech/reference_signal_16k.wav";
+        let short_path = crate::test_utility::temp_path("visqol_chunked_short.wav");
+        let short_path = short_path.to_str().unwrap();
+        let reference = audio_utils::load_as_mono(ref_path).unwrap();
+        let short = AudioSignal::new(
+            reference.data_matrix.slice(s![..16000]).to_vec(),
+            reference.sample_rate,
+        );
+        audio_utils::save_wav(&short, short_path).unwrap();
+        let build = || {
+            DynamicVisqolManager::builder()
+                .variant(Variant::Wideband {
+                    use_unscaled_mos_mapping: false,
+                })
+                .search_window(60)
+        };
+
+        let chunked = build().build().run_chunked(ref_path, short_path, 0.8, 0.2).unwrap();
+        let loudness = build()
+            .loudness_normalization(NormalizationTarget::Rms { target_dbfs: -20.0 })
+            .build();
+        let trimming = build().silence_trimming(-60.0).build();
+        let resampling = build().resample_to_match(true).build();
+        let subregion = build().find_best_subregion(true).build();
+        let unsupported_results = [loudness, trimming, resampling, subregion]
+            .map(|vm| vm.run_chunked(ref_path, short_path, 0.8, 0.2));
+        std::fs::remove_file(short_path).unwrap();
+
+        let is_duration_mismatch =
+            |warning: &&VisqolWarning| matches!(warning, VisqolWarning::DurationMismatch { .. });
+        assert_eq!(chunked.warnings.iter().filter(is_duration_mismatch).count(), 1);
+        assert!(chunked.num_patches > 0);
+        for result in unsupported_results {
+            assert!(matches!(
+                result,
+                Err(VisqolError::UnsupportedInChunkedMode { .. })
+            ));
+        }
     }
 }
@@ -0,0 +1,110 @@
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Conditions which do not prevent scoring but make the result less meaningful, see `SimilarityResult::warnings`.
+/// If the `logging` feature is enabled, each warning is also logged when it occurs.
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum VisqolWarning {
+    /// The durations in seconds of both signals differ by more than `VisqolManager::set_duration_tolerance`.
+    DurationMismatch { reference: f64, degraded: f64 },
+    /// The time range from `start` to `end` seconds passed to `VisqolManager::run_range` exceeds the file in `path`,
+    /// which is `duration` seconds long, and was clamped to it.
+    TimeRangeClamped {
+        path: String,
+        start: f64,
+        end: f64,
+        duration: f64,
+    },
+    /// The reference or the degraded signal is silent, as no sample reaches `constants::SILENCE_LEVEL_DB`.
+    /// If both signals are silent, the comparison is skipped and an unreliable result with the maximum MOS of the quality mapper is returned.
+    /// If only the reference is silent, the degraded signal is scaled down to its level and the score is meaningless.
+    Silence { reference: bool, degraded: bool },
+    /// `fraction` of the samples of the degraded signal are clipped at full scale, more than `constants::CLIPPING_WARNING_FRACTION`.
+    /// The fraction is measured before any resampling or normalization. Heavy clipping distorts the spectrogram in ways the MOS mapping is not trained on.
+    Clipping { fraction: f64 },
+    /// The DC offset of the reference or the degraded signal, i.e. the mean of its samples scaled from -1.0 to 1.0, exceeds `constants::DC_OFFSET_WARNING_LEVEL`.
+    /// A bias shifts the energy of the lowest band. It can be removed by `VisqolManager::set_remove_dc_offset`.
+    DcOffset { reference: f64, degraded: f64 },
+    /// `num_dropped` of `num_reference_patches` reference patches could not be compared, e.g. because the degraded signal is too short or misaligned.
+    DroppedReferencePatches {
+        num_dropped: usize,
+        num_reference_patches: usize,
+    },
+}
+
+impl fmt::Display for VisqolWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VisqolWarning::DurationMismatch { reference, degraded } => write!(
+                f,
+                "Mismatch in duration between reference and degraded signal. Reference is {} seconds. Degraded is {} seconds.",
+                reference, degraded
+            ),
+            VisqolWarning::TimeRangeClamped {
+                path,
+                start,
+                end,
+                duration,
+            } => write!(
+                f,
+                "Time range from {} to {} seconds exceeds {}, which is {} seconds long. Clamping it to {} to {} seconds.",
+                start,
+                end,
+                path,
+                duration,
+                start.max(0.0),
+                end.min(*duration)
+            ),
+            VisqolWarning::Silence { reference, degraded } => {
+                let which = match (reference, degraded) {
+                    (true, true) => "Both signals are",
+                    (true, false) => "The reference signal is",
+                    _ => "The degraded signal is",
+                };
+                write!(f, "{} silent. The score is not meaningful.", which)
+            }
+            VisqolWarning::Clipping { fraction } => write!(
+                f,
+                "{:.2}% of the samples of the degraded signal are clipped. The score of heavily clipped audio is less meaningful.",
+                fraction * 100.0
+            ),
+            VisqolWarning::DcOffset { reference, degraded } => write!(
+                f,
+                "DC offset in the signals. Reference has an offset of {:.4}, degraded has an offset of {:.4}.",
+                reference, degraded
+            ),
+            VisqolWarning::DroppedReferencePatches {
+                num_dropped,
+                num_reference_patches,
+            } => write!(
+                f,
+                "Dropped {} of {} reference patches due to the degraded file being misaligned or too short. If too many patches are dropped, the score will be less meaningful.",
+                num_dropped, num_reference_patches
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamped_time_range_is_displayed() {
+        let warning = VisqolWarning::TimeRangeClamped {
+            path: "signal.wav".to_string(),
+            start: -1.0,
+            end: 10.0,
+            duration: 3.5,
+        };
+
+        assert_eq!(
+            warning.to_string(),
+            "Time range from -1 to 10 seconds exceeds signal.wav, which is 3.5 seconds long. Clamping it to 0 to 3.5 seconds."
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::io::Cursor;
+
+use wasm_bindgen::prelude::*;
+
+use crate::audio_utils::{self, FormatHint};
+use crate::constants::DEFAULT_WINDOW_SIZE;
+use crate::variant::Variant;
+use crate::visqol_error::VisqolError;
+use crate::visqol_manager::DynamicVisqolManager;
+
+/// Scores the `degraded` samples against the `reference` samples, both mono and sampled at `sample_rate`, and returns the MOS.
+/// The variant is selected by `sample_rate`, see `score_samples`.
+#[wasm_bindgen]
+pub fn score(reference: &[f32], degraded: &[f32], sample_rate: u32) -> Result<f64, JsError> {
+    let reference = reference.iter().copied().map(f64::from).collect::<Vec<f64>>();
+    let degraded = degraded.iter().copied().map(f64::from).collect::<Vec<f64>>();
+
+    Ok(score_samples(&reference, &degraded, sample_rate)?)
+}
+
+/// Decodes the contents of the `reference` and `degraded` files, e.g. read from a `File` in the browser, and returns the MOS.
+/// The format of each file is detected from its contents. Both files must be sampled at the same rate, see `score_samples`.
+#[wasm_bindgen(js_name = scoreEncoded)]
+pub fn score_encoded(reference: &[u8], degraded: &[u8]) -> Result<f64, JsError> {
+    let ref_signal =
+        audio_utils::load_as_mono_from_reader(Cursor::new(reference), FormatHint::Detect)?;
+    let deg_signal =
+        audio_utils::load_as_mono_from_reader(Cursor::new(degraded), FormatHint::Detect)?;
+    if ref_signal.sample_rate != deg_signal.sample_rate {
+        return Err(VisqolError::DifferentSampleRates {
+            reference: ref_signal.sample_rate,
+            degraded: deg_signal.sample_rate,
+        }
+        .into());
+    }
+
+    Ok(score_samples(
+        ref_signal.data_matrix.as_slice().unwrap(),
+        deg_signal.data_matrix.as_slice().unwrap(),
+        ref_signal.sample_rate,
+    )?)
+}
+
+/// Scores the mono signals `reference` and `degraded` using `Variant::Wideband` for 16 kHz speech, `Variant::Narrowband` for 8 kHz speech or `Variant::Fullband` with the embedded model for 48 kHz audio.
+/// Other sample rates are rejected with `VisqolError::UnsupportedSampleRate`.
+fn score_samples(
+    reference: &[f64],
+    degraded: &[f64],
+    sample_rate: u32,
+) -> Result<f64, VisqolError> {
+    let variant = Variant::for_sample_rate(sample_rate, None)?;
+    let manager = DynamicVisqolManager::new(variant, DEFAULT_WINDOW_SIZE);
+
+    Ok(manager.run_from_samples(reference, degraded, sample_rate)?.moslqo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    #[test]
+    fn speech_and_audio_sample_rates_are_supported() {
+        let load = |path: &str| audio_utils::load_as_mono(path).unwrap().data_matrix.to_vec();
+        let ref_speech = load("test_data/clean_speech/reference_signal_16k.wav");
+        let deg_speech = load("test_data/clean_speech/degraded_signal_16k.wav");
+        let ref_audio = load("test_data/conformance_testdata_subset/contrabassoon48_stereo.wav");
+        let deg_audio =
+            load("test_data/conformance_testdata_subset/contrabassoon48_stereo_24kbps_aac.wav");
+
+        let speech_mos = score_samples(&ref_speech, &deg_speech, 16000).unwrap();
+        let audio_mos = score_samples(&ref_audio, &deg_audio, 48000).unwrap();
+        assert_abs_diff_eq!(speech_mos, 2.35, epsilon = 0.01);
+        assert_abs_diff_eq!(audio_mos, 4.09, epsilon = 0.01);
+        assert!(matches!(
+            score_samples(&ref_speech, &deg_speech, 44100),
+            Err(VisqolError::UnsupportedSampleRate { sample_rate: 44100 })
+        ));
+    }
+}
@@ -1,6 +1,6 @@
-use crate::visqol_error;
-use hound::WavReader;
-use std::error::Error;
+use crate::{audio_utils::AudioInfo, math_utils, visqol_error::VisqolError};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use std::io::{Read, Seek};
 /// Represents the metadata and contents of a wav file.
 /// Simple wrapper around the `hound` library.
 pub struct WavFile {
@@ -8,33 +8,134 @@ pub struct WavFile {
     pub num_channels: u16,
     /// The sample rate of the wav file
     pub sample_rate: u32,
-    /// The samples in the wav file. Note that these are not scaled from -1.0 to 1.0 but its integer values.
-    pub samples: Vec<i16>,
+    /// The samples in the wav file, scaled from -1.0 to 1.0.
+    pub samples: Vec<f64>,
 }
 
 impl WavFile {
-    /// given a `file_path` to the desired wav file, the contents of the wav file are returned.
+    /// given a `reader` providing the contents of a wav file, the contents of the wav file are returned.
+    /// 16 bit and 24 bit integer as well as 32 bit float samples are supported.
     /// Any possible errors are reported by `hound`.
-    pub fn open(file_path: &str) -> Result<Self, Box<dyn Error>> {
-        let mut reader = WavReader::open(file_path)?;
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, VisqolError> {
+        let mut reader = WavReader::new(reader)?;
         let spec = reader.spec();
-
-        if spec.bits_per_sample != 16 {
-            return Err(Box::new(visqol_error::VisqolError::InvalidBitsPerSample {
-                bits_per_sample: spec.bits_per_sample,
-            }));
-        }
-
-        let samples: Vec<i32> = reader
-            .samples::<i32>()
-            .map(|x| x.expect("Failed to read samples"))
-            .collect();
-        let samples_quantized = samples.iter().map(|&e| e as i16).collect();
+        let samples = read_normalized_samples(&mut reader, usize::MAX)?;
 
         Ok(Self {
             num_channels: spec.channels,
             sample_rate: spec.sample_rate,
-            samples: samples_quantized,
+            samples,
+        })
+    }
+
+    /// Reads the format from the header of the wav file provided by `reader` without decoding any samples, see `audio_utils::probe`.
+    pub fn probe<R: Read>(reader: R) -> Result<AudioInfo, VisqolError> {
+        let reader = WavReader::new(reader)?;
+        let spec = reader.spec();
+
+        Ok(AudioInfo {
+            sample_rate: spec.sample_rate,
+            channels: spec.channels,
+            bit_depth: Some(spec.bits_per_sample),
+            duration: Some(reader.duration() as f64 / spec.sample_rate as f64),
         })
     }
 }
+
+/// Reads ranges of frames from a wav file without decoding the whole file, so that long files can be processed with bounded memory.
+pub struct WavChunkReader<R: Read + Seek> {
+    reader: WavReader<R>,
+}
+
+impl<R: Read + Seek> WavChunkReader<R> {
+    /// Reads the header of the wav file provided by `reader`. The same sample formats as in `WavFile::from_reader` are supported.
+    pub fn new(reader: R) -> Result<Self, VisqolError> {
+        let reader = WavReader::new(reader)?;
+        let spec = reader.spec();
+        match (spec.sample_format, spec.bits_per_sample) {
+            (SampleFormat::Int, 16) | (SampleFormat::Int, 24) | (SampleFormat::Float, 32) => {
+                Ok(Self { reader })
+            }
+            _ => Err(VisqolError::InvalidBitsPerSample {
+                bits_per_sample: spec.bits_per_sample,
+            }),
+        }
+    }
+
+    /// The number of channels in the wav file
+    pub fn num_channels(&self) -> u16 { self.reader.spec().channels }
+
+    /// The sample rate of the wav file
+    pub fn sample_rate(&self) -> u32 { self.reader.spec().sample_rate }
+
+    /// The number of frames, i.e. samples per channel, in the wav file
+    pub fn num_frames(&self) -> u32 { self.reader.duration() }
+
+    /// Returns the interleaved samples of up to `num_frames` frames starting at frame `start`, scaled from -1.0 to 1.0.
+    pub fn read_frames(&mut self, start: u32, num_frames: u32) -> Result<Vec<f64>, VisqolError> {
+        self.reader.seek(start)?;
+        let num_samples = num_frames as usize * self.num_channels() as usize;
+        read_normalized_samples(&mut self.reader, num_samples)
+    }
+}
+
+/// Writes the mono `samples`, scaled from -1.0 to 1.0, to a wav file at `path` as 16 bit integer or, if `float` is set, 32 bit float samples.
+/// Integer samples exceeding full scale are clipped.
+pub fn write_mono(
+    path: &str,
+    samples: &[f64],
+    sample_rate: u32,
+    float: bool,
+) -> Result<(), VisqolError> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: if float { 32 } else { 16 },
+        sample_format: if float { SampleFormat::Float } else { SampleFormat::Int },
+    };
+    let mut writer = WavWriter::create(path, spec)?;
+    for &sample in samples {
+        if float {
+            writer.write_sample(sample as f32)?;
+        } else {
+            writer.write_sample((sample.clamp(-1.0, 1.0) * 32767.0).round() as i16)?;
+        }
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Reads up to `max_samples` samples from the current position of `reader` and scales them from -1.0 to 1.0.
+fn read_normalized_samples<R: Read>(
+    reader: &mut WavReader<R>,
+    max_samples: usize,
+) -> Result<Vec<f64>, VisqolError> {
+    let spec = reader.spec();
+    let samples = match (spec.sample_format, spec.bits_per_sample) {
+        (SampleFormat::Int, 16) => {
+            let samples = reader
+                .samples::<i16>()
+                .take(max_samples)
+                .collect::<Result<Vec<i16>, _>>()?;
+            math_utils::normalize_int16_to_double(&samples)
+        }
+        (SampleFormat::Int, 24) => {
+            let samples = reader
+                .samples::<i32>()
+                .take(max_samples)
+                .collect::<Result<Vec<i32>, _>>()?;
+            math_utils::normalize_int24_to_double(&samples)
+        }
+        (SampleFormat::Float, 32) => reader
+            .samples::<f32>()
+            .take(max_samples)
+            .map(|sample| sample.map(f64::from))
+            .collect::<Result<Vec<f64>, _>>()?,
+        _ => {
+            return Err(VisqolError::InvalidBitsPerSample {
+                bits_per_sample: spec.bits_per_sample,
+            })
+        }
+    };
+    Ok(samples)
+}
@@ -0,0 +1,123 @@
+use crate::audio_utils::WeightingCurve;
+use crate::signal_filter;
+use num::complex::Complex64;
+use std::f64::consts::PI;
+
+/// Frequencies in Hz of the poles of the analog A and C weighting curves as specified by IEC 61672-1.
+const POLE_FREQUENCY_20_HZ: f64 = 20.598997;
+const POLE_FREQUENCY_108_HZ: f64 = 107.65265;
+const POLE_FREQUENCY_738_HZ: f64 = 737.86223;
+const POLE_FREQUENCY_12_KHZ: f64 = 12194.217;
+/// Frequency in Hz at which both weighting curves have a gain of 0 dB.
+const NORMALIZATION_FREQUENCY: f64 = 1000.0;
+
+/// Numerator and denominator coefficients of a second order section.
+type Section = ([f64; 3], [f64; 3]);
+
+/// Filters `samples`, sampled at `sample_rate`, with `curve` and returns the filtered samples.
+pub fn apply_weighting(samples: &[f64], curve: WeightingCurve, sample_rate: u32) -> Vec<f64> {
+    design_sections(curve, sample_rate as f64)
+        .iter()
+        .fold(samples.to_vec(), |samples, (numerator, denominator)| {
+            signal_filter::filter_signal(
+                numerator,
+                denominator,
+                &samples,
+                &mut [0.0; signal_filter::FilterResults::NUM_FILTER_CONDITIONS],
+            )
+            .filtered_signal
+        })
+}
+
+/// Maps the analog weighting curve to second order sections by the bilinear transform, scaled to a gain of 0 dB at 1 kHz.
+/// The poles are not prewarped, as the one at 12.2 kHz lies above the Nyquist frequency of speech sampled at 16 kHz.
+/// The curve is therefore matched closely at low and mid frequencies, while its roll-off is compressed towards the Nyquist frequency.
+fn design_sections(curve: WeightingCurve, sample_rate: f64) -> Vec<Section> {
+    let pole = |frequency: f64| {
+        let omega = 2.0 * PI * frequency;
+        (2.0 * sample_rate - omega) / (2.0 * sample_rate + omega)
+    };
+    // Zeros at DC of the analog curve map to z = 1, the surplus poles to zeros at z = -1.
+    let section = |zero: f64, first_pole: f64, second_pole: f64| {
+        (
+            [1.0, -2.0 * zero, zero * zero],
+            [1.0, -(first_pole + second_pole), first_pole * second_pole],
+        )
+    };
+    let low_pole = pole(POLE_FREQUENCY_20_HZ);
+    let high_pole = pole(POLE_FREQUENCY_12_KHZ);
+
+    let mut sections = match curve {
+        WeightingCurve::A => vec![
+            section(1.0, low_pole, low_pole),
+            section(1.0, high_pole, high_pole),
+            section(-1.0, pole(POLE_FREQUENCY_108_HZ), pole(POLE_FREQUENCY_738_HZ)),
+        ],
+        WeightingCurve::C => vec![
+            section(1.0, low_pole, low_pole),
+            section(-1.0, high_pole, high_pole),
+        ],
+    };
+
+    let gain = sections
+        .iter()
+        .map(|section| response(section, NORMALIZATION_FREQUENCY / sample_rate).norm())
+        .product::<f64>();
+    sections[0].0.iter_mut().for_each(|coefficient| *coefficient /= gain);
+    sections
+}
+
+/// Returns the frequency response of `section` at `normalized_frequency`, i.e. the frequency divided by the sample rate.
+fn response((numerator, denominator): &Section, normalized_frequency: f64) -> Complex64 {
+    let z = Complex64::from_polar(1.0, -2.0 * PI * normalized_frequency);
+    let evaluate = |coefficients: &[f64; 3]| {
+        coefficients[0] + coefficients[1] * z + coefficients[2] * z * z
+    };
+    evaluate(numerator) / evaluate(denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_abs_diff_eq;
+
+    /// Returns the gain in dB of `curve` at `frequency` for a sample rate of 48 kHz.
+    fn gain_db(curve: WeightingCurve, frequency: f64) -> f64 {
+        let sections = design_sections(curve, 48000.0);
+        let gain = sections
+            .iter()
+            .map(|section| response(section, frequency / 48000.0).norm())
+            .product::<f64>();
+        20.0 * gain.log10()
+    }
+
+    #[test]
+    fn curves_match_standard_gains() {
+        assert_abs_diff_eq!(gain_db(WeightingCurve::A, 1000.0), 0.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(gain_db(WeightingCurve::C, 1000.0), 0.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(gain_db(WeightingCurve::A, 100.0), -19.1, epsilon = 0.1);
+        assert_abs_diff_eq!(gain_db(WeightingCurve::A, 4000.0), 1.0, epsilon = 0.2);
+        assert_abs_diff_eq!(gain_db(WeightingCurve::C, 31.5), -3.0, epsilon = 0.1);
+        assert_abs_diff_eq!(gain_db(WeightingCurve::C, 100.0), -0.3, epsilon = 0.1);
+    }
+
+    #[test]
+    fn low_frequency_sine_is_attenuated() {
+        let sine = |frequency: f64| {
+            (0..16000)
+                .map(|index| (2.0 * PI * frequency * index as f64 / 16000.0).sin())
+                .collect::<Vec<f64>>()
+        };
+        // Skips the transient of the filters before measuring the RMS.
+        let rms = |samples: &[f64]| {
+            let steady = &samples[8000..];
+            (steady.iter().map(|sample| sample * sample).sum::<f64>() / steady.len() as f64).sqrt()
+        };
+
+        let weighted_1_khz = apply_weighting(&sine(1000.0), WeightingCurve::A, 16000);
+        let weighted_100_hz = apply_weighting(&sine(100.0), WeightingCurve::A, 16000);
+
+        assert_abs_diff_eq!(rms(&weighted_1_khz), 0.5f64.sqrt(), epsilon = 1e-3);
+        assert!(rms(&weighted_100_hz) < 0.2 * 0.5f64.sqrt());
+    }
+}
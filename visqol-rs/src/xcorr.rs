@@ -3,8 +3,48 @@ use crate::fft_manager::FftManager;
 use num::complex::Complex64;
 
 /// Calculate the maximum delay between to signals.
-pub fn calculate_best_lag(signal_1: &[f64], signal_2: &[f64]) -> Option<i64> {
-    let max_lag = ((signal_1.len().max(signal_2.len())) - 1) as i64;
+/// The cross-correlation is computed as the inverse FFT of the pointwise product of both spectra, which takes O(n log n) time for signals of length n.
+/// Only lags of at most `max_lag` samples in either direction are considered. If `max_lag` is `None`, all lags are considered.
+pub fn calculate_best_lag(
+    signal_1: &[f64],
+    signal_2: &[f64],
+    max_lag: Option<usize>,
+) -> Option<i64> {
+    let (_, best_corr_idx, max_lag) = find_correlation_peak(signal_1, signal_2, max_lag)?;
+
+    Some(best_corr_idx as i64 - max_lag)
+}
+
+/// Like `calculate_best_lag`, but the lag is refined to a fraction of a sample by fitting a parabola through the correlation peak and its two neighbours.
+pub fn calculate_best_subsample_lag(
+    signal_1: &[f64],
+    signal_2: &[f64],
+    max_lag: Option<usize>,
+) -> Option<f64> {
+    let (corrs, best_corr_idx, max_lag) = find_correlation_peak(signal_1, signal_2, max_lag)?;
+
+    let mut offset = 0.0;
+    if best_corr_idx > 0 && best_corr_idx + 1 < corrs.len() {
+        let previous = corrs[best_corr_idx - 1].abs();
+        let peak = corrs[best_corr_idx].abs();
+        let next = corrs[best_corr_idx + 1].abs();
+        let curvature = previous - 2.0 * peak + next;
+        if curvature < 0.0 {
+            offset = 0.5 * (previous - next) / curvature;
+        }
+    }
+    Some((best_corr_idx as i64 - max_lag) as f64 + offset)
+}
+
+/// Computes the cross-correlation of both signals for all lags from `-max_lag` to `max_lag`.
+/// Returns the correlations, the index of the correlation with the largest magnitude and the effective maximum lag.
+fn find_correlation_peak(
+    signal_1: &[f64],
+    signal_2: &[f64],
+    max_lag: Option<usize>,
+) -> Option<(Vec<f64>, usize, i64)> {
+    let longest_lag = (signal_1.len().max(signal_2.len())) - 1;
+    let max_lag = max_lag.map_or(longest_lag, |max_lag| max_lag.min(longest_lag)) as i64;
 
     let point_wise_fft_vec =
         calculate_inverse_fft_pointwise_product(&mut signal_1.to_vec(), &mut signal_2.to_vec());
@@ -24,7 +64,7 @@ pub fn calculate_best_lag(signal_1: &[f64], signal_2: &[f64]) -> Option<i64> {
 
     let best_corr_idx = corrs.iter().position(|&r| r == *best_corr)?;
 
-    Some(best_corr_idx as i64 - max_lag)
+    Some((corrs, best_corr_idx, max_lag))
 }
 
 /// Calculates the pointwise inverse fft product of 2 signals
@@ -108,6 +148,7 @@ mod tests {
         let best_lag = calculate_best_lag(
             ref_signal_mat.as_slice().unwrap(),
             deg_signal_lag2_mat.as_slice().unwrap(),
+            None,
         )
         .unwrap();
 
@@ -130,6 +171,7 @@ mod tests {
         let best_lag = calculate_best_lag(
             ref_signal_mat.as_slice().unwrap(),
             deg_signal_lag2_mat.as_slice().unwrap(),
+            None,
         )
         .unwrap();
 
@@ -152,6 +194,7 @@ mod tests {
         let best_lag = calculate_best_lag(
             ref_signal_mat.as_slice().unwrap(),
             deg_signal_lag2_mat.as_slice().unwrap(),
+            None,
         )
         .unwrap();
 
@@ -172,6 +215,7 @@ mod tests {
         let best_lag = calculate_best_lag(
             ref_signal_mat.as_slice().unwrap(),
             deg_signal_lag2_mat.as_slice().unwrap(),
+            None,
         )
         .unwrap();
 
@@ -179,6 +223,66 @@ mod tests {
         assert_eq!(best_lag, expected_result);
     }
 
+    #[test]
+    fn best_lag_is_limited_to_max_lag() {
+        let ref_signal = vec![0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 2.0, 0.0];
+        let deg_signal = vec![1.0, 0.0, 2.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.0];
+
+        assert_eq!(calculate_best_lag(&ref_signal, &deg_signal, None), Some(6));
+        let limited_lag = calculate_best_lag(&ref_signal, &deg_signal, Some(3)).unwrap();
+        assert!(limited_lag.abs() <= 3);
+    }
+
+    #[test]
+    fn best_lag_matches_direct_cross_correlation() {
+        let ref_signal = (0..500)
+            .map(|n| (n as f64 * 0.05).sin() + (n as f64 * 0.31).cos() * 0.5)
+            .collect::<Vec<f64>>();
+        let deg_signal = ref_signal[37..].to_vec();
+
+        let direct_best_lag = (-(deg_signal.len() as i64 - 1)..ref_signal.len() as i64)
+            .max_by(|&a, &b| {
+                direct_cross_correlation(&ref_signal, &deg_signal, a)
+                    .abs()
+                    .partial_cmp(&direct_cross_correlation(&ref_signal, &deg_signal, b).abs())
+                    .unwrap()
+            })
+            .unwrap();
+
+        assert_eq!(
+            calculate_best_lag(&ref_signal, &deg_signal, None),
+            Some(direct_best_lag)
+        );
+    }
+
+    /// Computes the cross-correlation of `signal_1` and `signal_2` at `lag` in the time domain.
+    fn direct_cross_correlation(signal_1: &[f64], signal_2: &[f64], lag: i64) -> f64 {
+        signal_2
+            .iter()
+            .enumerate()
+            .filter_map(|(n, sample)| {
+                let index = n as i64 + lag;
+                (0..signal_1.len() as i64)
+                    .contains(&index)
+                    .then(|| signal_1[index as usize] * sample)
+            })
+            .sum()
+    }
+
+    #[test]
+    fn subsample_lag_is_interpolated_between_samples() {
+        let pulse = |center: f64| {
+            (0..64)
+                .map(|n| (-((n as f64 - center) / 3.0).powi(2)).exp())
+                .collect::<Vec<f64>>()
+        };
+
+        let lag = calculate_best_subsample_lag(&pulse(20.0), &pulse(27.5), None).unwrap();
+
+        assert_eq!(calculate_best_lag(&pulse(20.0), &pulse(27.0), None), Some(-7));
+        assert!((lag + 7.5).abs() < 0.1);
+    }
+
     #[test]
     fn test_frexp() {
         let (_, result) = frexp(27.0f64);
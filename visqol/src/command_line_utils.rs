@@ -1,7 +1,8 @@
 use std::{error::Error, path::PathBuf};
 
 use crate::path_pair::PathPair;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use visqol_rs::{variant::Variant, visqol_manager};
 use csv::{ReaderBuilder, StringRecord};
 
 #[derive(Subcommand, Clone, Debug)]
@@ -21,13 +22,26 @@ pub enum Subcommands {
     Fullband {
         /// The libsvm model to use during comparison. Use this only if you
         /// want to explicitly specify the model file location, otherwise the
-        /// default model will be used.
-        #[clap(
-            long = "similarity_to_quality_model",
-            default_value = "./model/libsvm_nu_svr_model.txt"
-        )]
-        similarity_to_quality_model: String,
+        /// default model embedded in the binary will be used.
+        #[clap(long = "similarity_to_quality_model")]
+        similarity_to_quality_model: Option<String>,
     },
+    /// Evaluate narrowband telephony speech at 8 kHz sample rate, using
+    /// the same voice activity detection and MOS mapping as `wideband`.
+    Narrowband {
+        /// Perfect NSIM scores will instead result in MOS scores of ~4.x. [default: false]
+        #[clap(long = "use_unscaled_speech_mos_mapping")]
+        use_unscaled_speech_mos_mapping: bool,
+    },
+}
+
+/// Selects the variant if no subcommand is given.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Same as the `wideband` subcommand with the scaled MOS mapping.
+    Speech,
+    /// Same as the `fullband` subcommand, using the model given by `--model`.
+    Audio,
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -37,7 +51,20 @@ pub enum Subcommands {
 #[clap(arg_required_else_help = true)]
 pub struct CommandLineArgs {
     #[command(subcommand)]
-    pub subcommand: Subcommands,
+    pub subcommand: Option<Subcommands>,
+
+    /// Selects the variant if no subcommand is given: `speech` for 16 kHz
+    /// speech or `audio` for 48 kHz audio.
+    #[clap(long, value_enum, default_value_t = Mode::Speech)]
+    pub mode: Mode,
+
+    /// The libsvm model used by the `audio` mode. If omitted, the default
+    /// model embedded in the binary is used. Use the `fullband`
+    /// subcommand's `--similarity_to_quality_model` flag instead if the
+    /// subcommand is given.
+    #[clap(long)]
+    pub model: Option<String>,
+
     /// Used to specify a path to a CSV file with the format:{n}
     /// ------------------{n}
     /// reference,degraded{n}
@@ -46,26 +73,35 @@ pub struct CommandLineArgs {
     /// ------------------{n}
     /// If the `batch_input_csv` flag is used, the `reference_file`
     /// and `degraded_file` flags will be ignored.
+    /// The file pairs are scored in parallel. Unless `results_csv` is given,
+    /// the results are printed as CSV.
     #[clap(
         long = "batch_input_csv",
         name = "batch_input_csv",
+        visible_alias = "batch",
         conflicts_with = "reference_file",
         conflicts_with = "degraded_file"
     )]
     pub batch_input_csv: Option<String>,
 
-    /// The wav file path used as the reference audio.
+    /// The wav file path used as the reference audio. A glob pattern like
+    /// `ref/*.wav` scores a batch: its matches are paired in sorted order
+    /// with those of the `degraded_file` pattern, which must match as many
+    /// files.
     #[clap(
         long = "reference_file",
-        requires = "degraded-file",
+        visible_alias = "reference",
+        requires = "degraded_file",
         conflicts_with = "batch_input_csv"
     )]
     pub reference_file: Option<String>,
 
-    /// The wav file path used as the degraded audio.
+    /// The wav file path used as the degraded audio, or a glob pattern, see
+    /// `reference_file`.
     #[clap(
         long = "degraded_file",
-        requires = "reference-file",
+        visible_alias = "degraded",
+        requires = "reference_file",
         conflicts_with = "batch_input_csv"
     )]
     pub degraded_file: Option<String>,
@@ -73,12 +109,21 @@ pub struct CommandLineArgs {
     /// Used to specify a path that the similarity score results will be
     /// stored in. This will be a CSV file with the format:{n}
     /// ------------------{n}
-    /// reference,degraded,moslqo{n}
-    /// ref1.wav,deg1.wav,3.4{n}
-    /// ref2.wav,deg2.wav,4.1{n}
+    /// reference,degraded,moslqo,error{n}
+    /// ref1.wav,deg1.wav,3.4,{n}
+    /// ref2.wav,deg2.wav,4.1,{n}
+    /// ref3.wav,deg3.wav,,File "deg3.wav" does not exist!{n}
+    /// ------------------{n}
+    /// The `error` column describes why a file pair failed to be scored.
     #[clap(long = "results_csv")]
     pub results_csv: Option<String>,
 
+    /// Prints the full similarity result as JSON instead of the MOS-LQO.
+    /// For a batch, an array with one entry per file pair is printed, holding the `reference` and `degraded` paths and either the `result` or the `error`.
+    /// [default: false]
+    #[clap(long)]
+    pub json: bool,
+
     /// Enables verbose output in the terminal [default: false]
     #[clap(long)]
     pub verbose: bool,
@@ -100,11 +145,79 @@ pub struct CommandLineArgs {
     /// optimal match.
     #[clap(long = "search_window_radius", default_value_t = 60)]
     pub search_window_radius: usize,
+
+    /// Reads the variant, search window, model path and alignment settings
+    /// from a TOML or JSON file, see `VisqolConfig` of the `visqol-rs`
+    /// crate. The settings of the file replace the subcommand, `--mode`,
+    /// `--model` and `--search_window_radius`.
+    #[clap(long)]
+    pub config: Option<String>,
+
+    /// Limits the number of threads used to score a batch. By default, one
+    /// thread per core is used.
+    #[clap(long)]
+    pub threads: Option<usize>,
+}
+
+impl CommandLineArgs {
+    /// Returns `true` if the reference or degraded file is given as a glob pattern.
+    pub fn is_glob(&self) -> bool {
+        [&self.reference_file, &self.degraded_file]
+            .iter()
+            .filter_map(|path| path.as_deref())
+            .any(|path| path.contains(['*', '?', '[']))
+    }
+
+    /// Returns `true` if several file pairs are scored, given by a batch file or glob patterns.
+    pub fn is_batch(&self) -> bool { self.batch_input_csv.is_some() || self.is_glob() }
+
+    /// Returns the variant selected by the subcommand, or by `--mode` if no subcommand is given.
+    pub fn variant(&self) -> Variant {
+        match (&self.subcommand, self.mode) {
+            (
+                Some(Subcommands::Wideband {
+                    use_unscaled_speech_mos_mapping,
+                }),
+                _,
+            ) => Variant::Wideband {
+                use_unscaled_mos_mapping: *use_unscaled_speech_mos_mapping,
+            },
+            (
+                Some(Subcommands::Fullband {
+                    similarity_to_quality_model,
+                }),
+                _,
+            ) => Variant::Fullband {
+                model_path: similarity_to_quality_model.clone(),
+            },
+            (
+                Some(Subcommands::Narrowband {
+                    use_unscaled_speech_mos_mapping,
+                }),
+                _,
+            ) => Variant::Narrowband {
+                use_unscaled_mos_mapping: *use_unscaled_speech_mos_mapping,
+            },
+            (None, Mode::Speech) => Variant::Wideband {
+                use_unscaled_mos_mapping: false,
+            },
+            (None, Mode::Audio) => Variant::Fullband {
+                model_path: self.model.clone(),
+            },
+        }
+    }
 }
 
 pub fn build_file_pair_paths(args: &CommandLineArgs) -> Result<Vec<PathPair>, Box<dyn Error>> {
     let mut file_pairs = Vec::<PathPair>::new();
     if let (Some(ref_file), Some(deg_file)) = (&args.reference_file, &args.degraded_file) {
+        if args.is_glob() {
+            let pairs = visqol_manager::glob_pairs(ref_file, deg_file)?;
+            return Ok(pairs
+                .iter()
+                .map(|(reference, degraded)| PathPair::new(reference, degraded))
+                .collect());
+        }
         file_pairs.push(PathPair::new(ref_file, deg_file));
         Ok(file_pairs)
     } else if let Some(csv_file) = &args.batch_input_csv {
@@ -119,15 +232,12 @@ pub fn read_files_to_compare(batch_input_path: &PathBuf) -> Result<Vec<PathPair>
     let mut reader = ReaderBuilder::new()
         .has_headers(true)
         .delimiter(b',')
-        .from_path(batch_input_path)
-        .unwrap_or_else(|_| panic!("Failed to read csv file!"));
+        .from_path(batch_input_path)?;
 
     let header = StringRecord::from(vec!["reference", "degraded"]);
     while let Some(result) = reader.records().next() {
         let record = result?;
-        let row: PathPair = record
-            .deserialize(Some(&header))
-            .expect("Failed to deserialize line in csv file!");
+        let row: PathPair = record.deserialize(Some(&header))?;
         file_paths.push(row);
     }
     Ok(file_paths)
@@ -137,6 +247,7 @@ pub fn read_files_to_compare(batch_input_path: &PathBuf) -> Result<Vec<PathPair>
 mod tests {
 
     use super::*;
+    use crate::exit_code;
 
     #[test]
     fn file_pairs_are_constructed_correctly() {
@@ -162,4 +273,47 @@ mod tests {
         assert_eq!(file_pairs[1].reference, ref_file_2);
         assert_eq!(file_pairs[1].degraded, deg_file_2);
     }
+
+    #[test]
+    fn missing_batch_file_is_reported_as_failure() {
+        let result = read_files_to_compare(&PathBuf::from("does_not_exist.csv"));
+
+        let code = result.map_err(|error| exit_code::for_error(error.as_ref()));
+        assert!(matches!(code, Err(exit_code::FAILURE)));
+    }
+
+    #[test]
+    fn malformed_batch_file_is_reported_as_failure() {
+        let batch_path =
+            std::env::temp_dir().join(format!("{}_visqol_malformed_batch.csv", std::process::id()));
+        let contents = "reference,degraded\nref_1.wav,deg_1.wav\nref_2.wav\n";
+        std::fs::write(&batch_path, contents).unwrap();
+
+        let result = read_files_to_compare(&batch_path);
+        std::fs::remove_file(&batch_path).unwrap();
+
+        let code = result.map_err(|error| exit_code::for_error(error.as_ref()));
+        assert!(matches!(code, Err(exit_code::FAILURE)));
+    }
+
+    #[test]
+    fn glob_patterns_are_expanded_to_file_pairs() {
+        let data_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../visqol-rs/test_data/clean_speech");
+        let args = CommandLineArgs::parse_from([
+            "visqol",
+            "--reference",
+            &format!("{data_dir}/reference_signal_16k*.wav"),
+            "--degraded",
+            &format!("{data_dir}/degraded_signal_16k*.wav"),
+        ]);
+
+        let file_pairs = build_file_pair_paths(&args).unwrap();
+
+        assert!(args.is_batch());
+        assert_eq!(file_pairs.len(), 2);
+        assert!(file_pairs[0].reference.ends_with("reference_signal_16k.wav"));
+        assert!(file_pairs[0].degraded.ends_with("degraded_signal_16k.wav"));
+        assert!(file_pairs[1].reference.ends_with("reference_signal_16k_stereo.wav"));
+        assert!(file_pairs[1].degraded.ends_with("degraded_signal_16k_stereo.wav"));
+    }
 }
@@ -0,0 +1,86 @@
+use std::error::Error;
+
+use visqol_rs::visqol_error::VisqolError;
+
+/// Exit code for errors which are not caused by ViSQOL, e.g. failing to read a batch file.
+pub const FAILURE: u8 = 1;
+/// Exit code if an input file does not exist or cannot be read.
+pub const FILE_NOT_FOUND: u8 = 3;
+/// Exit code if an input file cannot be decoded.
+pub const DECODE_FAILED: u8 = 4;
+/// Exit code if the input signals are unsuitable for scoring, e.g. because their sample rates differ or they are too short.
+pub const INVALID_INPUT: u8 = 5;
+/// Exit code if the signals could not be aligned or compared.
+pub const SCORING_FAILED: u8 = 6;
+/// Exit code if ViSQOL is configured with invalid settings or an invalid model.
+pub const INVALID_CONFIGURATION: u8 = 7;
+
+/// Returns the exit code describing `error`. Exit code 2 is used by `clap` for invalid arguments.
+pub fn for_error(error: &(dyn Error + 'static)) -> u8 {
+    match error.downcast_ref::<VisqolError>() {
+        Some(VisqolError::FileNotFound { .. }) | Some(VisqolError::Io(_)) => FILE_NOT_FOUND,
+        Some(
+            VisqolError::InvalidBitsPerSample { .. }
+            | VisqolError::InvalidRawPcm { .. }
+            | VisqolError::DecodeFailed { .. }
+            | VisqolError::NoAudioTrack
+            | VisqolError::Wav(_),
+        ) => DECODE_FAILED,
+        Some(
+            VisqolError::DifferentSampleRates { .. }
+            | VisqolError::UnsupportedSampleRate { .. }
+            | VisqolError::SampleRateVariantMismatch { .. }
+            | VisqolError::DifferentChannelCounts { .. }
+            | VisqolError::NonFiniteSamples { .. }
+            | VisqolError::TooFewSamples { .. }
+            | VisqolError::EmptySignal { .. }
+            | VisqolError::ReferenceSpectrogramTooSmall { .. }
+            | VisqolError::SignalTooShort { .. }
+            | VisqolError::DurationMismatch { .. }
+            | VisqolError::GlobCountMismatch { .. },
+        ) => INVALID_INPUT,
+        Some(
+            VisqolError::SignalsTooDifferent
+            | VisqolError::FailedToAlignSignals
+            | VisqolError::FailedToComputeVad,
+        ) => SCORING_FAILED,
+        Some(
+            VisqolError::InvalidFrameSizes { .. }
+            | VisqolError::InvalidFrequencyRange { .. }
+            | VisqolError::InvalidDownmixWeights { .. }
+            | VisqolError::InvalidBandWeights { .. }
+            | VisqolError::InvalidNumBands { .. }
+            | VisqolError::InvalidPatchStride { .. }
+            | VisqolError::InvalidChunkSize { .. }
+            | VisqolError::UnsupportedInChunkedMode { .. }
+            | VisqolError::InvalidSegmentDuration { .. }
+            | VisqolError::InvalidTimeRange { .. }
+            | VisqolError::InvalidConfig { .. }
+            | VisqolError::InvalidGlobPattern { .. }
+            | VisqolError::InvalidModel { .. },
+        ) => INVALID_CONFIGURATION,
+        _ => FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visqol_errors_are_mapped_to_exit_codes() {
+        let missing_file = VisqolError::FileNotFound {
+            path: "missing.wav".to_string(),
+        };
+        let different_rates = VisqolError::DifferentSampleRates {
+            reference: 16000,
+            degraded: 48000,
+        };
+        let other_error = std::fmt::Error;
+
+        assert_eq!(for_error(&missing_file), FILE_NOT_FOUND);
+        assert_eq!(for_error(&different_rates), INVALID_INPUT);
+        assert_eq!(for_error(&VisqolError::SignalsTooDifferent), SCORING_FAILED);
+        assert_eq!(for_error(&other_error), FAILURE);
+    }
+}
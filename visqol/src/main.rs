@@ -2,71 +2,116 @@ use clap::Parser;
 use log::LevelFilter;
 use simplelog::{ColorChoice, Config, TermLogger, TerminalMode};
 use std::error::Error;
+use std::process::ExitCode;
 
 use visqol_rs::{
-    constants::{NUM_BANDS_AUDIO, NUM_BANDS_SPEECH},
+    constants::{NUM_BANDS_AUDIO, NUM_BANDS_NARROWBAND, NUM_BANDS_SPEECH},
     similarity_result::SimilarityResult,
     variant::Variant,
-    visqol_manager::VisqolManager,
+    visqol_config::VisqolConfig,
+    visqol_error::VisqolError,
+    visqol_manager::{DynamicVisqolManager, VisqolManager},
 };
 
 pub mod command_line_utils;
+pub mod exit_code;
 pub mod output_utils;
 pub mod path_pair;
 pub use crate::command_line_utils::{build_file_pair_paths, CommandLineArgs};
 use crate::path_pair::PathPair;
 
+/// Scores all `path_pairs` in parallel, see `VisqolManager::run_batch`.
 fn run<const NUM_BANDS: usize>(
-    path_pairs: &Vec<PathPair>,
-    visqol: &mut VisqolManager<NUM_BANDS>,
-) -> Result<Vec<SimilarityResult>, Box<dyn Error>> {
-    let mut results = Vec::<SimilarityResult>::with_capacity(path_pairs.len());
-    for file_pair in path_pairs {
-        let result = visqol.run(&file_pair.reference, &file_pair.degraded)?;
-        results.push(result);
+    path_pairs: &[PathPair],
+    visqol: &VisqolManager<NUM_BANDS>,
+) -> Vec<Result<SimilarityResult, VisqolError>> {
+    let pairs = path_pairs
+        .iter()
+        .map(|pair| (pair.reference.clone(), pair.degraded.clone()))
+        .collect::<Vec<(String, String)>>();
+    visqol.run_batch(&pairs)
+}
+
+fn main() -> ExitCode {
+    // Parse arguments
+    let args = CommandLineArgs::parse();
+
+    match score(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {error}");
+            ExitCode::from(exit_code::for_error(error.as_ref()))
+        }
     }
-    Ok(results)
 }
-fn main() -> Result<(), Box<dyn Error>> {
-    // Set up logger
+
+/// Scores all file pairs given by `args` and writes the results.
+fn score(args: &CommandLineArgs) -> Result<(), Box<dyn Error>> {
+    // Set up logger. Log messages would interleave with the JSON output on stdout.
+    let terminal_mode = if args.json {
+        TerminalMode::Stderr
+    } else {
+        TerminalMode::Stdout
+    };
     TermLogger::init(
         LevelFilter::Trace,
         Config::default(),
-        TerminalMode::Stdout,
+        terminal_mode,
         ColorChoice::Always,
     )?;
 
-    // Parse arguments
-    let args = CommandLineArgs::parse();
-
-    let files_to_compare = build_file_pair_paths(&args)?;
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()?;
+    }
 
-    let variant: Variant;
-    let mut visqol_speech: VisqolManager<NUM_BANDS_SPEECH>;
-    let mut visqol_audio: VisqolManager<NUM_BANDS_AUDIO>;
+    let files_to_compare = build_file_pair_paths(args)?;
 
-    let results: Vec<SimilarityResult>;
-    match &args.subcommand {
-        command_line_utils::Subcommands::Wideband {
-            use_unscaled_speech_mos_mapping,
-        } => {
-            variant = Variant::Wideband {
-                use_unscaled_mos_mapping: *use_unscaled_speech_mos_mapping,
-            };
-            visqol_speech = VisqolManager::new(variant, args.search_window_radius);
-            results = run(&files_to_compare, &mut visqol_speech)?;
-        }
-        command_line_utils::Subcommands::Fullband {
-            similarity_to_quality_model,
-        } => {
-            variant = Variant::Fullband {
-                model_path: similarity_to_quality_model.clone(),
-            };
-            visqol_audio = VisqolManager::new(variant, args.search_window_radius);
-            results = run(&files_to_compare, &mut visqol_audio)?;
+    let mut results = if let Some(config_path) = &args.config {
+        let visqol: DynamicVisqolManager =
+            VisqolConfig::from_file(config_path)?.builder().try_build()?;
+        run(&files_to_compare, &visqol)
+    } else {
+        match args.variant() {
+            variant @ Variant::Wideband { .. } => {
+                let visqol_speech = VisqolManager::<NUM_BANDS_SPEECH>::builder()
+                    .variant(variant)
+                    .search_window(args.search_window_radius)
+                    .try_build()?;
+                run(&files_to_compare, &visqol_speech)
+            }
+            variant @ Variant::Fullband { .. } => {
+                let visqol_audio = VisqolManager::<NUM_BANDS_AUDIO>::builder()
+                    .variant(variant)
+                    .search_window(args.search_window_radius)
+                    .try_build()?;
+                run(&files_to_compare, &visqol_audio)
+            }
+            variant @ Variant::Narrowband { .. } => {
+                let visqol_narrowband = VisqolManager::<NUM_BANDS_NARROWBAND>::builder()
+                    .variant(variant)
+                    .search_window(args.search_window_radius)
+                    .try_build()?;
+                run(&files_to_compare, &visqol_narrowband)
+            }
         }
+    };
+
+    // Without a batch, the error of the only file pair is reported directly.
+    if !args.is_batch() && matches!(results.first(), Some(Err(_))) {
+        return Err(results.remove(0).unwrap_err().into());
     }
 
-    output_utils::write_results(&args, &results, &files_to_compare);
+    output_utils::write_results(args, &results, &files_to_compare)?;
+
+    let num_failed = results.iter().filter(|result| result.is_err()).count();
+    if num_failed > 0 {
+        return Err(format!(
+            "{num_failed} of {} file pairs failed to be scored",
+            results.len()
+        )
+        .into());
+    }
     Ok(())
 }
@@ -1,32 +1,105 @@
+use std::error::Error;
+use std::io;
+
 use crate::{command_line_utils::CommandLineArgs, path_pair::PathPair};
-use visqol_rs::similarity_result::SimilarityResult;
+use visqol_rs::{similarity_result::SimilarityResult, visqol_error::VisqolError};
 
-use csv::WriterBuilder;
+use csv::{Writer, WriterBuilder};
 use prettytable::{
     format::{FormatBuilder, LinePosition, LineSeparator, TableFormat},
     Cell, Row, Table,
 };
+use serde::Serialize;
 use serde_json;
 
 /// Writes debug info to either console or to file.
+/// File pairs which failed to be scored are reported on stderr and in the results CSV.
 pub fn write_results(
     args: &CommandLineArgs,
-    results: &Vec<SimilarityResult>,
-    file_pairs: &Vec<PathPair>,
-) {
-    let version_number = env!("CARGO_PKG_VERSION");
-    println!("ViSQOL conformance version: {version_number:}");
+    results: &[Result<SimilarityResult, VisqolError>],
+    file_pairs: &[PathPair],
+) -> Result<(), Box<dyn Error>> {
+    let scored_results = results
+        .iter()
+        .filter_map(|result| result.as_ref().ok())
+        .collect::<Vec<&SimilarityResult>>();
+
+    for (result, file_pair) in results.iter().zip(file_pairs) {
+        if let Err(error) = result {
+            eprintln!(
+                "Failed to score {} against {}: {error}",
+                file_pair.degraded, file_pair.reference
+            );
+        }
+    }
+
+    if args.json {
+        println!("{}", results_json(args.is_batch(), results, file_pairs)?);
+    } else if args.is_batch() && args.results_csv.is_none() {
+        write_results_csv(WriterBuilder::new().from_writer(io::stdout()), results, file_pairs)?;
+    } else {
+        write_summary_to_console(args, results, file_pairs);
+    }
 
     if let Some(json_output_path) = &args.output_debug {
-        write_debug_json(json_output_path, results);
+        write_debug_json(json_output_path, &scored_results);
     }
 
     if let Some(csv_output_path) = &args.results_csv {
-        write_results_to_csv(csv_output_path, results);
+        let writer = WriterBuilder::new().from_path(csv_output_path)?;
+        write_results_csv(writer, results, file_pairs)?;
     }
+    Ok(())
+}
+
+/// Entry of the JSON array printed for a batch. Either `result` or `error` is set.
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    reference: &'a str,
+    degraded: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<&'a SimilarityResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Formats the results as JSON, a single object for a pair of files or an array with one entry per file pair for a batch.
+fn results_json(
+    is_batch: bool,
+    results: &[Result<SimilarityResult, VisqolError>],
+    file_pairs: &[PathPair],
+) -> Result<String, serde_json::Error> {
+    match results {
+        [Ok(result)] if !is_batch => serde_json::to_string_pretty(result),
+        _ => {
+            let entries = results
+                .iter()
+                .zip(file_pairs)
+                .map(|(result, file_pair)| JsonEntry {
+                    reference: &file_pair.reference,
+                    degraded: &file_pair.degraded,
+                    result: result.as_ref().ok(),
+                    error: result.as_ref().err().map(|error| error.to_string()),
+                })
+                .collect::<Vec<JsonEntry>>();
+            serde_json::to_string_pretty(&entries)
+        }
+    }
+}
+
+/// Prints the version and the MOS of each file pair which was scored successfully.
+fn write_summary_to_console(
+    args: &CommandLineArgs,
+    results: &[Result<SimilarityResult, VisqolError>],
+    file_pairs: &[PathPair],
+) {
+    let version_number = env!("CARGO_PKG_VERSION");
+    println!("ViSQOL conformance version: {version_number:}");
 
     for (sim_result, file_pair) in results.iter().zip(file_pairs) {
-        write_to_console(args, sim_result, file_pair);
+        if let Ok(sim_result) = sim_result {
+            write_to_console(args, sim_result, file_pair);
+        }
     }
 }
 
@@ -38,14 +111,24 @@ fn write_to_console(args: &CommandLineArgs, result: &SimilarityResult, file_pair
     }
 
     println!("MOS-LQO:\t\t{}", result.moslqo);
+    if !result.is_reliable {
+        eprintln!(
+            "Warning: the MOS of {} is unreliable, only {} patches were compared",
+            file_pair.degraded, result.num_patches
+        );
+    }
 
     if args.verbose {
+        println!(
+            "Patches compared:\t{} of {}",
+            result.num_patches, result.num_reference_patches
+        );
         write_fvnsim_table(result);
         write_patch_similarity(result);
     }
 }
 /// Writes json formatted debug information.
-fn write_debug_json(json_output_path: &String, results: &Vec<SimilarityResult>) {
+fn write_debug_json(json_output_path: &String, results: &[&SimilarityResult]) {
     let mut json_output = String::new();
     for result in results {
         json_output = serde_json::to_string_pretty(result).expect("Could not format JSON!");
@@ -54,19 +137,31 @@ fn write_debug_json(json_output_path: &String, results: &Vec<SimilarityResult>)
         .unwrap_or_else(|_| panic!("Could not write JSON to {}!", json_output_path.as_str()));
 }
 
-/// Writes computes MOS values to csv file.
-fn write_results_to_csv(csv_output_path: &String, results: &Vec<SimilarityResult>) {
-    let mut writer = WriterBuilder::new()
-        .has_headers(false)
-        .delimiter(b',')
-        .from_path(csv_output_path)
-        .expect("Failed to instantiate CSV writer!");
-    for result in results {
-        writer
-            .serialize(result)
-            .expect("Failed to serialize SimilarityResult!");
+/// Row of the results CSV. Either `moslqo` or `error` is set.
+#[derive(Serialize)]
+struct ResultRow<'a> {
+    reference: &'a str,
+    degraded: &'a str,
+    moslqo: Option<f64>,
+    error: Option<String>,
+}
+
+/// Writes the MOS or the error of each file pair as CSV to `writer`.
+fn write_results_csv<W: io::Write>(
+    mut writer: Writer<W>,
+    results: &[Result<SimilarityResult, VisqolError>],
+    file_pairs: &[PathPair],
+) -> Result<(), csv::Error> {
+    for (result, file_pair) in results.iter().zip(file_pairs) {
+        writer.serialize(ResultRow {
+            reference: &file_pair.reference,
+            degraded: &file_pair.degraded,
+            moslqo: result.as_ref().ok().map(|result| result.moslqo),
+            error: result.as_ref().err().map(|error| error.to_string()),
+        })?;
     }
-    writer.flush().expect("Failed to flush csv file!")
+    writer.flush()?;
+    Ok(())
 }
 
 /// Formats FVNSIM info to table and writes it to console.
@@ -132,3 +227,31 @@ fn get_default_table_format() -> TableFormat {
         .padding(1, 1)
         .build()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_json_holds_one_entry_per_file_pair() {
+        let file_pairs = [
+            PathPair::new("ref1.wav", "deg1.wav"),
+            PathPair::new("ref2.wav", "deg2.wav"),
+        ];
+        let results = [
+            Err(VisqolError::FileNotFound {
+                path: String::from("deg1.wav"),
+            }),
+            Err(VisqolError::SignalsTooDifferent),
+        ];
+
+        let json = results_json(true, &results, &file_pairs).unwrap();
+        let entries = serde_json::from_str::<Vec<serde_json::Value>>(&json).unwrap();
+
+        assert_eq!(entries.len(), file_pairs.len());
+        assert_eq!(entries[1]["reference"], "ref2.wav");
+        assert_eq!(entries[1]["degraded"], "deg2.wav");
+        assert_eq!(entries[0]["error"], results[0].as_ref().unwrap_err().to_string());
+        assert!(entries[0].get("result").is_none());
+    }
+}